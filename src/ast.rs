@@ -1,9 +1,32 @@
+use std::fmt;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Expr {
   NoOp,
   ValueExpr(u8),
   LastValueExpr,
+  /// `L-n` in the days field, `n` days before the last day of the month.<br/>
+  /// 日のフィールドの`L-n`。月の最終日の`n`日前。
+  LastDayOffsetExpr(u8),
   AnyValueExpr,
+  /// Quartz style `?`, which matches any value and marks the day field that is not specified.<br/>
+  /// Quartz形式の`?`。任意の値にマッチし、指定しない方の日のフィールドを表す。
+  NoSpecificValueExpr,
+  /// `nW` in the days field, the weekday nearest to day `n` within the same month.<br/>
+  /// 日のフィールドの`nW`。同じ月の中で`n`日に最も近い平日。
+  NearestWeekdayExpr(u8),
+  /// `LW` in the days field, the last weekday of the month.<br/>
+  /// 日のフィールドの`LW`。月の最後の平日。
+  LastWeekdayExpr,
+  /// `nL` in the day of weeks field, the last day of week `n` of the month.<br/>
+  /// 曜日のフィールドの`nL`。月の最後の曜日`n`。
+  LastDayOfWeekExpr(u8),
+  /// `n#k` in the day of weeks field, the `k`th day of week `n` of the month.<br/>
+  /// 曜日のフィールドの`n#k`。月の第`k`曜日`n`。
+  NthDayOfWeekExpr {
+    day_of_week: u8,
+    nth: u8,
+  },
   PerExpr {
     digit: Box<Expr>,
     option: Box<Expr>,
@@ -14,11 +37,322 @@ pub enum Expr {
     per_option: Box<Expr>,
   },
   ListExpr(Vec<Expr>),
+  /// Jenkins style `H`, `H/n` or `H(from-to)/n`, resolved to concrete values by `CronHashResolver`.<br/>
+  /// Jenkins形式の`H`。`CronHashResolver`によって具体的な値に解決される。
+  HashExpr {
+    range: Box<Expr>,
+    per_option: Box<Expr>,
+  },
+  /// A whole expression. `secs` and `years` are `NoOp` unless the dialect has those fields.<br/>
+  /// 式全体。`secs`と`years`は方言がそのフィールドを持たない場合`NoOp`となる。
   CronExpr {
+    secs: Box<Expr>,
     mins: Box<Expr>,
     hours: Box<Expr>,
     days: Box<Expr>,
     months: Box<Expr>,
     day_of_weeks: Box<Expr>,
+    years: Box<Expr>,
+    day_combination: DayCombination,
   },
+  /// AWS style `rate(value unit)`, which fires every `value` units counted from when the schedule starts.<br/>
+  /// AWS形式の`rate(value unit)`。スケジュールの開始時点から`value`単位ごとに実行する。
+  RateExpr {
+    value: u32,
+    unit: RateUnit,
+  },
+}
+
+/// The unit of a `RateExpr`.<br/>
+/// `RateExpr`の単位。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateUnit {
+  Minute,
+  Hour,
+  Day,
+}
+
+impl RateUnit {
+  /// Returns the length of the unit in seconds.<br/>
+  /// 単位の長さを秒で返す。
+  pub fn seconds(&self) -> i64 {
+    match self {
+      RateUnit::Minute => 60,
+      RateUnit::Hour => 60 * 60,
+      RateUnit::Day => 24 * 60 * 60,
+    }
+  }
+
+  /// Returns the name of the unit, e.g. `minute`.<br/>
+  /// `minute`などの単位の名前を返す。
+  pub fn name(&self) -> &'static str {
+    match self {
+      RateUnit::Minute => "minute",
+      RateUnit::Hour => "hour",
+      RateUnit::Day => "day",
+    }
+  }
+}
+
+/// How the days and day of weeks fields are combined.<br/>
+/// 日と曜日のフィールドの組み合わせ方。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DayCombination {
+  /// Both fields must match.<br/>
+  /// 両方のフィールドがマッチする必要がある。
+  #[default]
+  Both,
+  /// Either field may match, as Vixie cron does when both fields are restricted.<br/>
+  /// Vixie cronで両方のフィールドが制限されている場合のように、どちらかのフィールドがマッチすればよい。
+  Either,
+}
+
+/// The year that `ValueExpr(0)` stands for in the years field.<br/>
+/// 年のフィールドで`ValueExpr(0)`が表す年。
+///
+/// Years are stored as the offset from this year so that they fit in `ValueExpr`.<br/>
+/// 年は`ValueExpr`に収まるよう、この年からのオフセットとして保持する。
+pub const YEAR_BASE: u16 = 1970;
+
+/// A field of a CROND expression.<br/>
+/// CROND式のフィールド。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CronField {
+  Second,
+  Minute,
+  Hour,
+  Day,
+  Month,
+  DayOfWeek,
+  Year,
+}
+
+impl CronField {
+  /// Returns the smallest value of the field, which is also the origin of steps such as `*/2`.<br/>
+  /// フィールドの最小値を返す。`*/2`などの間隔指定の起点でもある。
+  pub fn min(&self) -> u8 {
+    match self {
+      CronField::Second | CronField::Minute | CronField::Hour | CronField::Year => 0,
+      CronField::Day | CronField::Month | CronField::DayOfWeek => 1,
+    }
+  }
+
+  /// Returns the largest value of the field. Years are offsets from `YEAR_BASE`, up to 2199.<br/>
+  /// フィールドの最大値を返す。年は`YEAR_BASE`からのオフセットで、2199年までとなる。
+  pub fn max(&self) -> u8 {
+    match self {
+      CronField::Second | CronField::Minute => 59,
+      CronField::Hour => 23,
+      CronField::Day => 31,
+      CronField::Month => 12,
+      CronField::DayOfWeek => 7,
+      CronField::Year => 229,
+    }
+  }
+
+  /// Returns the sorted values the expression matches in this field, or `None` if they depend on the date,
+  /// as `L` in the days field and `W`, `nL` and `n#k` do.<br/>
+  /// このフィールドで式がマッチする値を昇順で返す。日のフィールドの`L`や`W`、`nL`、`n#k`のように
+  /// 日付に依存する場合は`None`を返す。
+  pub(crate) fn values(&self, expr: &Expr) -> Option<Vec<u8>> {
+    let (min, max) = (self.min(), self.max());
+    let (values, step): (Vec<u8>, u8) = match expr {
+      Expr::NoOp | Expr::AnyValueExpr | Expr::NoSpecificValueExpr => ((min..=max).collect(), 1),
+      Expr::ValueExpr(n) => (vec![*n], 1),
+      Expr::LastValueExpr if *self != CronField::Day => (vec![max], 1),
+      Expr::RangeExpr {
+        from: box Expr::ValueExpr(from),
+        to: box Expr::ValueExpr(to),
+        per_option,
+      } => {
+        let step = match per_option {
+          box Expr::ValueExpr(step) => *step,
+          _ => 1,
+        };
+        if from <= to {
+          ((*from..=*to).collect(), step)
+        } else {
+          ((*from..=max).chain(min..=*to).collect(), step)
+        }
+      }
+      Expr::PerExpr {
+        digit: box Expr::ValueExpr(start),
+        option: box Expr::ValueExpr(step),
+      } => ((*start..=max).collect(), *step),
+      Expr::PerExpr {
+        digit: box Expr::AnyValueExpr,
+        option: box Expr::ValueExpr(step),
+      } => ((min..=max).collect(), *step),
+      Expr::ListExpr(list) => (
+        list
+          .iter()
+          .map(|e| self.values(e))
+          .collect::<Option<Vec<_>>>()?
+          .concat(),
+        1,
+      ),
+      _ => return None,
+    };
+    let mut values = values.into_iter().step_by(step as usize).collect::<Vec<_>>();
+    values.sort_unstable();
+    values.dedup();
+    Some(values)
+  }
+
+  /// Returns the name of the field.<br/>
+  /// フィールドの名前を返す。
+  pub fn name(&self) -> &'static str {
+    match self {
+      CronField::Second => "seconds",
+      CronField::Minute => "minutes",
+      CronField::Hour => "hours",
+      CronField::Day => "days",
+      CronField::Month => "months",
+      CronField::DayOfWeek => "day of weeks",
+      CronField::Year => "years",
+    }
+  }
+}
+
+/// Displays an expression of a field, writing a step starting at the field's minimum as `*/n` and years as
+/// calendar years.<br/>
+/// フィールドの式を表示する。フィールドの最小値から始まる間隔指定は`*/n`、年は西暦で表示する。
+pub(crate) struct FieldDisplay<'a>(pub(crate) CronField, pub(crate) &'a Expr);
+
+impl fmt::Display for FieldDisplay<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let field = self.0;
+    match self.1 {
+      Expr::PerExpr {
+        digit: box Expr::ValueExpr(start),
+        option,
+      } if *start == field.min() && field != CronField::Year => write!(f, "*/{}", option),
+      Expr::ValueExpr(n) if field == CronField::Year => write!(f, "{}", YEAR_BASE + *n as u16),
+      Expr::PerExpr { digit, option } => write!(f, "{}/{}", FieldDisplay(field, digit), option),
+      Expr::RangeExpr { from, to, per_option } => {
+        write!(f, "{}-{}", FieldDisplay(field, from), FieldDisplay(field, to))?;
+        if **per_option != Expr::NoOp {
+          write!(f, "/{}", per_option)?;
+        }
+        Ok(())
+      }
+      Expr::ListExpr(list) => {
+        let items = list
+          .iter()
+          .map(|e| FieldDisplay(field, e).to_string())
+          .collect::<Vec<_>>();
+        write!(f, "{}", items.join(","))
+      }
+      e => write!(f, "{}", e),
+    }
+  }
+}
+
+impl fmt::Display for Expr {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Expr::NoOp => Ok(()),
+      Expr::ValueExpr(n) => write!(f, "{}", n),
+      Expr::LastValueExpr => write!(f, "L"),
+      Expr::LastDayOffsetExpr(n) => write!(f, "L-{}", n),
+      Expr::AnyValueExpr => write!(f, "*"),
+      Expr::NoSpecificValueExpr => write!(f, "?"),
+      Expr::NearestWeekdayExpr(n) => write!(f, "{}W", n),
+      Expr::LastWeekdayExpr => write!(f, "LW"),
+      Expr::LastDayOfWeekExpr(n) => write!(f, "{}L", n),
+      Expr::NthDayOfWeekExpr { day_of_week, nth } => write!(f, "{}#{}", day_of_week, nth),
+      Expr::RateExpr { value, unit } => {
+        write!(
+          f,
+          "rate({} {}{})",
+          value,
+          unit.name(),
+          if *value == 1 { "" } else { "s" }
+        )
+      }
+      Expr::PerExpr { digit, option } => write!(f, "{}/{}", digit, option),
+      Expr::RangeExpr { from, to, per_option } => match per_option {
+        box Expr::NoOp => write!(f, "{}-{}", from, to),
+        per => write!(f, "{}-{}/{}", from, to, per),
+      },
+      Expr::ListExpr(list) => {
+        let items = list.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        write!(f, "{}", items.join(","))
+      }
+      Expr::HashExpr { range, per_option } => {
+        write!(f, "H")?;
+        if **range != Expr::NoOp {
+          write!(f, "({})", range)?;
+        }
+        if **per_option != Expr::NoOp {
+          write!(f, "/{}", per_option)?;
+        }
+        Ok(())
+      }
+      Expr::CronExpr {
+        secs,
+        mins,
+        hours,
+        days,
+        months,
+        day_of_weeks,
+        years,
+        ..
+      } => {
+        if **secs != Expr::NoOp {
+          write!(f, "{} ", FieldDisplay(CronField::Second, secs))?;
+        }
+        write!(
+          f,
+          "{} {} {} {} {}",
+          FieldDisplay(CronField::Minute, mins),
+          FieldDisplay(CronField::Hour, hours),
+          FieldDisplay(CronField::Day, days),
+          FieldDisplay(CronField::Month, months),
+          FieldDisplay(CronField::DayOfWeek, day_of_weeks)
+        )?;
+        if **years != Expr::NoOp {
+          write!(f, " {}", FieldDisplay(CronField::Year, years))?;
+        }
+        Ok(())
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{CronField, CronParser, Expr};
+
+  #[test]
+  fn test_display() {
+    for s in ["* * * * *", "*/5 0-23/2 1,15 1-6 2-6", "0 0 1 1 L", "H H(0-5)/2 * * *"] {
+      let expr = CronParser::parse(s).to_result().unwrap();
+      assert_eq!(expr.to_string(), s);
+    }
+  }
+
+  #[test]
+  fn test_values() {
+    let values = |field: CronField, s: &str| {
+      let expr = CronParser::parse(&format!("{} * * * *", s)).to_result().unwrap();
+      match expr {
+        Expr::CronExpr { mins, .. } => field.values(&mins),
+        _ => unreachable!(),
+      }
+    };
+    assert_eq!(values(CronField::Minute, "5,1-3"), Some(vec![1, 2, 3, 5]));
+    assert_eq!(values(CronField::Minute, "50-5/5"), Some(vec![0, 5, 50, 55]));
+    assert_eq!(values(CronField::Minute, "*/20"), Some(vec![0, 20, 40]));
+    assert_eq!(CronField::Minute.values(&Expr::LastValueExpr), Some(vec![59]));
+    assert_eq!(CronField::Day.values(&Expr::LastValueExpr), None);
+  }
+
+  #[test]
+  fn test_display_step_from_minimum() {
+    let expr = CronParser::parse("0/5 0/2 1/3 1/4,6 1/2").to_result().unwrap();
+    assert_eq!(expr.to_string(), "*/5 */2 */3 */4,6 */2");
+    let expr = CronParser::parse("1/5 1/2 2/3 2/4 2/2").to_result().unwrap();
+    assert_eq!(expr.to_string(), "1/5 1/2 2/3 2/4 2/2");
+  }
 }
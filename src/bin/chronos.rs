@@ -0,0 +1,422 @@
+//! Command line interface for inspecting cron expressions.<br/>
+//! cron式を調べるためのコマンドラインインターフェイス。
+use std::process::ExitCode;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
+use chronos_parser_rs::{describe, CronInterval, CronSchedule, Dialect, English, Japanese, Specification};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use intervals_rs::LimitValue;
+use serde_json::json;
+
+#[derive(Debug, Parser)]
+#[command(name = "chronos", version, about = "Inspect cron expressions")]
+struct Cli {
+  /// Print the result as JSON for scripting
+  #[arg(long, global = true)]
+  json: bool,
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+  /// Print the next occurrences
+  Next {
+    #[command(flatten)]
+    schedule: ScheduleArgs,
+    /// The number of occurrences to print
+    #[arg(short = 'n', long = "number", default_value_t = 10)]
+    number: usize,
+    /// The date and time to start from, now if omitted
+    #[arg(long)]
+    from: Option<String>,
+  },
+  /// Print the previous occurrences, latest first
+  Prev {
+    #[command(flatten)]
+    schedule: ScheduleArgs,
+    /// The number of occurrences to print
+    #[arg(short = 'n', long = "number", default_value_t = 10)]
+    number: usize,
+    /// The date and time to look back from, now if omitted
+    #[arg(long)]
+    from: Option<String>,
+  },
+  /// Check that an expression parses
+  Validate {
+    #[command(flatten)]
+    expr: ExprArgs,
+  },
+  /// Describe an expression in natural language
+  Explain {
+    #[command(flatten)]
+    expr: ExprArgs,
+    /// The language of the description
+    #[arg(long, value_enum, default_value_t = Lang::En)]
+    lang: Lang,
+  },
+  /// Print the occurrences in [from, to)
+  Between {
+    #[command(flatten)]
+    schedule: ScheduleArgs,
+    from: String,
+    to: String,
+  },
+  /// Count the occurrences in [from, to)
+  Count {
+    #[command(flatten)]
+    schedule: ScheduleArgs,
+    from: String,
+    to: String,
+  },
+}
+
+#[derive(Debug, Args)]
+struct ExprArgs {
+  /// The cron expression, quoted
+  expr: String,
+  /// The syntax the expression is written in
+  #[arg(long, value_enum, default_value_t = DialectArg::Native)]
+  dialect: DialectArg,
+}
+
+#[derive(Debug, Args)]
+struct ScheduleArgs {
+  #[command(flatten)]
+  expr: ExprArgs,
+  /// The IANA timezone the expression is evaluated in
+  #[arg(long, default_value = "UTC")]
+  tz: Tz,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum DialectArg {
+  Native,
+  Vixie,
+  Kubernetes,
+  Quartz,
+  Spring,
+  Aws,
+}
+
+impl From<DialectArg> for Dialect {
+  fn from(value: DialectArg) -> Self {
+    match value {
+      DialectArg::Native => Dialect::Native,
+      DialectArg::Vixie => Dialect::Vixie,
+      DialectArg::Kubernetes => Dialect::Kubernetes,
+      DialectArg::Quartz => Dialect::Quartz,
+      DialectArg::Spring => Dialect::Spring,
+      DialectArg::Aws => Dialect::Aws,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Lang {
+  En,
+  Ja,
+}
+
+/// The text to print and whether the command succeeded.
+struct Output {
+  body: String,
+  success: bool,
+}
+
+impl Output {
+  fn success(body: String) -> Self {
+    Self { body, success: true }
+  }
+}
+
+impl ExprArgs {
+  fn schedule<Z: TimeZone>(&self) -> Result<CronSchedule<Z>> {
+    CronSchedule::with_dialect(self.dialect.into(), &self.expr)
+  }
+}
+
+/// Parses RFC 3339, or a local date and time such as `2021-01-01 09:00` in the timezone.
+fn parse_date_time(s: &str, tz: &Tz) -> Result<DateTime<Tz>> {
+  if let Ok(date_time) = DateTime::parse_from_rfc3339(s) {
+    return Ok(date_time.with_timezone(tz));
+  }
+  let naive = [
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+  ]
+  .iter()
+  .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+  .or_else(|| {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+      .ok()
+      .and_then(|date| date.and_hms_opt(0, 0, 0))
+  })
+  .ok_or_else(|| anyhow!("Failed to parse date and time: {}", s))?;
+  tz.from_local_datetime(&naive)
+    .earliest()
+    .ok_or_else(|| anyhow!("Failed to parse date and time: {} does not exist in {}", s, tz))
+}
+
+/// Rounds up to the granularity, so that occurrences are printed on whole minutes or seconds.
+fn align_up(date_time: DateTime<Tz>, schedule: &CronSchedule<Tz>) -> DateTime<Tz> {
+  let granularity = Specification::<DateTime<Tz>>::granularity(&schedule.specification());
+  let step = granularity.num_milliseconds();
+  let remainder = date_time.timestamp_millis().rem_euclid(step);
+  if remainder == 0 {
+    date_time
+  } else {
+    date_time + Duration::milliseconds(step - remainder)
+  }
+}
+
+fn from_or_now(from: &Option<String>, tz: &Tz) -> Result<DateTime<Tz>> {
+  match from {
+    Some(from) => parse_date_time(from, tz),
+    None => Ok(Utc::now().with_timezone(tz)),
+  }
+}
+
+fn format(date_time: &DateTime<Tz>) -> String {
+  date_time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn occurrences(json: bool, args: &ScheduleArgs, date_times: Vec<DateTime<Tz>>) -> Output {
+  let date_times = date_times.iter().map(format).collect::<Vec<_>>();
+  if json {
+    Output::success(
+      json!({
+        "expression": args.expr.expr,
+        "timezone": args.tz.name(),
+        "occurrences": date_times,
+      })
+      .to_string(),
+    )
+  } else {
+    Output::success(date_times.join("\n"))
+  }
+}
+
+fn between(args: &ScheduleArgs, from: &str, to: &str) -> Result<Vec<DateTime<Tz>>> {
+  let schedule = args.expr.schedule::<Tz>()?;
+  let from = parse_date_time(from, &args.tz)?;
+  let to = parse_date_time(to, &args.tz)?;
+  let from = align_up(from, &schedule);
+  if to <= from {
+    return Ok(vec![]);
+  }
+  let spec = schedule.specification().anchored_at(&from);
+  let interval = CronInterval::new(LimitValue::Limit(from), LimitValue::Limit(to), spec);
+  Ok(interval.iter(args.tz).filter(|date_time| *date_time < to).collect())
+}
+
+fn run(cli: &Cli) -> Result<Output> {
+  let json = cli.json;
+  match &cli.command {
+    Command::Next { schedule, number, from } => {
+      let cron_schedule = schedule.expr.schedule::<Tz>()?;
+      let from = from_or_now(from, &schedule.tz)?;
+      let from = align_up(from, &cron_schedule);
+      // `next_after` ends for schedules with no further occurrence, where `upcoming` would search forever.
+      let first = match cron_schedule.specification().anchored_at(&from).is_satisfied_by(&from) {
+        true => Some(from),
+        false => cron_schedule.next_after(from),
+      };
+      let date_times = std::iter::successors(first, |d| cron_schedule.next_after(*d))
+        .take(*number)
+        .collect();
+      Ok(occurrences(json, schedule, date_times))
+    }
+    Command::Prev { schedule, number, from } => {
+      let cron_schedule = schedule.expr.schedule::<Tz>()?;
+      let from = from_or_now(from, &schedule.tz)?;
+      let date_times = std::iter::successors(cron_schedule.previous_before(from), |d| {
+        cron_schedule.previous_before(*d)
+      })
+      .take(*number)
+      .collect();
+      Ok(occurrences(json, schedule, date_times))
+    }
+    Command::Validate { expr } => {
+      let result = expr.schedule::<Utc>();
+      let body = match (&result, json) {
+        (Ok(schedule), true) => json!({
+          "expression": expr.expr,
+          "valid": true,
+          "normalized": schedule.expr().to_string(),
+        })
+        .to_string(),
+        (Err(e), true) => json!({
+          "expression": expr.expr,
+          "valid": false,
+          "error": e.to_string(),
+        })
+        .to_string(),
+        (Ok(schedule), false) => format!("valid: {}", schedule.expr()),
+        (Err(e), false) => format!("invalid: {}", e),
+      };
+      Ok(Output {
+        body,
+        success: result.is_ok(),
+      })
+    }
+    Command::Explain { expr, lang } => {
+      let schedule = expr.schedule::<Utc>()?;
+      let description = match lang {
+        Lang::En => describe(schedule.expr(), &English),
+        Lang::Ja => describe(schedule.expr(), &Japanese),
+      };
+      let description = if description.is_empty() {
+        schedule.expr().to_string()
+      } else {
+        description
+      };
+      if json {
+        Ok(Output::success(
+          json!({
+            "expression": expr.expr,
+            "normalized": schedule.expr().to_string(),
+            "description": description,
+          })
+          .to_string(),
+        ))
+      } else {
+        Ok(Output::success(description))
+      }
+    }
+    Command::Between { schedule, from, to } => Ok(occurrences(json, schedule, between(schedule, from, to)?)),
+    Command::Count { schedule, from, to } => {
+      let cron_schedule = schedule.expr.schedule::<Tz>()?;
+      let start = align_up(parse_date_time(from, &schedule.tz)?, &cron_schedule);
+      let count = cron_schedule.count_between(start, parse_date_time(to, &schedule.tz)?);
+      if json {
+        Ok(Output::success(
+          json!({
+            "expression": schedule.expr.expr,
+            "timezone": schedule.tz.name(),
+            "from": from,
+            "to": to,
+            "count": count,
+          })
+          .to_string(),
+        ))
+      } else {
+        Ok(Output::success(count.to_string()))
+      }
+    }
+  }
+}
+
+fn main() -> ExitCode {
+  let cli = Cli::parse();
+  match run(&cli) {
+    Ok(output) => {
+      println!("{}", output.body);
+      if output.success {
+        ExitCode::SUCCESS
+      } else {
+        ExitCode::FAILURE
+      }
+    }
+    Err(e) if cli.json => {
+      println!("{}", json!({ "error": e.to_string() }));
+      ExitCode::FAILURE
+    }
+    Err(e) => {
+      eprintln!("error: {}", e);
+      ExitCode::FAILURE
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::Value;
+
+  fn run_args(args: &[&str]) -> Output {
+    let cli = Cli::try_parse_from(std::iter::once("chronos").chain(args.iter().copied())).unwrap();
+    run(&cli).unwrap()
+  }
+
+  #[test]
+  fn test_next_and_prev() {
+    let output = run_args(&["next", "0 9 * * *", "-n", "2", "--from", "2021-01-01T10:00:00Z"]);
+    assert_eq!(output.body, "2021-01-02T09:00:00Z\n2021-01-03T09:00:00Z");
+
+    let output = run_args(&[
+      "next",
+      "0 9 * * *",
+      "-n",
+      "1",
+      "--tz",
+      "Asia/Tokyo",
+      "--from",
+      "2021-01-01 08:59:30",
+    ]);
+    assert_eq!(output.body, "2021-01-01T09:00:00+09:00");
+
+    let output = run_args(&[
+      "next",
+      "0 0 0 1 1 ? 2020-2022",
+      "--dialect",
+      "quartz",
+      "--from",
+      "2021-01-01",
+    ]);
+    assert_eq!(output.body, "2021-01-01T00:00:00Z\n2022-01-01T00:00:00Z");
+    let output = run_args(&["next", "0 0 30 2 *", "--from", "2021-01-01"]);
+    assert_eq!(output.body, "");
+
+    let output = run_args(&["prev", "0 9 * * *", "-n", "2", "--from", "2021-01-02T09:00:00Z"]);
+    assert_eq!(output.body, "2021-01-01T09:00:00Z\n2020-12-31T09:00:00Z");
+    let output = run_args(&["prev", "0 0 30 2 *", "-n", "1", "--from", "2021-01-01"]);
+    assert_eq!(output.body, "");
+  }
+
+  #[test]
+  fn test_between_and_count() {
+    let output = run_args(&["between", "0 */6 * * *", "2021-01-01", "2021-01-02"]);
+    assert_eq!(
+      output.body,
+      "2021-01-01T00:00:00Z\n2021-01-01T06:00:00Z\n2021-01-01T12:00:00Z\n2021-01-01T18:00:00Z"
+    );
+    let output = run_args(&["between", "0 */6 * * *", "2021-01-02", "2021-01-01"]);
+    assert_eq!(output.body, "");
+    let output = run_args(&["count", "*/15 * * * *", "2021-01-01", "2021-01-02"]);
+    assert_eq!(output.body, "96");
+    let output = run_args(&["--json", "count", "*/15 * * * *", "2021-01-01", "2021-01-02"]);
+    let value: Value = serde_json::from_str(&output.body).unwrap();
+    assert_eq!(value["count"], 96);
+  }
+
+  #[test]
+  fn test_validate_and_explain() {
+    let output = run_args(&["validate", "0 9 * * MON-FRI", "--dialect", "vixie"]);
+    assert!(output.success);
+    assert_eq!(output.body, "valid: 0 9 * * 2-6");
+
+    let output = run_args(&["--json", "validate", "61 * * * *"]);
+    assert!(!output.success);
+    let value: Value = serde_json::from_str(&output.body).unwrap();
+    assert_eq!(value["valid"], false);
+
+    let output = run_args(&["explain", "0 9 * * *"]);
+    assert_eq!(output.body, "At 09:00");
+    let output = run_args(&[
+      "--json",
+      "next",
+      "0 9 * * *",
+      "-n",
+      "1",
+      "--from",
+      "2021-01-01T00:00:00Z",
+    ]);
+    let value: Value = serde_json::from_str(&output.body).unwrap();
+    assert_eq!(value["occurrences"][0], "2021-01-01T09:00:00Z");
+  }
+}
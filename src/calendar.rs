@@ -0,0 +1,279 @@
+use std::collections::BTreeSet;
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+
+use crate::{CronEvaluator, Expr, Specification, SpecificationExt};
+
+/// A calendar that excludes date and time ranges from a schedule, like Quartz's `Calendar`.<br/>
+/// QuartzのCalendarのように、スケジュールから日時を除外するカレンダー。
+///
+/// Calendars work on the local date and time of the instant being tested.<br/>
+/// カレンダーは判定対象の日時のローカル日時に対して働く。
+pub trait Calendar {
+  /// Returns whether the local date and time is not excluded by this calendar.<br/>
+  /// ローカル日時がこのカレンダーで除外されていないかを返す。
+  fn is_time_included(&self, date_time: &NaiveDateTime) -> bool;
+
+  /// Returns a specification satisfied by the date and times included in this calendar.<br/>
+  /// このカレンダーに含まれる日時で満たされる仕様を返す。
+  fn to_specification(self) -> CalendarSpecification<Self>
+  where
+    Self: Sized + Clone, {
+    CalendarSpecification::new(self)
+  }
+}
+
+/// A specification backed by a Calendar.<br/>
+/// Calendarに基づく仕様。
+#[derive(Debug, Clone)]
+pub struct CalendarSpecification<C> {
+  calendar: C,
+}
+
+impl<C> CalendarSpecification<C> {
+  pub fn new(calendar: C) -> Self {
+    Self { calendar }
+  }
+}
+
+impl<C> SpecificationExt for CalendarSpecification<C> {}
+
+impl<Tz: TimeZone, C: Calendar + Clone> Specification<DateTime<Tz>> for CalendarSpecification<C> {
+  fn is_satisfied_by(&self, date_time: &DateTime<Tz>) -> bool {
+    self.calendar.is_time_included(&date_time.naive_local())
+  }
+}
+
+/// A calendar that excludes a fixed set of dates.<br/>
+/// 固定の日付の集合を除外するカレンダー。
+#[derive(Debug, Clone, Default)]
+pub struct HolidayCalendar {
+  excluded_dates: BTreeSet<NaiveDate>,
+}
+
+impl HolidayCalendar {
+  pub fn new(excluded_dates: impl IntoIterator<Item = NaiveDate>) -> Self {
+    Self {
+      excluded_dates: excluded_dates.into_iter().collect(),
+    }
+  }
+
+  /// Adds a date to exclude.<br/>
+  /// 除外する日付を追加する。
+  pub fn add_excluded_date(&mut self, date: NaiveDate) {
+    self.excluded_dates.insert(date);
+  }
+
+  /// Returns the excluded dates.<br/>
+  /// 除外する日付を返す。
+  pub fn excluded_dates(&self) -> &BTreeSet<NaiveDate> {
+    &self.excluded_dates
+  }
+}
+
+impl Calendar for HolidayCalendar {
+  fn is_time_included(&self, date_time: &NaiveDateTime) -> bool {
+    !self.excluded_dates.contains(&date_time.date())
+  }
+}
+
+/// A calendar that excludes the same month and day every year.<br/>
+/// 毎年同じ月日を除外するカレンダー。
+#[derive(Debug, Clone, Default)]
+pub struct AnnualCalendar {
+  excluded_days: BTreeSet<(u32, u32)>,
+}
+
+impl AnnualCalendar {
+  /// The Factory method.<br/>
+  /// ファクトリメソッド。
+  ///
+  /// # Arguments(引数)
+  ///
+  /// * excluded_days - Pairs of month and day to exclude(除外する月と日の組)
+  pub fn new(excluded_days: impl IntoIterator<Item = (u32, u32)>) -> Self {
+    Self {
+      excluded_days: excluded_days.into_iter().collect(),
+    }
+  }
+
+  /// Adds a month and day to exclude.<br/>
+  /// 除外する月日を追加する。
+  pub fn add_excluded_day(&mut self, month: u32, day: u32) {
+    self.excluded_days.insert((month, day));
+  }
+}
+
+impl Calendar for AnnualCalendar {
+  fn is_time_included(&self, date_time: &NaiveDateTime) -> bool {
+    !self.excluded_days.contains(&(date_time.month(), date_time.day()))
+  }
+}
+
+/// A calendar that excludes days of the week.<br/>
+/// 曜日を除外するカレンダー。
+#[derive(Debug, Clone, Default)]
+pub struct WeeklyCalendar {
+  excluded_days: [bool; 7],
+}
+
+impl WeeklyCalendar {
+  pub fn new(excluded_days: impl IntoIterator<Item = Weekday>) -> Self {
+    let mut result = Self::default();
+    for weekday in excluded_days {
+      result.excluded_days[weekday.num_days_from_sunday() as usize] = true;
+    }
+    result
+  }
+
+  /// Returns a WeeklyCalendar that excludes Saturday and Sunday.<br/>
+  /// 土曜日と日曜日を除外するWeeklyCalendarを返す。
+  pub fn weekends() -> Self {
+    Self::new([Weekday::Sat, Weekday::Sun])
+  }
+}
+
+impl Calendar for WeeklyCalendar {
+  fn is_time_included(&self, date_time: &NaiveDateTime) -> bool {
+    !self.excluded_days[date_time.weekday().num_days_from_sunday() as usize]
+  }
+}
+
+/// A calendar that excludes a time window of every day.<br/>
+/// 毎日の時間帯を除外するカレンダー。
+///
+/// The window is `[start, end)` and crosses midnight when `start > end`.<br/>
+/// 時間帯は`[start, end)`で、`start > end`の場合は日付を跨ぐ。
+#[derive(Debug, Clone)]
+pub struct DailyCalendar {
+  start: NaiveTime,
+  end: NaiveTime,
+  inverted: bool,
+}
+
+impl DailyCalendar {
+  pub fn new(start: NaiveTime, end: NaiveTime) -> Self {
+    Self {
+      start,
+      end,
+      inverted: false,
+    }
+  }
+
+  /// Returns a DailyCalendar that excludes everything except the time window.<br/>
+  /// 時間帯以外を除外するDailyCalendarを返す。
+  pub fn inverted(self) -> Self {
+    Self {
+      inverted: !self.inverted,
+      ..self
+    }
+  }
+
+  fn in_window(&self, time: NaiveTime) -> bool {
+    if self.start <= self.end {
+      self.start <= time && time < self.end
+    } else {
+      self.start <= time || time < self.end
+    }
+  }
+}
+
+impl Calendar for DailyCalendar {
+  fn is_time_included(&self, date_time: &NaiveDateTime) -> bool {
+    self.in_window(date_time.time()) == self.inverted
+  }
+}
+
+/// A calendar that excludes the date and times matching a CROND expression.<br/>
+/// CROND式にマッチする日時を除外するカレンダー。
+#[derive(Debug, Clone)]
+pub struct CronCalendar {
+  expr: Expr,
+}
+
+impl CronCalendar {
+  pub fn new(expr: Expr) -> Self {
+    Self { expr }
+  }
+}
+
+impl Calendar for CronCalendar {
+  fn is_time_included(&self, date_time: &NaiveDateTime) -> bool {
+    let date_time = Utc.from_utc_datetime(date_time);
+    !CronEvaluator::new(&date_time).eval(&self.expr)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{CronParser, CronSchedule};
+
+  fn date_time(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(y, m, d)
+      .unwrap()
+      .and_hms_opt(h, min, 0)
+      .unwrap()
+  }
+
+  #[test]
+  fn test_holiday_calendar() {
+    let calendar = HolidayCalendar::new([NaiveDate::from_ymd_opt(2021, 1, 1).unwrap()]);
+    assert!(!calendar.is_time_included(&date_time(2021, 1, 1, 12, 0)));
+    assert!(calendar.is_time_included(&date_time(2022, 1, 1, 12, 0)));
+  }
+
+  #[test]
+  fn test_annual_calendar() {
+    let calendar = AnnualCalendar::new([(12, 25)]);
+    assert!(!calendar.is_time_included(&date_time(2021, 12, 25, 0, 0)));
+    assert!(!calendar.is_time_included(&date_time(2030, 12, 25, 23, 59)));
+    assert!(calendar.is_time_included(&date_time(2021, 12, 24, 0, 0)));
+  }
+
+  #[test]
+  fn test_weekly_calendar() {
+    let calendar = WeeklyCalendar::weekends();
+    // 2021-01-02 is a Saturday
+    assert!(!calendar.is_time_included(&date_time(2021, 1, 2, 0, 0)));
+    assert!(!calendar.is_time_included(&date_time(2021, 1, 3, 0, 0)));
+    assert!(calendar.is_time_included(&date_time(2021, 1, 4, 0, 0)));
+  }
+
+  #[test]
+  fn test_daily_calendar() {
+    let calendar = DailyCalendar::new(
+      NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+      NaiveTime::from_hms_opt(6, 0, 0).unwrap(),
+    );
+    assert!(!calendar.is_time_included(&date_time(2021, 1, 1, 23, 0)));
+    assert!(!calendar.is_time_included(&date_time(2021, 1, 1, 5, 59)));
+    assert!(calendar.is_time_included(&date_time(2021, 1, 1, 6, 0)));
+    let calendar = calendar.inverted();
+    assert!(calendar.is_time_included(&date_time(2021, 1, 1, 23, 0)));
+    assert!(!calendar.is_time_included(&date_time(2021, 1, 1, 12, 0)));
+  }
+
+  #[test]
+  fn test_cron_calendar() {
+    let calendar = CronCalendar::new(CronParser::parse("* 12 * * *").to_result().unwrap());
+    assert!(!calendar.is_time_included(&date_time(2021, 1, 1, 12, 30)));
+    assert!(calendar.is_time_included(&date_time(2021, 1, 1, 13, 0)));
+  }
+
+  #[test]
+  fn test_upcoming_filtered() {
+    let start = Utc.with_ymd_and_hms(2020, 12, 31, 0, 0, 0).unwrap();
+    let calendar = WeeklyCalendar::weekends()
+      .to_specification()
+      .and(HolidayCalendar::new([NaiveDate::from_ymd_opt(2021, 1, 1).unwrap()]).to_specification());
+    let dt_vec = CronSchedule::new("0 9 * * *")
+      .unwrap()
+      .upcoming_filtered(start, calendar)
+      .take(3)
+      .collect::<Vec<_>>();
+    assert_eq!(dt_vec[0], Utc.with_ymd_and_hms(2020, 12, 31, 9, 0, 0).unwrap());
+    assert_eq!(dt_vec[1], Utc.with_ymd_and_hms(2021, 1, 4, 9, 0, 0).unwrap());
+    assert_eq!(dt_vec[2], Utc.with_ymd_and_hms(2021, 1, 5, 9, 0, 0).unwrap());
+  }
+}
@@ -0,0 +1,100 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+/// A source of the current date and time, so that code depending on it can be tested without waiting.<br/>
+/// 現在日時の取得元。これに依存するコードを待たずにテストできる。
+pub trait Clock<Tz: TimeZone> {
+  /// Returns the current date and time.<br/>
+  /// 現在日時を返す。
+  fn now(&self) -> DateTime<Tz>;
+}
+
+/// A clock that reads the system time in a timezone.<br/>
+/// タイムゾーンにおけるシステム時刻を読む時計。
+#[derive(Debug, Clone)]
+pub struct SystemClock<Tz: TimeZone> {
+  timezone: Tz,
+}
+
+impl<Tz: TimeZone> SystemClock<Tz> {
+  /// The factory method.<br/>
+  /// ファクトリメソッド。
+  pub fn new(timezone: Tz) -> Self {
+    Self { timezone }
+  }
+}
+
+impl Default for SystemClock<Utc> {
+  fn default() -> Self {
+    Self::new(Utc)
+  }
+}
+
+impl<Tz: TimeZone> Clock<Tz> for SystemClock<Tz> {
+  fn now(&self) -> DateTime<Tz> {
+    Utc::now().with_timezone(&self.timezone)
+  }
+}
+
+/// A clock that stands still until it is set or advanced. Clones share the same time, so a test can keep one and
+/// hand another to the code under test.<br/>
+/// 設定するか進めるまで止まっている時計。クローンは同じ時刻を共有するため、テストは一方を持ち、
+/// もう一方をテスト対象のコードに渡せる。
+#[derive(Debug, Clone)]
+pub struct ManualClock<Tz: TimeZone> {
+  now: Arc<Mutex<DateTime<Tz>>>,
+}
+
+impl<Tz: TimeZone> ManualClock<Tz> {
+  /// The factory method, starting at the given date and time.<br/>
+  /// 与えた日時から始まるファクトリメソッド。
+  pub fn new(start: DateTime<Tz>) -> Self {
+    Self {
+      now: Arc::new(Mutex::new(start)),
+    }
+  }
+
+  /// Sets the current date and time.<br/>
+  /// 現在日時を設定する。
+  pub fn set(&self, date_time: DateTime<Tz>) {
+    *self.now.lock().unwrap_or_else(|e| e.into_inner()) = date_time;
+  }
+
+  /// Advances the current date and time by the duration.<br/>
+  /// 現在日時を期間だけ進める。
+  pub fn advance(&self, duration: Duration) {
+    let mut now = self.now.lock().unwrap_or_else(|e| e.into_inner());
+    *now = now.clone() + duration;
+  }
+}
+
+impl<Tz: TimeZone> Clock<Tz> for ManualClock<Tz> {
+  fn now(&self) -> DateTime<Tz> {
+    self.now.lock().unwrap_or_else(|e| e.into_inner()).clone()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono_tz::Asia::Tokyo;
+
+  #[test]
+  fn test_manual_clock() {
+    let clock = ManualClock::new(Tokyo.with_ymd_and_hms(2021, 1, 1, 9, 0, 0).unwrap());
+    let shared = clock.clone();
+    clock.advance(Duration::days(3));
+    assert_eq!(shared.now(), Tokyo.with_ymd_and_hms(2021, 1, 4, 9, 0, 0).unwrap());
+    shared.set(Tokyo.with_ymd_and_hms(2021, 2, 1, 0, 0, 0).unwrap());
+    assert_eq!(clock.now(), Tokyo.with_ymd_and_hms(2021, 2, 1, 0, 0, 0).unwrap());
+  }
+
+  #[test]
+  fn test_system_clock() {
+    let before = Utc::now();
+    let now = SystemClock::new(Tokyo).now();
+    assert!(before <= now && now <= Utc::now());
+    assert_eq!(now.timezone(), Tokyo);
+  }
+}
@@ -0,0 +1,341 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, TimeZone};
+use intervals_rs::LimitValue;
+
+use crate::cron_specification::matches_any_date;
+use crate::{
+  CronField, CronInterval, CronIntervalIterator, CronSchedule, CronSpecification, DayCombination, Expr, FieldDisplay,
+  Specification,
+};
+
+/// The instants at which all of two or more schedules fire, found by intersecting the values of their fields.<br/>
+/// 2つ以上のスケジュールが全て実行される日時。フィールドの値の積集合から求める。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Collision {
+  expr: Expr,
+  date_filters: Vec<Expr>,
+}
+
+impl Collision {
+  /// Detects whether the schedules ever fire at the same instant, over the full cycle of the calendar.<br/>
+  /// スケジュールが同じ日時に実行されることがあるかを、暦の周期全体について調べる。
+  ///
+  /// # Return values(戻り値)
+  ///
+  /// * Err:
+  ///   - If fewer than two schedules are given, or a schedule is a `rate(...)`
+  ///   - スケジュールが2つ未満の場合、または`rate(...)`のスケジュールがある場合
+  /// * Ok(None)
+  ///   - If the schedules never coincide
+  ///   - スケジュールが一致することがない場合
+  /// * Ok(Some)
+  ///   - If the schedules coincide
+  ///   - スケジュールが一致する場合
+  pub fn detect<Tz: TimeZone>(schedules: &[&CronSchedule<Tz>]) -> Result<Option<Self>> {
+    if schedules.len() < 2 {
+      return Err(anyhow!(
+        "Failed to detect collisions: at least two schedules are required"
+      ));
+    }
+    let exprs = schedules.iter().map(|s| s.expr()).collect::<Vec<_>>();
+    let mut fields = vec![];
+    for expr in &exprs {
+      match expr {
+        Expr::CronExpr {
+          secs,
+          mins,
+          hours,
+          days,
+          months,
+          day_of_weeks,
+          years,
+          day_combination,
+        } => fields.push((secs, mins, hours, days, months, day_of_weeks, years, day_combination)),
+        expr => {
+          return Err(anyhow!(
+            "Failed to detect collisions: {} is not a cron expression",
+            expr
+          ))
+        }
+      }
+    }
+
+    let secs = if fields.iter().all(|f| **f.0 == Expr::NoOp) {
+      Some(Expr::NoOp)
+    } else {
+      let zero = Expr::ValueExpr(0);
+      let secs = fields
+        .iter()
+        .map(|f| if **f.0 == Expr::NoOp { &zero } else { &**f.0 })
+        .collect::<Vec<_>>();
+      intersect(CronField::Second, &secs)?
+    };
+    let years = if fields.iter().all(|f| **f.6 == Expr::NoOp) {
+      Some(Expr::NoOp)
+    } else {
+      let years = fields.iter().map(|f| &**f.6).collect::<Vec<_>>();
+      intersect(CronField::Year, &years)?
+    };
+    let mins = intersect(CronField::Minute, &fields.iter().map(|f| &**f.1).collect::<Vec<_>>())?;
+    let hours = intersect(CronField::Hour, &fields.iter().map(|f| &**f.2).collect::<Vec<_>>())?;
+    let months = intersect(CronField::Month, &fields.iter().map(|f| &**f.4).collect::<Vec<_>>())?;
+    let (Some(secs), Some(mins), Some(hours), Some(months), Some(years)) = (secs, mins, hours, months, years) else {
+      return Ok(None);
+    };
+
+    // Days and day of weeks that depend on the date, or match either of them, are kept as filters.
+    let first = fields[0];
+    let mut date_filters = vec![];
+    let (days, day_of_weeks) = if fields.iter().all(|f| (f.3, f.5, f.7) == (first.3, first.5, first.7)) {
+      (Some((**first.3).clone()), Some((**first.5).clone()))
+    } else if fields.iter().all(|f| {
+      *f.7 == DayCombination::Both && CronField::Day.values(f.3).is_some() && CronField::DayOfWeek.values(f.5).is_some()
+    }) {
+      (
+        intersect(CronField::Day, &fields.iter().map(|f| &**f.3).collect::<Vec<_>>())?,
+        intersect(CronField::DayOfWeek, &fields.iter().map(|f| &**f.5).collect::<Vec<_>>())?,
+      )
+    } else {
+      date_filters = exprs.iter().map(|e| (*e).clone()).collect();
+      (Some(Expr::AnyValueExpr), Some(Expr::AnyValueExpr))
+    };
+    let (Some(days), Some(day_of_weeks)) = (days, day_of_weeks) else {
+      return Ok(None);
+    };
+    let day_combination = if date_filters.is_empty() {
+      *first.7
+    } else {
+      DayCombination::Both
+    };
+
+    let expr = Expr::CronExpr {
+      secs: Box::new(secs),
+      mins: Box::new(mins),
+      hours: Box::new(hours),
+      days: Box::new(days),
+      months: Box::new(months),
+      day_of_weeks: Box::new(day_of_weeks),
+      years: Box::new(years),
+      day_combination,
+    };
+    let dates = std::iter::once(&expr).chain(date_filters.iter()).collect::<Vec<_>>();
+    if !matches_any_date(&dates) {
+      return Ok(None);
+    }
+    Ok(Some(Self { expr, date_filters }))
+  }
+
+  /// Returns the expression that fires at the collisions.
+  /// If `is_exact` is false, the schedules also have to match days that the expression cannot express.<br/>
+  /// 一致する日時に実行される式を返す。
+  /// `is_exact`がfalseの場合、式で表せない日の条件もスケジュールが満たす必要がある。
+  pub fn expr(&self) -> &Expr {
+    &self.expr
+  }
+
+  /// Returns whether `expr` alone describes the collisions.<br/>
+  /// `expr`だけで一致する日時を表せるかどうかを返す。
+  pub fn is_exact(&self) -> bool {
+    self.date_filters.is_empty()
+  }
+
+  /// Returns the constant time between consecutive collisions, or `None` if it varies, e.g. with the length of
+  /// the month.<br/>
+  /// 連続する一致の間隔が一定の場合はその間隔を返す。月の長さなどによって変わる場合は`None`を返す。
+  pub fn period(&self) -> Option<Duration> {
+    let Expr::CronExpr {
+      secs,
+      mins,
+      hours,
+      days,
+      months,
+      day_of_weeks,
+      years,
+      ..
+    } = &self.expr
+    else {
+      return None;
+    };
+    let every_day = [
+      (CronField::Day, days),
+      (CronField::Month, months),
+      (CronField::DayOfWeek, day_of_weeks),
+    ]
+    .iter()
+    .all(|(field, e)| {
+      field
+        .values(e)
+        .is_some_and(|v| v.len() == (field.max() - field.min() + 1) as usize)
+    }) && **years == Expr::NoOp;
+    if !self.is_exact() || !every_day {
+      return None;
+    }
+    let secs = match &**secs {
+      Expr::NoOp => vec![0],
+      secs => CronField::Second.values(secs)?,
+    };
+    let mut times = vec![];
+    for hour in CronField::Hour.values(hours)? {
+      for min in CronField::Minute.values(mins)? {
+        for sec in &secs {
+          times.push(hour as i64 * 3600 + min as i64 * 60 + *sec as i64);
+        }
+      }
+    }
+    let day = 24 * 60 * 60;
+    let gaps = times
+      .windows(2)
+      .map(|w| w[1] - w[0])
+      .chain(std::iter::once(times[0] + day - times[times.len() - 1]))
+      .collect::<Vec<_>>();
+    if gaps.iter().all(|gap| *gap == gaps[0]) {
+      Some(Duration::seconds(gaps[0]))
+    } else {
+      None
+    }
+  }
+
+  /// Returns the CollisionSpecification, which is satisfied at the collisions.<br/>
+  /// 一致する日時に満たされるCollisionSpecificationを返す。
+  pub fn specification(&self) -> CollisionSpecification {
+    CollisionSpecification {
+      underlying: CronSpecification::new(self.expr.clone()),
+      date_filters: self.date_filters.iter().cloned().map(CronSpecification::new).collect(),
+    }
+  }
+
+  /// Returns a CronIntervalIterator with the collisions after the start date and time.<br/>
+  /// 開始日時以降の一致する日時を持つCronIntervalIteratorを返す。
+  pub fn upcoming<Tz: TimeZone>(&self, start: DateTime<Tz>) -> CronIntervalIterator<Tz, CollisionSpecification> {
+    let interval = CronInterval::new(
+      LimitValue::Limit(start.clone()),
+      LimitValue::Limitless,
+      self.specification(),
+    );
+    interval.iter(start.timezone())
+  }
+
+  /// Returns the first collision between the start date and time and the horizon.<br/>
+  /// 開始日時から期限までの最初の一致する日時を返す。
+  pub fn first<Tz: TimeZone>(&self, start: DateTime<Tz>, horizon: DateTime<Tz>) -> Option<DateTime<Tz>> {
+    if horizon < start {
+      return None;
+    }
+    let interval = CronInterval::new(
+      LimitValue::Limit(start.clone()),
+      LimitValue::Limit(horizon),
+      self.specification(),
+    );
+    interval.iter(start.timezone()).next()
+  }
+}
+
+/// The values all expressions match in the field as an expression, or `None` if there are none.
+fn intersect(field: CronField, exprs: &[&Expr]) -> Result<Option<Expr>> {
+  let mut common: Option<Vec<u8>> = None;
+  for expr in exprs {
+    let values = field.values(expr).ok_or_else(|| {
+      anyhow!(
+        "Failed to detect collisions: {}: {} has no fixed values",
+        field.name(),
+        FieldDisplay(field, expr)
+      )
+    })?;
+    common = Some(match common {
+      Some(common) => common.into_iter().filter(|v| values.contains(v)).collect(),
+      None => values,
+    });
+  }
+  let values = common.unwrap_or_default();
+  let expr = match values.as_slice() {
+    [] => return Ok(None),
+    values if values.len() == (field.max() - field.min() + 1) as usize => Expr::AnyValueExpr,
+    [value] => Expr::ValueExpr(*value),
+    values => Expr::ListExpr(values.iter().map(|v| Expr::ValueExpr(*v)).collect()),
+  };
+  Ok(Some(expr))
+}
+
+/// A specification that is satisfied when all of the schedules of a `Collision` fire.<br/>
+/// `Collision`の全てのスケジュールが実行されるときに満たされる仕様。
+#[derive(Debug, Clone)]
+pub struct CollisionSpecification {
+  underlying: CronSpecification,
+  date_filters: Vec<CronSpecification>,
+}
+
+impl<Tz: TimeZone> Specification<DateTime<Tz>> for CollisionSpecification {
+  fn is_satisfied_by(&self, datetime: &DateTime<Tz>) -> bool {
+    self.underlying.is_satisfied_by(datetime) && self.date_filters.iter().all(|spec| spec.is_satisfied_by(datetime))
+  }
+
+  fn granularity(&self) -> Duration {
+    Specification::<DateTime<Tz>>::granularity(&self.underlying)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::Utc;
+
+  fn detect(sources: &[&str]) -> Option<Collision> {
+    let schedules = sources
+      .iter()
+      .map(|s| CronSchedule::<Utc>::new(s).unwrap())
+      .collect::<Vec<_>>();
+    Collision::detect(&schedules.iter().collect::<Vec<_>>()).unwrap()
+  }
+
+  #[test]
+  fn test_detect() {
+    let collision = detect(&["*/15 * * * *", "*/10 * * * *"]).unwrap();
+    assert_eq!(collision.expr().to_string(), "0,30 * * * *");
+    assert!(collision.is_exact());
+    assert_eq!(collision.period(), Some(Duration::minutes(30)));
+    let start = Utc.with_ymd_and_hms(2021, 1, 1, 0, 1, 0).unwrap();
+    let horizon = Utc.with_ymd_and_hms(2021, 1, 2, 0, 0, 0).unwrap();
+    assert_eq!(
+      collision.first(start, horizon),
+      Some(Utc.with_ymd_and_hms(2021, 1, 1, 0, 30, 0).unwrap())
+    );
+
+    let collision = detect(&["0 */2 * * *", "0 */3 * * *"]).unwrap();
+    assert_eq!(collision.expr().to_string(), "0 0,6,12,18 * * *");
+    assert_eq!(collision.period(), Some(Duration::hours(6)));
+    let collision = detect(&["0 */2 * * *", "0 */3 * * *", "0 0-12 * * *"]).unwrap();
+    assert_eq!(collision.expr().to_string(), "0 0,6,12 * * *");
+    assert_eq!(collision.period(), None);
+
+    let collision = detect(&["0 9 * * 2", "0 9 13 * *"]).unwrap();
+    assert_eq!(collision.expr().to_string(), "0 9 13 * 2");
+    assert_eq!(collision.period(), None);
+  }
+
+  #[test]
+  fn test_never_coincide() {
+    assert!(detect(&["0 9 * * *", "0 10 * * *"]).is_none());
+    assert!(detect(&["0 0 30 * *", "0 0 * 2 *"]).is_none());
+    assert!(detect(&["0 0 1 * 2", "0 0 2 * 2"]).is_none());
+    let start = Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap();
+    let schedule = CronSchedule::<Utc>::new("0 9 * * *").unwrap();
+    assert!(Collision::detect(&[&schedule]).is_err());
+    let collision = detect(&["0 9 * * *", "0 9 * * *"]).unwrap();
+    assert_eq!(collision.first(start, start + Duration::hours(8)), None);
+    assert_eq!(collision.first(start, start - Duration::hours(1)), None);
+  }
+
+  #[test]
+  fn test_date_filters() {
+    // The last day of the month that is a Friday
+    let collision = detect(&["0 9 L * *", "0 9 * * 6"]).unwrap();
+    assert!(!collision.is_exact());
+    assert_eq!(collision.period(), None);
+    let start = Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap();
+    assert_eq!(
+      collision.upcoming(start).next(),
+      Some(Utc.with_ymd_and_hms(2021, 4, 30, 9, 0, 0).unwrap())
+    );
+    assert!(detect(&["0 9 LW * *", "0 9 * * 1"]).is_none());
+  }
+}
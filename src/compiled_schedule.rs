@@ -0,0 +1,339 @@
+use chrono::{DateTime, Datelike, Duration, LocalResult, Months, NaiveDate, Offset, TimeZone, Utc};
+
+use crate::cron_evaluator::get_days_from_month;
+use crate::{CronField, CronSpecification, DayCombination, Expr, Specification, YEAR_BASE};
+
+/// A `CronExpr` compiled into the times of day it fires at and bitmasks of its days, so that occurrences can be
+/// counted per day or per month instead of per instant.<br/>
+/// 実行する時刻と日のビットマスクにコンパイルした`CronExpr`。日時ごとではなく日や月ごとに実行回数を数えられる。
+#[derive(Debug, Clone)]
+pub(crate) struct CompiledSchedule {
+  times: Vec<u32>,
+  months: u16,
+  years: Option<Vec<u8>>,
+  days: Option<u32>,
+  day_of_weeks: Option<u8>,
+  day_combination: DayCombination,
+  date_spec: CronSpecification,
+}
+
+impl CompiledSchedule {
+  /// Compiles a `CronExpr`, returning `None` for other expressions such as `rate(...)`.<br/>
+  /// `CronExpr`をコンパイルする。`rate(...)`などの他の式の場合は`None`を返す。
+  pub(crate) fn new(expr: &Expr) -> Option<Self> {
+    let Expr::CronExpr {
+      secs,
+      mins,
+      hours,
+      days,
+      months,
+      day_of_weeks,
+      years,
+      day_combination,
+    } = expr
+    else {
+      return None;
+    };
+    let secs = match &**secs {
+      Expr::NoOp => vec![0],
+      secs => CronField::Second.values(secs)?,
+    };
+    let mut times = vec![];
+    for hour in CronField::Hour.values(hours)? {
+      for min in CronField::Minute.values(mins)? {
+        for sec in &secs {
+          times.push(hour as u32 * 3600 + min as u32 * 60 + *sec as u32);
+        }
+      }
+    }
+    let months = CronField::Month.values(months)?.iter().fold(0, |mask, m| mask | 1 << m);
+    let years = match &**years {
+      Expr::NoOp => None,
+      years => Some(CronField::Year.values(years)?),
+    };
+    let date_spec = CronSpecification::new(Expr::CronExpr {
+      secs: Box::new(Expr::NoOp),
+      mins: Box::new(Expr::AnyValueExpr),
+      hours: Box::new(Expr::AnyValueExpr),
+      days: days.clone(),
+      months: Box::new(Expr::AnyValueExpr),
+      day_of_weeks: day_of_weeks.clone(),
+      years: Box::new(Expr::NoOp),
+      day_combination: *day_combination,
+    });
+    // Day steps that cross the end of the month depend on its length, so those are evaluated per day.
+    let days = CronField::Day
+      .values(days)
+      .filter(|_| !crosses_month_end(days))
+      .map(|values| values.iter().fold(0, |mask, d| mask | 1 << d));
+    let day_of_weeks = CronField::DayOfWeek
+      .values(day_of_weeks)
+      .map(|values| values.iter().fold(0, |mask, d| mask | 1 << (d - 1)));
+    Some(Self {
+      times,
+      months,
+      years,
+      days,
+      day_of_weeks,
+      day_combination: *day_combination,
+      date_spec,
+    })
+  }
+
+  /// Returns the sorted times of day, in seconds from midnight.<br/>
+  /// 昇順の時刻を深夜0時からの秒数で返す。
+  pub(crate) fn times(&self) -> &[u32] {
+    &self.times
+  }
+
+  /// Returns the instants of the times of day on the local date in order. Local times skipped when the clocks go
+  /// forward do not fire, and those repeated when they go back fire twice.<br/>
+  /// ローカル日付における時刻の日時を順に返す。時計が進められて飛ばされた時刻は実行されず、
+  /// 戻されて繰り返された時刻は2回実行される。
+  pub(crate) fn occurrences_on<Tz: TimeZone>(&self, date: NaiveDate, timezone: &Tz) -> Vec<DateTime<Tz>> {
+    let mut date_times = vec![];
+    for time in &self.times {
+      let local = date.and_hms_opt(0, 0, 0).unwrap() + Duration::seconds(*time as i64);
+      match timezone.from_local_datetime(&local) {
+        LocalResult::Single(date_time) => date_times.push(date_time),
+        LocalResult::Ambiguous(earliest, latest) => date_times.extend([earliest, latest]),
+        LocalResult::None => {}
+      }
+    }
+    date_times.sort();
+    date_times
+  }
+
+  /// Returns the first occurrence strictly after `after`, looking month by month at the matching days. The search
+  /// ends with the last year of the years field, or after a whole 400-year cycle of the Gregorian calendar, which
+  /// contains every pattern of days and weekdays, so that a schedule with no further occurrence returns `None`.<br/>
+  /// `after`より後の最初の実行日時を、マッチする日を月ごとに調べて返す。探索は年のフィールドの最後の年、
+  /// または全ての日と曜日の並びを含むグレゴリオ暦の400年周期で終わるため、以降の実行日時がない場合は`None`を返す。
+  pub(crate) fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+    let timezone = after.timezone();
+    let first_date = after.naive_local().date();
+    let last_year = match &self.years {
+      Some(years) => YEAR_BASE as i32 + *years.iter().max()? as i32,
+      None => first_date.year() + 400,
+    };
+    let mut month = first_date.with_day(1).unwrap();
+    while month.year() <= last_year {
+      let mask = self.day_mask(month.year(), month.month());
+      for day in (1..=31).filter(|d| mask & (1 << d) != 0) {
+        let date = month.with_day(day).unwrap();
+        if date < first_date {
+          continue;
+        }
+        if let Some(next) = self.occurrences_on(date, &timezone).into_iter().find(|d| d > after) {
+          return Some(next);
+        }
+      }
+      month = month.checked_add_months(Months::new(1))?;
+    }
+    None
+  }
+
+  /// Returns the last occurrence strictly before `before`, looking for matching days month by month backwards. As
+  /// with `next_after`, the search ends at the first year of the years field or a 400-year cycle of the Gregorian
+  /// calendar, so that a schedule with no earlier occurrence returns `None`.<br/>
+  /// `before`より前の最後の実行日時を、マッチする日を月ごとに遡って調べて返す。`next_after`と同様に探索は年の
+  /// フィールドの最初の年、またはグレゴリオ暦の400年周期で終わるため、以前の実行日時がない場合は`None`を返す。
+  pub(crate) fn previous_before<Tz: TimeZone>(&self, before: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+    let timezone = before.timezone();
+    let last_date = before.naive_local().date();
+    let first_year = match &self.years {
+      Some(years) => YEAR_BASE as i32 + *years.iter().min()? as i32,
+      None => last_date.year() - 400,
+    };
+    let mut month = last_date.with_day(1).unwrap();
+    while month.year() >= first_year {
+      let mask = self.day_mask(month.year(), month.month());
+      for day in (1..=31).rev().filter(|d| mask & (1 << d) != 0) {
+        let date = month.with_day(day).unwrap();
+        if date > last_date {
+          continue;
+        }
+        if let Some(previous) = self
+          .occurrences_on(date, &timezone)
+          .into_iter()
+          .rev()
+          .find(|d| d < before)
+        {
+          return Some(previous);
+        }
+      }
+      month = month.checked_sub_months(Months::new(1))?;
+    }
+    None
+  }
+
+  /// Counts the occurrences in `[start, end)` month by month, multiplying the number of matching days by the number
+  /// of times of day. Months whose UTC offset changes, and the days at either end of the window, are counted day by
+  /// day. Offsets are compared at the start and end of each month, so a month whose offset changes and changes back
+  /// is counted as if it had not changed.<br/>
+  /// `[start, end)`の実行回数を、マッチする日数と時刻の数の積として月ごとに数える。UTCオフセットが変わる月と
+  /// 期間の両端の日は1日ずつ数える。オフセットは月の初めと終わりで比較するため、月内で変わって元に戻る場合は
+  /// 変わらなかったものとして数える。
+  pub(crate) fn count_between<Tz: TimeZone>(&self, start: &DateTime<Tz>, end: &DateTime<Tz>) -> u64 {
+    if start >= end {
+      return 0;
+    }
+    let timezone = start.timezone();
+    let first_date = start.naive_local().date();
+    let last_date = end.naive_local().date();
+    let offset_at = |date: NaiveDate| {
+      timezone
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+        .earliest()
+        .map(|d| d.offset().fix())
+    };
+    let mut count = 0;
+    let mut month = first_date.with_day(1).unwrap();
+    while month <= last_date {
+      let next_month = month.checked_add_months(Months::new(1)).unwrap();
+      let mut mask = self.day_mask(month.year(), month.month());
+      for date in [first_date, last_date] {
+        if date.year() == month.year() && date.month() == month.month() && mask & (1 << date.day()) != 0 {
+          mask &= !(1 << date.day());
+          count += self
+            .occurrences_on(date, &timezone)
+            .iter()
+            .filter(|d| *d >= start && *d < end)
+            .count() as u64;
+        }
+      }
+      let interior = (1..=31)
+        .filter(|d| {
+          month
+            .with_day(*d)
+            .is_some_and(|date| date > first_date && date < last_date)
+        })
+        .fold(0_u32, |m, d| m | 1 << d);
+      mask &= interior;
+      if mask != 0 {
+        let offset = offset_at(month);
+        if offset.is_some() && offset == offset_at(next_month) {
+          count += mask.count_ones() as u64 * self.times.len() as u64;
+        } else {
+          for day in (1..=31).filter(|d| mask & (1 << d) != 0) {
+            count += self.occurrences_on(month.with_day(day).unwrap(), &timezone).len() as u64;
+          }
+        }
+      }
+      month = next_month;
+    }
+    count
+  }
+
+  /// Returns the days of the month that match as a bitmask, where bit `d` stands for day `d`.<br/>
+  /// マッチする日をビットマスクで返す。ビット`d`が`d`日を表す。
+  pub(crate) fn day_mask(&self, year: i32, month: u32) -> u32 {
+    let in_years = match &self.years {
+      Some(years) => u8::try_from(year - YEAR_BASE as i32).is_ok_and(|y| years.contains(&y)),
+      None => true,
+    };
+    if !in_years || self.months & (1 << month) == 0 {
+      return 0;
+    }
+    let first = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+    let length = get_days_from_month(year, month).unwrap() as u32;
+    let month_mask = ((1_u64 << (length + 1)) - 2) as u32;
+    match (self.days, self.day_of_weeks) {
+      (Some(days), Some(day_of_weeks)) => {
+        // Day `d` falls on the weekday `(first + d - 1) % 7`, counted from Sunday.
+        let first_weekday = first.weekday().num_days_from_sunday();
+        let weekdays = (1..=length)
+          .filter(|d| day_of_weeks & (1 << ((first_weekday + d - 1) % 7)) != 0)
+          .fold(0, |mask, d| mask | 1 << d);
+        let mask = match self.day_combination {
+          DayCombination::Both => days & weekdays,
+          DayCombination::Either => days | weekdays,
+        };
+        mask & month_mask
+      }
+      _ => (1..=length)
+        .filter(|d| {
+          let date = first.with_day(*d).unwrap().and_hms_opt(0, 0, 0).unwrap();
+          self.date_spec.is_satisfied_by(&Utc.from_utc_datetime(&date))
+        })
+        .fold(0, |mask, d| mask | 1 << d),
+    }
+  }
+}
+
+fn crosses_month_end(expr: &Expr) -> bool {
+  match expr {
+    Expr::RangeExpr {
+      from: box Expr::ValueExpr(from),
+      to: box Expr::ValueExpr(to),
+      per_option,
+    } => from > to && **per_option != Expr::NoOp,
+    Expr::ListExpr(list) => list.iter().any(crosses_month_end),
+    _ => false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{CronParser, Dialect};
+
+  #[test]
+  fn test_day_mask() {
+    let days = |s: &str, year: i32, month: u32| {
+      let compiled = CompiledSchedule::new(&CronParser::parse(s).to_result().unwrap()).unwrap();
+      let mask = compiled.day_mask(year, month);
+      (1..=31).filter(|d| mask & (1 << d) != 0).collect::<Vec<_>>()
+    };
+    // 2021-02-01 is a Monday
+    assert_eq!(days("0 0 29-31 * *", 2021, 2), Vec::<u32>::new());
+    assert_eq!(days("0 0 29-31 * *", 2024, 2), vec![29]);
+    assert_eq!(days("0 0 * * 2", 2021, 2), vec![1, 8, 15, 22]);
+    assert_eq!(days("0 0 1-10 * 2", 2021, 2), vec![1, 8]);
+    assert_eq!(days("0 0 * 3 *", 2021, 2), Vec::<u32>::new());
+    assert_eq!(days("0 0 L * *", 2021, 2), vec![28]);
+    assert_eq!(days("0 0 * * 2L", 2021, 2), vec![22]);
+  }
+
+  #[test]
+  fn test_next_after() {
+    let next = |dialect: Dialect, s: &str, after: DateTime<Utc>| {
+      CompiledSchedule::new(&CronParser::parse_with(dialect, s).to_result().unwrap())
+        .unwrap()
+        .next_after(&after)
+    };
+    let after = Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap();
+    assert_eq!(
+      next(Dialect::Native, "30 9 * * *", after),
+      Some(Utc.with_ymd_and_hms(2021, 1, 1, 9, 30, 0).unwrap())
+    );
+    // February 29th on a Monday
+    assert_eq!(
+      next(Dialect::Native, "0 0 29 2 MON", after),
+      Some(Utc.with_ymd_and_hms(2044, 2, 29, 0, 0, 0).unwrap())
+    );
+    assert_eq!(next(Dialect::Quartz, "0 0 0 1 1 ? 2020", after), None);
+    assert_eq!(next(Dialect::Native, "0 0 30 2 *", after), None);
+  }
+
+  #[test]
+  fn test_previous_before() {
+    let previous = |dialect: Dialect, s: &str, before: DateTime<Utc>| {
+      CompiledSchedule::new(&CronParser::parse_with(dialect, s).to_result().unwrap())
+        .unwrap()
+        .previous_before(&before)
+    };
+    let before = Utc.with_ymd_and_hms(2021, 1, 1, 9, 30, 0).unwrap();
+    assert_eq!(
+      previous(Dialect::Native, "30 9 * * *", before),
+      Some(Utc.with_ymd_and_hms(2020, 12, 31, 9, 30, 0).unwrap())
+    );
+    // February 29th on a Monday
+    assert_eq!(
+      previous(Dialect::Native, "0 0 29 2 MON", before),
+      Some(Utc.with_ymd_and_hms(2016, 2, 29, 0, 0, 0).unwrap())
+    );
+    assert_eq!(previous(Dialect::Quartz, "0 0 0 1 1 ? 2022", before), None);
+    assert_eq!(previous(Dialect::Native, "0 0 30 2 *", before), None);
+  }
+}
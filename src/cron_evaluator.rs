@@ -1,6 +1,6 @@
-use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Timelike};
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Timelike, Weekday};
 
-use crate::Expr;
+use crate::{CronField, DayCombination, Expr, YEAR_BASE};
 
 pub struct CronEvaluator<'a, Tz: TimeZone> {
   instant: &'a DateTime<Tz>,
@@ -9,16 +9,17 @@ pub struct CronEvaluator<'a, Tz: TimeZone> {
 #[derive(Debug, Clone)]
 pub struct Environment {
   now: u8,
+  min: u8,
   max: u8,
 }
 
 impl Environment {
-  pub fn new(now: u8, max: u8) -> Self {
-    Self { now, max }
+  pub fn new(now: u8, min: u8, max: u8) -> Self {
+    Self { now, min, max }
   }
 }
 
-fn get_days_from_month(year: i32, month: u32) -> Option<i64> {
+pub(crate) fn get_days_from_month(year: i32, month: u32) -> Option<i64> {
   NaiveDate::from_ymd_opt(
     match month {
       12 => year + 1,
@@ -44,34 +45,117 @@ impl<'a, Tz: TimeZone> CronEvaluator<'a, Tz> {
   pub fn eval(&self, ast: &Expr) -> bool {
     match ast {
       Expr::CronExpr {
+        box secs,
         box mins,
         box hours,
         box months,
         box days,
         box day_of_weeks,
+        box years,
+        day_combination,
       } => {
-        let last_day =
-          get_days_from_month(self.instant.date_naive().year(), self.instant.date_naive().month()).unwrap();
-        let fmins = self.visit0(&Environment::new(self.instant.time().minute() as u8, 59), mins);
-        let fhours = self.visit0(&Environment::new(self.instant.time().hour() as u8, 23), hours);
+        let last_day = self.last_day();
+        let fmins = self.visit0(
+          &Self::environment(CronField::Minute, self.instant.time().minute()),
+          mins,
+        );
+        let fhours = self.visit0(&Self::environment(CronField::Hour, self.instant.time().hour()), hours);
         let fdays = self.visit0(
-          &Environment::new(self.instant.date_naive().day() as u8, last_day as u8),
+          &Environment::new(
+            self.instant.date_naive().day() as u8,
+            CronField::Day.min(),
+            last_day as u8,
+          ),
           days,
         );
-        let fmonths = self.visit0(&Environment::new(self.instant.date_naive().month() as u8, 12), months);
-        let fday_of_weeks = self.visit0(&Environment::new(self.instant.time().minute() as u8, 7), day_of_weeks);
-        fmins && fhours && fdays && fmonths && fday_of_weeks
+        let fmonths = self.visit0(
+          &Self::environment(CronField::Month, self.instant.date_naive().month()),
+          months,
+        );
+        let fday_of_weeks = self.visit0(
+          &Self::environment(CronField::DayOfWeek, self.instant.weekday().number_from_sunday()),
+          day_of_weeks,
+        );
+        let fsecs = *secs == Expr::NoOp
+          || self.visit0(
+            &Self::environment(CronField::Second, self.instant.time().second()),
+            secs,
+          );
+        let fyears = *years == Expr::NoOp
+          || (self.instant.year() - YEAR_BASE as i32).try_into().is_ok_and(|year| {
+            year <= CronField::Year.max()
+              && self.visit0(
+                &Environment::new(year, CronField::Year.min(), CronField::Year.max()),
+                years,
+              )
+          });
+        let fday_fields = match day_combination {
+          DayCombination::Both => fdays && fday_of_weeks,
+          DayCombination::Either => fdays || fday_of_weeks,
+        };
+        fsecs && fmins && fhours && fday_fields && fmonths && fyears
       }
       _ => false,
     }
   }
 
+  fn environment(field: CronField, now: u32) -> Environment {
+    Environment::new(now as u8, field.min(), field.max())
+  }
+
+  fn last_day(&self) -> u32 {
+    let date = self.instant.date_naive();
+    get_days_from_month(date.year(), date.month()).unwrap() as u32
+  }
+
+  /// Returns the weekday nearest to the day within the same month, as Quartz's `W` does.<br/>
+  /// Quartzの`W`と同様に、同じ月の中でその日に最も近い平日を返す。
+  fn nearest_weekday(&self, day: u32) -> Option<u32> {
+    let last_day = self.last_day();
+    let date = self.instant.date_naive().with_day(day)?;
+    Some(match date.weekday() {
+      Weekday::Sat if day == 1 => day + 2,
+      Weekday::Sat => day - 1,
+      Weekday::Sun if day == last_day => day - 2,
+      Weekday::Sun => day + 1,
+      _ => day,
+    })
+  }
+
   fn visit1(&self, env: &Environment, ast: &Expr) -> bool {
     match ast {
-      Expr::AnyValueExpr => true,
+      Expr::AnyValueExpr | Expr::NoSpecificValueExpr => true,
       Expr::LastValueExpr if env.now == env.max => true,
       Expr::ValueExpr(n) if env.now == *n => true,
+      Expr::LastDayOffsetExpr(offset) => env.max.checked_sub(*offset) == Some(env.now),
       Expr::ListExpr(list) => list.iter().any(|e| self.visit0(env, e)),
+      Expr::NearestWeekdayExpr(day) => self.nearest_weekday(*day as u32) == Some(self.instant.day()),
+      Expr::LastWeekdayExpr => self.nearest_weekday(self.last_day()) == Some(self.instant.day()),
+      Expr::LastDayOfWeekExpr(day_of_week) => env.now == *day_of_week && self.instant.day() + 7 > self.last_day(),
+      Expr::NthDayOfWeekExpr { day_of_week, nth } => {
+        env.now == *day_of_week && (self.instant.day() - 1) / 7 + 1 == *nth as u32
+      }
+      Expr::RangeExpr {
+        from: box Expr::ValueExpr(start),
+        to: box Expr::ValueExpr(end),
+        per_option,
+      } if start > end => {
+        // A wrap-around range such as `22-2` covers `start..=max` followed by `min..=end`,
+        // and its step keeps counting across the wrap. In a month shorter than `start`, the part before the wrap
+        // is empty.
+        let distance = if env.now >= *start {
+          (env.now - start) as u32
+        } else if env.now <= *end {
+          (env.max as u32 + 1).saturating_sub(*start as u32) + (env.now - env.min) as u32
+        } else {
+          return false;
+        };
+        match per_option {
+          box Expr::NoOp => true,
+          box Expr::ValueExpr(per) => distance % *per as u32 == 0,
+          _ => false,
+        }
+      }
       Expr::RangeExpr {
         from: box Expr::ValueExpr(start),
         to: box Expr::ValueExpr(end),
@@ -84,10 +168,23 @@ impl<'a, Tz: TimeZone> CronEvaluator<'a, Tz> {
           .any(|e| e == env.now as usize),
         _ => false,
       },
+      Expr::PerExpr {
+        digit: box Expr::ValueExpr(start),
+        option: box Expr::ValueExpr(per),
+      } => (*start as usize..=(env.max as usize))
+        .step_by(*per as usize)
+        .any(|e| e == env.now as usize),
+      Expr::PerExpr {
+        digit: box Expr::LastDayOffsetExpr(offset),
+        option: box Expr::ValueExpr(per),
+      } => env
+        .max
+        .checked_sub(*offset)
+        .is_some_and(|start| env.now >= start && (env.now - start).is_multiple_of(*per)),
       Expr::PerExpr {
         digit: box Expr::AnyValueExpr,
         option: box Expr::ValueExpr(per),
-      } => (0usize..=(env.max as usize))
+      } => (env.min as usize..=(env.max as usize))
         .step_by(*per as usize)
         .into_iter()
         .any(|e| e == env.now as usize),
@@ -102,21 +199,24 @@ impl<'a, Tz: TimeZone> CronEvaluator<'a, Tz> {
 
 #[cfg(test)]
 mod tests {
-  use chrono::{TimeZone, Utc};
+  use chrono::{Datelike, TimeZone, Utc};
 
-  use crate::cron_evaluator::CronEvaluator;
-  use crate::Expr;
+  use crate::cron_evaluator::{get_days_from_month, CronEvaluator};
+  use crate::{DayCombination, Expr};
 
   #[test]
   fn test_anytime() {
     let date_time = Utc.with_ymd_and_hms(2021, 1, 1, 1, 1, 1).unwrap();
     let cron_evaluator = CronEvaluator::new(&date_time);
     let expr = Expr::CronExpr {
+      secs: Box::from(Expr::NoOp),
       mins: Box::from(Expr::AnyValueExpr),
       hours: Box::from(Expr::AnyValueExpr),
       days: Box::from(Expr::AnyValueExpr),
       months: Box::from(Expr::AnyValueExpr),
       day_of_weeks: Box::from(Expr::AnyValueExpr),
+      years: Box::from(Expr::NoOp),
+      day_combination: DayCombination::Both,
     };
     let result = cron_evaluator.eval(&expr);
     assert!(result)
@@ -127,13 +227,228 @@ mod tests {
     let date_time = Utc.with_ymd_and_hms(2021, 1, 1, 1, 1, 1).unwrap();
     let cron_evaluator = CronEvaluator::new(&date_time);
     let expr = Expr::CronExpr {
+      secs: Box::from(Expr::NoOp),
       mins: Box::from(Expr::ValueExpr(1)),
       hours: Box::from(Expr::ValueExpr(1)),
       days: Box::from(Expr::ValueExpr(1)),
       months: Box::from(Expr::ValueExpr(1)),
       day_of_weeks: Box::from(Expr::AnyValueExpr),
+      years: Box::from(Expr::NoOp),
+      day_combination: DayCombination::Both,
     };
     let result = cron_evaluator.eval(&expr);
     assert!(result)
   }
+
+  fn eval_field(date_time: chrono::DateTime<Utc>, field: usize, e: Expr) -> bool {
+    let mut fields = vec![Expr::AnyValueExpr; 5];
+    fields[field] = e;
+    let expr = Expr::CronExpr {
+      secs: Box::from(Expr::NoOp),
+      mins: Box::from(fields[0].clone()),
+      hours: Box::from(fields[1].clone()),
+      days: Box::from(fields[2].clone()),
+      months: Box::from(fields[3].clone()),
+      day_of_weeks: Box::from(fields[4].clone()),
+      years: Box::from(Expr::NoOp),
+      day_combination: DayCombination::Both,
+    };
+    CronEvaluator::new(&date_time).eval(&expr)
+  }
+
+  fn range(from: u8, to: u8, per: Option<u8>) -> Expr {
+    Expr::RangeExpr {
+      from: Box::from(Expr::ValueExpr(from)),
+      to: Box::from(Expr::ValueExpr(to)),
+      per_option: Box::from(per.map(Expr::ValueExpr).unwrap_or(Expr::NoOp)),
+    }
+  }
+
+  #[test]
+  fn test_wrap_around_range() {
+    let at_hour = |h| Utc.with_ymd_and_hms(2021, 1, 1, h, 0, 0).unwrap();
+    let hours = |from, to, per| {
+      (0..24)
+        .filter(|h| eval_field(at_hour(*h), 1, range(from, to, per)))
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(hours(22, 2, None), vec![0, 1, 2, 22, 23]);
+    assert_eq!(hours(22, 2, Some(2)), vec![0, 2, 22]);
+    assert_eq!(hours(21, 3, Some(2)), vec![1, 3, 21, 23]);
+
+    // 2021-01-01 is a Friday (6) and 2021-01-04 is a Monday (2)
+    let at_day = |d| Utc.with_ymd_and_hms(2021, 1, d, 0, 0, 0).unwrap();
+    let days = (1..=7)
+      .filter(|d| eval_field(at_day(*d), 4, range(6, 2, None)))
+      .collect::<Vec<_>>();
+    assert_eq!(days, vec![1, 2, 3, 4]);
+
+    // Days wrap at the last day of the month being evaluated
+    let at_date = |m, d| Utc.with_ymd_and_hms(2021, m, d, 0, 0, 0).unwrap();
+    let days = (1..=28)
+      .filter(|d| eval_field(at_date(2, *d), 2, range(27, 3, Some(2))))
+      .collect::<Vec<_>>();
+    assert_eq!(days, vec![1, 3, 27]);
+
+    // A range starting after the last day of February only covers the part after the wrap
+    let february = |year, from, to, per| {
+      let length = get_days_from_month(year, 2).unwrap() as u32;
+      (1..=length)
+        .filter(|d| {
+          eval_field(
+            Utc.with_ymd_and_hms(year, 2, *d, 0, 0, 0).unwrap(),
+            2,
+            range(from, to, per),
+          )
+        })
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(february(2021, 30, 2, None), vec![1, 2]);
+    assert_eq!(february(2021, 29, 3, Some(2)), vec![1, 3]);
+    assert_eq!(february(2024, 29, 3, Some(2)), vec![2, 29]);
+
+    let months = (1..=12)
+      .filter(|m| eval_field(at_date(*m, 1), 3, range(11, 2, None)))
+      .collect::<Vec<_>>();
+    assert_eq!(months, vec![1, 2, 11, 12]);
+  }
+
+  fn per_any(per: u8) -> Expr {
+    Expr::PerExpr {
+      digit: Box::from(Expr::AnyValueExpr),
+      option: Box::from(Expr::ValueExpr(per)),
+    }
+  }
+
+  fn per(start: u8, per: u8) -> Expr {
+    Expr::PerExpr {
+      digit: Box::from(Expr::ValueExpr(start)),
+      option: Box::from(Expr::ValueExpr(per)),
+    }
+  }
+
+  #[test]
+  fn test_start_per() {
+    let at_minute = |m| Utc.with_ymd_and_hms(2021, 1, 1, 0, m, 0).unwrap();
+    let mins = (0..60)
+      .filter(|m| eval_field(at_minute(*m), 0, per(5, 15)))
+      .collect::<Vec<_>>();
+    assert_eq!(mins, vec![5, 20, 35, 50]);
+
+    // 2021-01-04 is a Monday (2)
+    let at_day = |d| Utc.with_ymd_and_hms(2021, 1, d, 0, 0, 0).unwrap();
+    let days = (3..=9)
+      .filter(|d| eval_field(at_day(*d), 4, per(2, 2)))
+      .collect::<Vec<_>>();
+    assert_eq!(days, vec![4, 6, 8]);
+
+    // Days step up to the last day of the month being evaluated
+    let at_date = |m, d| Utc.with_ymd_and_hms(2021, m, d, 0, 0, 0).unwrap();
+    let days = (1..=28)
+      .filter(|d| eval_field(at_date(2, *d), 2, per(20, 4)))
+      .collect::<Vec<_>>();
+    assert_eq!(days, vec![20, 24, 28]);
+  }
+
+  #[test]
+  fn test_any_per_starts_at_minimum() {
+    let every_two = || per_any(2);
+    let at_minute = |m| Utc.with_ymd_and_hms(2021, 1, 1, 0, m, 0).unwrap();
+    let mins = (0..60)
+      .filter(|m| eval_field(at_minute(*m), 0, every_two()))
+      .collect::<Vec<_>>();
+    assert_eq!(mins, (0..60).step_by(2).collect::<Vec<_>>());
+
+    let at_hour = |h| Utc.with_ymd_and_hms(2021, 1, 1, h, 0, 0).unwrap();
+    let hours = (0..24)
+      .filter(|h| eval_field(at_hour(*h), 1, every_two()))
+      .collect::<Vec<_>>();
+    assert_eq!(hours, (0..24).step_by(2).collect::<Vec<_>>());
+
+    let at_day = |d| Utc.with_ymd_and_hms(2021, 1, d, 0, 0, 0).unwrap();
+    let days = (1..=31)
+      .filter(|d| eval_field(at_day(*d), 2, every_two()))
+      .collect::<Vec<_>>();
+    assert_eq!(days, (1..=31).step_by(2).collect::<Vec<_>>());
+
+    let at_month = |m| Utc.with_ymd_and_hms(2021, m, 1, 0, 0, 0).unwrap();
+    let months = (1..=12)
+      .filter(|m| eval_field(at_month(*m), 3, every_two()))
+      .collect::<Vec<_>>();
+    assert_eq!(months, vec![1, 3, 5, 7, 9, 11]);
+
+    // 2021-01-03 is a Sunday (1), so day d of this week has the day of week number d - 2
+    let day_of_weeks = (3..=9)
+      .filter(|d| eval_field(at_day(*d), 4, every_two()))
+      .map(|d| d - 2)
+      .collect::<Vec<_>>();
+    assert_eq!(day_of_weeks, vec![1, 3, 5, 7]);
+  }
+
+  #[test]
+  fn test_day_of_week() {
+    // 2021-01-01 is a Friday (6)
+    let date_time = Utc.with_ymd_and_hms(2021, 1, 1, 1, 1, 1).unwrap();
+    let cron_evaluator = CronEvaluator::new(&date_time);
+    let expr = |day_of_week: Expr| Expr::CronExpr {
+      secs: Box::from(Expr::NoOp),
+      mins: Box::from(Expr::AnyValueExpr),
+      hours: Box::from(Expr::AnyValueExpr),
+      days: Box::from(Expr::AnyValueExpr),
+      months: Box::from(Expr::AnyValueExpr),
+      day_of_weeks: Box::from(day_of_week),
+      years: Box::from(Expr::NoOp),
+      day_combination: DayCombination::Both,
+    };
+    assert!(cron_evaluator.eval(&expr(Expr::ValueExpr(6))));
+    assert!(!cron_evaluator.eval(&expr(Expr::ValueExpr(1))));
+  }
+
+  #[test]
+  fn test_calendar_modifiers() {
+    let days = |e: Expr, y: i32, m: u32| {
+      (1..=31)
+        .filter_map(|d| Utc.with_ymd_and_hms(y, m, d, 0, 0, 0).single())
+        .filter(|dt| eval_field(*dt, 2, e.clone()))
+        .map(|dt| dt.day())
+        .collect::<Vec<_>>()
+    };
+    let day_of_weeks = |e: Expr, y: i32, m: u32| {
+      (1..=31)
+        .filter_map(|d| Utc.with_ymd_and_hms(y, m, d, 0, 0, 0).single())
+        .filter(|dt| eval_field(*dt, 4, e.clone()))
+        .map(|dt| dt.day())
+        .collect::<Vec<_>>()
+    };
+    // 2021-01-16 is a Saturday and 2021-01-31 a Sunday
+    assert_eq!(days(Expr::NearestWeekdayExpr(16), 2021, 1), vec![15]);
+    assert_eq!(days(Expr::NearestWeekdayExpr(17), 2021, 1), vec![18]);
+    assert_eq!(days(Expr::NearestWeekdayExpr(31), 2021, 1), vec![29]);
+    assert_eq!(days(Expr::LastWeekdayExpr, 2021, 1), vec![29]);
+    // 2021-05-01 is a Saturday, which does not move back into April
+    assert_eq!(days(Expr::NearestWeekdayExpr(1), 2021, 5), vec![3]);
+    assert_eq!(days(Expr::NearestWeekdayExpr(31), 2021, 2), Vec::<u32>::new());
+    assert_eq!(days(Expr::LastValueExpr, 2021, 2), vec![28]);
+    assert_eq!(days(Expr::LastDayOffsetExpr(2), 2021, 2), vec![26]);
+    assert_eq!(
+      days(
+        Expr::PerExpr {
+          digit: Box::from(Expr::LastDayOffsetExpr(6)),
+          option: Box::from(Expr::ValueExpr(3)),
+        },
+        2021,
+        2
+      ),
+      vec![22, 25, 28]
+    );
+    assert_eq!(day_of_weeks(Expr::LastDayOfWeekExpr(6), 2021, 1), vec![29]);
+    assert_eq!(
+      day_of_weeks(Expr::NthDayOfWeekExpr { day_of_week: 6, nth: 3 }, 2021, 1),
+      vec![15]
+    );
+    assert_eq!(
+      day_of_weeks(Expr::NthDayOfWeekExpr { day_of_week: 6, nth: 5 }, 2021, 1),
+      vec![29]
+    );
+  }
 }
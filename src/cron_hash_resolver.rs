@@ -0,0 +1,144 @@
+use crate::Expr;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// The ranges `H` picks from for minutes, hours, days, months and day of weeks.<br/>
+/// 分・時・日・月・曜日それぞれで`H`が値を選ぶ範囲。
+///
+/// Days are limited to 1-28 so that a hashed day exists in every month, as Jenkins does.<br/>
+/// Jenkinsと同様に、どの月にも存在するよう日は1-28に制限する。
+const FIELD_RANGES: [(u8, u8); 5] = [(0, 59), (0, 23), (1, 28), (1, 12), (1, 7)];
+
+/// Resolves Jenkins style `H` tokens into concrete values derived from a seed such as a job name.<br/>
+/// ジョブ名などのシードから、Jenkins形式の`H`を具体的な値に解決する。
+///
+/// The value for each field is `from + FNV-1a-64(seed ++ [field index]) % span`. This algorithm is part of
+/// the public contract, so a given seed resolves to the same schedule in every version of this crate.<br/>
+/// 各フィールドの値は`from + FNV-1a-64(seed ++ [フィールド番号]) % span`で求める。
+/// このアルゴリズムは公開仕様の一部であり、同じシードはどのバージョンでも同じスケジュールに解決される。
+#[derive(Debug, Clone)]
+pub struct CronHashResolver<'a> {
+  seed: &'a str,
+}
+
+impl<'a> CronHashResolver<'a> {
+  pub fn new(seed: &'a str) -> Self {
+    Self { seed }
+  }
+
+  /// Returns whether the expression contains `H`.<br/>
+  /// 式が`H`を含むかどうかを返す。
+  pub fn contains_hash(expr: &Expr) -> bool {
+    match expr {
+      Expr::HashExpr { .. } => true,
+      Expr::ListExpr(list) => list.iter().any(Self::contains_hash),
+      Expr::CronExpr {
+        mins,
+        hours,
+        days,
+        months,
+        day_of_weeks,
+        ..
+      } => [mins, hours, days, months, day_of_weeks]
+        .into_iter()
+        .any(|e| Self::contains_hash(e)),
+      _ => false,
+    }
+  }
+
+  /// Returns the expression with every `H` replaced by concrete values.<br/>
+  /// 全ての`H`を具体的な値に置き換えた式を返す。
+  pub fn resolve(&self, expr: &Expr) -> Expr {
+    match expr {
+      Expr::CronExpr {
+        secs,
+        mins,
+        hours,
+        days,
+        months,
+        day_of_weeks,
+        years,
+        day_combination,
+      } => Expr::CronExpr {
+        secs: secs.clone(),
+        mins: Box::from(self.visit(0, mins)),
+        hours: Box::from(self.visit(1, hours)),
+        days: Box::from(self.visit(2, days)),
+        months: Box::from(self.visit(3, months)),
+        day_of_weeks: Box::from(self.visit(4, day_of_weeks)),
+        years: years.clone(),
+        day_combination: *day_combination,
+      },
+      e => e.clone(),
+    }
+  }
+
+  fn hash(&self, field: u8) -> u64 {
+    self
+      .seed
+      .as_bytes()
+      .iter()
+      .chain([field].iter())
+      .fold(FNV_OFFSET_BASIS, |h, b| (h ^ *b as u64).wrapping_mul(FNV_PRIME))
+  }
+
+  fn visit(&self, field: u8, expr: &Expr) -> Expr {
+    match expr {
+      Expr::ListExpr(list) => Expr::ListExpr(list.iter().map(|e| self.visit(field, e)).collect()),
+      Expr::HashExpr { range, per_option } => {
+        let (from, to) = match range {
+          box Expr::RangeExpr {
+            from: box Expr::ValueExpr(from),
+            to: box Expr::ValueExpr(to),
+            ..
+          } => (*from, *to),
+          _ => FIELD_RANGES[field as usize],
+        };
+        let span = (to.max(from) - from) as u64 + 1;
+        let hash = self.hash(field);
+        match per_option {
+          box Expr::ValueExpr(per) => Expr::RangeExpr {
+            from: Box::from(Expr::ValueExpr(from + (hash % span.min(*per as u64)) as u8)),
+            to: Box::from(Expr::ValueExpr(to)),
+            per_option: Box::from(Expr::ValueExpr(*per)),
+          },
+          _ => Expr::ValueExpr(from + (hash % span) as u8),
+        }
+      }
+      e => e.clone(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::CronParser;
+
+  fn resolve(s: &str, seed: &str) -> String {
+    let expr = CronParser::parse(s).to_result().unwrap();
+    CronHashResolver::new(seed).resolve(&expr).to_string()
+  }
+
+  #[test]
+  fn test_resolve() {
+    // These values are part of the stability contract and must never change.
+    assert_eq!(resolve("H H * * *", "nightly-backup"), "1 18 * * *");
+    assert_eq!(resolve("H/15 * * * *", "nightly-backup"), "1-59/15 * * * *");
+    assert_eq!(resolve("H(0-29) H(1-5) H H H", "report"), "29 4 8 9 5");
+    assert_eq!(resolve("H,30 * * * *", "report"), "29,30 * * * *");
+  }
+
+  #[test]
+  fn test_deterministic() {
+    assert_eq!(resolve("H H * * *", "a"), resolve("H H * * *", "a"));
+    assert_ne!(resolve("H H * * *", "a"), resolve("H H * * *", "b"));
+    assert!(!CronHashResolver::contains_hash(
+      &CronParser::parse("* * * * *").to_result().unwrap()
+    ));
+    assert!(CronHashResolver::contains_hash(
+      &CronParser::parse("* * * * H").to_result().unwrap()
+    ));
+  }
+}
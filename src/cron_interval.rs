@@ -1,10 +1,10 @@
 use std::marker::PhantomData;
 use std::rc::Rc;
 
-use chrono::{DateTime, TimeZone};
+use chrono::{DateTime, Duration, TimeZone};
 use intervals_rs::{Interval, LimitValue};
 
-use crate::{CronIntervalIterator, Specification};
+use crate::{CronIntervalIterator, CronIntervalReverseIterator, Specification};
 
 /// A structure representing an interval of CROND date and time.<br/>
 /// CROND日時の区間を表す構造体。
@@ -48,4 +48,27 @@ impl<Tz: TimeZone, S: Specification<DateTime<Tz>>> CronInterval<Tz, S> {
     let date_time = timezone.timestamp_millis_opt(*timestamp).unwrap();
     CronIntervalIterator::new(timezone, date_time.clone(), date_time, Rc::new(self.clone()))
   }
+
+  /// Returns a CronIntervalReverseIterator that starts at the end, aligned down to the granularity of the specification.
+  /// The interval must have an end.<br/>
+  /// 終了日時から仕様の粒度に切り捨てて開始するCronIntervalReverseIteratorを返す。区間は終了日時を持つ必要がある。
+  pub fn iter_rev(&self, timezone: Tz) -> CronIntervalReverseIterator<Tz, S> {
+    let timestamp = self.underlying.as_upper_limit().as_value().unwrap();
+    let step = self.cron_specification.granularity().num_milliseconds();
+    let aligned = timestamp - timestamp.rem_euclid(step);
+    let date_time = timezone.timestamp_millis_opt(aligned).unwrap();
+    CronIntervalReverseIterator::new(timezone, date_time, Rc::new(self.clone()))
+  }
+}
+
+/// Rounds up to a multiple of the granularity, so that iteration starts on a whole minute or second.<br/>
+/// 粒度の倍数に切り上げる。反復が分や秒の区切りから始まるようにする。
+pub(crate) fn align_up<Tz: TimeZone>(date_time: DateTime<Tz>, granularity: Duration) -> DateTime<Tz> {
+  let step = granularity.num_milliseconds();
+  let remainder = date_time.timestamp_millis().rem_euclid(step);
+  if remainder == 0 {
+    date_time
+  } else {
+    date_time + Duration::milliseconds(step - remainder)
+  }
 }
@@ -1,5 +1,5 @@
 use crate::{CronInterval, Specification};
-use chrono::{DateTime, Duration, TimeZone};
+use chrono::{DateTime, TimeZone};
 use std::rc::Rc;
 
 /// Iterator for The CronInterval.<br/>
@@ -29,23 +29,14 @@ impl<Tz: TimeZone, S: Specification<DateTime<Tz>>> Iterator for CronIntervalIter
   type Item = DateTime<Tz>;
 
   fn next(&mut self) -> Option<Self::Item> {
+    let granularity = self.cron_interval.cron_specification.granularity();
     self.curr = self.next.clone();
-    self.next = self.next.clone() + Duration::minutes(1);
-    match self.end_value() {
-      None => {
-        self.proceed_next();
-        let curr: DateTime<Tz> = self.curr.clone();
-        Some(curr)
-      }
-      Some(end) => {
-        if end >= self.curr {
-          self.proceed_next();
-          let curr: DateTime<Tz> = self.curr.clone();
-          Some(curr)
-        } else {
-          None
-        }
-      }
+    self.next = self.next.clone() + granularity;
+    let end = self.end_value();
+    self.proceed_next(end.as_ref());
+    match end {
+      Some(end) if self.curr > end => None,
+      _ => Some(self.curr.clone()),
     }
   }
 }
@@ -73,10 +64,16 @@ impl<Tz: TimeZone, S: Specification<DateTime<Tz>>> CronIntervalIterator<Tz, S> {
     }
   }
 
-  fn proceed_next(&mut self) {
+  /// Advances to the next satisfying date and time, giving up once it passes the end.<br/>
+  /// 条件を満たす次の日時まで進める。終了日時を過ぎた場合は打ち切る。
+  fn proceed_next(&mut self, end: Option<&DateTime<Tz>>) {
+    let granularity = self.cron_interval.cron_specification.granularity();
     while !self.cron_interval.cron_specification.is_satisfied_by(&self.curr) {
+      if end.is_some_and(|end| self.curr > *end) {
+        return;
+      }
       self.curr = self.next.clone();
-      self.next = self.next.clone() + Duration::minutes(1);
+      self.next = self.next.clone() + granularity;
     }
   }
 }
@@ -101,4 +98,17 @@ mod tests {
     let itr = interval.iter(Utc);
     itr.take(5).for_each(|e| println!("{:?}", e));
   }
+
+  #[test]
+  fn test_iterator_stops_at_end() {
+    let start = Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap();
+    let end = Utc.with_ymd_and_hms(2021, 1, 1, 1, 30, 0).unwrap();
+    let expr = CronParser::parse("0 * * * *").to_result().unwrap();
+    let interval = CronInterval::new(
+      LimitValue::Limit(start),
+      LimitValue::Limit(end),
+      CronSpecification::new(expr),
+    );
+    assert_eq!(interval.iter(Utc).count(), 2);
+  }
 }
@@ -0,0 +1,90 @@
+use crate::{CronInterval, Specification};
+use chrono::{DateTime, TimeZone};
+use std::rc::Rc;
+
+/// Iterator for The CronInterval that walks from the end towards the start, latest first.<br/>
+/// 終了日時から開始日時に向かって遡る、CronIntervalのためのイテレータ。新しい日時から順に返す。
+#[derive(Debug, Clone)]
+pub struct CronIntervalReverseIterator<Tz: TimeZone, S: Specification<DateTime<Tz>>> {
+  timezone: Tz,
+  next: DateTime<Tz>,
+  cron_interval: Rc<CronInterval<Tz, S>>,
+}
+
+impl<Tz: TimeZone, S: Specification<DateTime<Tz>>> CronIntervalReverseIterator<Tz, S> {
+  /// The factory method.
+  /// ファクトリメソッド。
+  pub fn new(timezone: Tz, next: DateTime<Tz>, cron_interval: Rc<CronInterval<Tz, S>>) -> Self {
+    Self {
+      timezone,
+      next,
+      cron_interval,
+    }
+  }
+
+  /// Returns the timezone of CronIntervalReverseIterator.<br/>
+  /// CronIntervalReverseIteratorのタイムゾーンを返す。
+  pub fn timezone(&self) -> &Tz {
+    &self.timezone
+  }
+
+  /// Returns the CronInterval.<br/>
+  /// CronIntervalを返す。
+  pub fn cron_interval(&self) -> Rc<CronInterval<Tz, S>> {
+    self.cron_interval.clone()
+  }
+
+  fn start_value(&self) -> Option<DateTime<Tz>> {
+    if self.cron_interval.underlying.has_lower_limit() {
+      let timestamp = self.cron_interval.underlying.as_lower_limit().as_value().unwrap();
+      let date_time = self.timezone.timestamp_millis_opt(*timestamp).unwrap();
+      Some(date_time)
+    } else {
+      None
+    }
+  }
+}
+
+impl<Tz: TimeZone, S: Specification<DateTime<Tz>>> Iterator for CronIntervalReverseIterator<Tz, S> {
+  type Item = DateTime<Tz>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let granularity = self.cron_interval.cron_specification.granularity();
+    let start = self.start_value();
+    loop {
+      let curr = self.next.clone();
+      if start.as_ref().is_some_and(|start| curr < *start) {
+        return None;
+      }
+      self.next = curr.clone() - granularity;
+      if self.cron_interval.cron_specification.is_satisfied_by(&curr) {
+        return Some(curr);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::{TimeZone, Utc};
+  use intervals_rs::LimitValue;
+
+  use crate::{CronInterval, CronParser, CronSpecification};
+
+  #[test]
+  fn test_iterator() {
+    let start = Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap();
+    let end = Utc.with_ymd_and_hms(2021, 1, 1, 2, 45, 30).unwrap();
+    let expr = CronParser::parse("0-59/30 * * * *").to_result().unwrap();
+    let interval = CronInterval::new(
+      LimitValue::Limit(start),
+      LimitValue::Limit(end),
+      CronSpecification::new(expr),
+    );
+    let dt_vec = interval.iter_rev(Utc).collect::<Vec<_>>();
+    assert_eq!(dt_vec.len(), 6);
+    assert_eq!(dt_vec[0], Utc.with_ymd_and_hms(2021, 1, 1, 2, 30, 0).unwrap());
+    assert_eq!(dt_vec[1], Utc.with_ymd_and_hms(2021, 1, 1, 2, 0, 0).unwrap());
+    assert_eq!(dt_vec[5], start);
+  }
+}
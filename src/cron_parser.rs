@@ -1,5 +1,9 @@
-use crate::Expr;
-use crate::Expr::{AnyValueExpr, CronExpr, LastValueExpr, ListExpr, NoOp, PerExpr, RangeExpr, ValueExpr};
+use crate::dialect::{nickname, FieldPresence};
+use crate::Expr::{
+  AnyValueExpr, CronExpr, HashExpr, LastDayOfWeekExpr, LastValueExpr, LastWeekdayExpr, ListExpr, NearestWeekdayExpr,
+  NoOp, NoSpecificValueExpr, NthDayOfWeekExpr, PerExpr, RangeExpr, RateExpr, ValueExpr,
+};
+use crate::{CronField, DayCombination, Dialect, Expr, RateUnit, YEAR_BASE};
 use oni_comb_parser_rs::prelude::*;
 
 fn min_digit<'a>() -> Parser<'a, u8, Expr> {
@@ -32,6 +36,16 @@ fn day_digit<'a>() -> Parser<'a, u8, Expr> {
     | elm_of(b"123456789").map(|e| ValueExpr(e - 48))
 }
 
+fn day<'a>() -> Parser<'a, u8, Expr> {
+  seq(b"LW").attempt().map(|_| LastWeekdayExpr)
+    | elm(b'L').attempt().map(|_| LastValueExpr)
+    | (day_digit() - elm(b'W')).attempt().map(|e| match e {
+      ValueExpr(n) => NearestWeekdayExpr(n),
+      e => e,
+    })
+    | day_digit()
+}
+
 fn month_digit<'a>() -> Parser<'a, u8, Expr> {
   (elm(b'1') + elm_of(b"012"))
     .attempt()
@@ -40,19 +54,61 @@ fn month_digit<'a>() -> Parser<'a, u8, Expr> {
     | elm_of(b"123456789").map(|e| ValueExpr(e - 48))
 }
 
-fn day_of_week_digit<'a>() -> Parser<'a, u8, Expr> {
-  seq(b"SUN").attempt().map(|_| ValueExpr(1))
-    | seq(b"MON").attempt().map(|_| ValueExpr(2))
-    | seq(b"TUE").attempt().map(|_| ValueExpr(3))
-    | seq(b"WED").attempt().map(|_| ValueExpr(4))
-    | seq(b"THU").attempt().map(|_| ValueExpr(5))
-    | seq(b"FRI").attempt().map(|_| ValueExpr(6))
-    | seq(b"SAT").attempt().map(|_| ValueExpr(7))
+fn month_name<'a>() -> Parser<'a, u8, Expr> {
+  seq(b"JAN").attempt().map(|_| ValueExpr(1))
+    | seq(b"FEB").attempt().map(|_| ValueExpr(2))
+    | seq(b"MAR").attempt().map(|_| ValueExpr(3))
+    | seq(b"APR").attempt().map(|_| ValueExpr(4))
+    | seq(b"MAY").attempt().map(|_| ValueExpr(5))
+    | seq(b"JUN").attempt().map(|_| ValueExpr(6))
+    | seq(b"JUL").attempt().map(|_| ValueExpr(7))
+    | seq(b"AUG").attempt().map(|_| ValueExpr(8))
+    | seq(b"SEP").attempt().map(|_| ValueExpr(9))
+    | seq(b"OCT").attempt().map(|_| ValueExpr(10))
+    | seq(b"NOV").attempt().map(|_| ValueExpr(11))
+    | seq(b"DEC").map(|_| ValueExpr(12))
+}
+
+fn month<'a>() -> Parser<'a, u8, Expr> {
+  month_digit().attempt() | month_name()
+}
+
+fn year_digit<'a>() -> Parser<'a, u8, Expr> {
+  (elm_of(b"12") + elm_of(b"0123456789").of_count(3))
+    .map(|(e, es)| es.iter().fold((e - 48) as u16, |year, d| year * 10 + (d - 48) as u16))
+    .with_filter(|year| (YEAR_BASE..=YEAR_BASE + CronField::Year.max() as u16).contains(year))
+    .map(|year| ValueExpr((year - YEAR_BASE) as u8))
+}
+
+fn day_of_week_name<'a>(sunday: u8) -> Parser<'a, u8, u8> {
+  seq(b"SUN").attempt().map(move |_| sunday)
+    | seq(b"MON").attempt().map(move |_| sunday + 1)
+    | seq(b"TUE").attempt().map(move |_| sunday + 2)
+    | seq(b"WED").attempt().map(move |_| sunday + 3)
+    | seq(b"THU").attempt().map(move |_| sunday + 4)
+    | seq(b"FRI").attempt().map(move |_| sunday + 5)
+    | seq(b"SAT").map(move |_| sunday + 6)
+}
+
+fn day_of_week_number<'a>(digits: &'static [u8]) -> Parser<'a, u8, u8> {
+  elm_of(digits).map(|e| e - 48)
+}
+
+fn day_of_week<'a>(dialect: Dialect) -> Parser<'a, u8, Expr> {
+  let value = move || day_of_week_number(dialect.day_of_week_digits()).attempt() | day_of_week_name(dialect.sunday());
+  (value() - elm(b'#') + elm_of(b"12345"))
+    .attempt()
+    .map(|(day_of_week, nth)| NthDayOfWeekExpr {
+      day_of_week,
+      nth: nth - 48,
+    })
+    | (value() - elm(b'L')).attempt().map(LastDayOfWeekExpr)
+    | value().attempt().map(ValueExpr)
     | elm(b'L').map(|_| LastValueExpr)
 }
 
-fn day_of_week_text<'a>() -> Parser<'a, u8, Expr> {
-  elm_of(b"1234567").map(|e| ValueExpr(e as u8 - 48))
+fn question<'a>() -> Parser<'a, u8, Expr> {
+  elm(b'?').map(|_| NoSpecificValueExpr)
 }
 
 fn asterisk<'a>() -> Parser<'a, u8, Expr> {
@@ -70,6 +126,15 @@ fn asterisk_per(p: Parser<u8, Expr>) -> Parser<u8, Expr> {
   })
 }
 
+macro_rules! value_per {
+  ( $x:expr ) => {
+    ($x + per($x)).map(|(d, op)| PerExpr {
+      digit: Box::from(d),
+      option: Box::from(op),
+    })
+  };
+}
+
 fn range_per(p: Parser<u8, Expr>) -> Parser<u8, Expr> {
   per(p).opt().map(|e| match e {
     None => NoOp,
@@ -87,44 +152,392 @@ macro_rules! range {
   };
 }
 
+macro_rules! hash {
+  ( $x:expr ) => {
+    (elm(b'H') * (elm(b'(') * $x - elm(b'-') + $x - elm(b')')).opt() + range_per($x)).map(|(range, e3)| HashExpr {
+      range: Box::from(match range {
+        None => NoOp,
+        Some((e1, e2)) => RangeExpr {
+          from: Box::from(e1),
+          to: Box::from(e2),
+          per_option: Box::from(NoOp),
+        },
+      }),
+      per_option: Box::from(e3),
+    })
+  };
+}
+
 fn list(p: Parser<u8, Expr>) -> Parser<u8, Expr> {
-  p.of_many0_sep(elm(b',')).map(|e| match e {
-    e if e.len() == 1 => e.get(0).unwrap().clone(),
+  p.of_many1_sep(elm(b',')).map(|e| match e {
+    e if e.len() == 1 => e.first().unwrap().clone(),
     e => ListExpr(e),
   })
 }
 
 macro_rules! digit_instruction {
   ( $x:expr ) => {
-    asterisk_per($x).attempt() | asterisk().attempt() | list(range!($x).attempt() | $x)
+    question().attempt()
+      | asterisk_per($x).attempt()
+      | asterisk().attempt()
+      | list(hash!($x).attempt() | range!($x).attempt() | value_per!($x).attempt() | $x)
   };
 }
 
+fn whitespace<'a>() -> Parser<'a, u8, ()> {
+  elm_of(b" \t").of_many0().discard()
+}
+
+fn separator<'a>() -> Parser<'a, u8, ()> {
+  elm_of(b" \t").of_many1().discard()
+}
+
 fn instruction<'a>() -> Parser<'a, u8, Expr> {
-  (digit_instruction!(min_digit()) - elm(b' ') + digit_instruction!(hour_digit()) - elm(b' ')
-    + digit_instruction!(day_digit())
-    - elm(b' ')
-    + digit_instruction!(month_digit())
-    - elm(b' ')
-    + digit_instruction!(day_of_week_text().attempt() | day_of_week_digit()))
-  .map(|((((mins, hours), days), months), day_of_weeks)| CronExpr {
-    mins: Box::from(mins),
-    hours: Box::from(hours),
-    days: Box::from(days),
-    months: Box::from(months),
-    day_of_weeks: Box::from(day_of_weeks),
-  })
+  dialect_instruction(Dialect::Native)
+}
+
+fn dialect_instruction<'a>(dialect: Dialect) -> Parser<'a, u8, Expr> {
+  let secs = match dialect.seconds() {
+    FieldPresence::Absent => successful(NoOp),
+    FieldPresence::Optional => (digit_instruction!(min_digit()) - separator())
+      .attempt()
+      .opt()
+      .map(|e| e.unwrap_or(NoOp)),
+    FieldPresence::Required => digit_instruction!(min_digit()) - separator(),
+  };
+  let years = match dialect.years() {
+    FieldPresence::Absent => successful(NoOp),
+    FieldPresence::Optional => (separator() * digit_instruction!(year_digit()))
+      .attempt()
+      .opt()
+      .map(|e| e.unwrap_or(NoOp)),
+    FieldPresence::Required => separator() * digit_instruction!(year_digit()),
+  };
+  (secs + digit_instruction!(min_digit()) - separator() + digit_instruction!(hour_digit()) - separator()
+    + digit_instruction!(day())
+    - separator()
+    + digit_instruction!(month())
+    - separator()
+    + digit_instruction!(day_of_week(dialect))
+    + years)
+    .map(
+      |((((((secs, mins), hours), days), months), day_of_weeks), years)| CronExpr {
+        secs: Box::from(secs),
+        mins: Box::from(mins),
+        hours: Box::from(hours),
+        days: Box::from(days),
+        months: Box::from(months),
+        day_of_weeks: Box::from(day_of_weeks),
+        years: Box::from(years),
+        day_combination: DayCombination::Both,
+      },
+    )
+}
+
+fn rate<'a>() -> Parser<'a, u8, Expr> {
+  let value = elm_of(b"0123456789")
+    .of_many1()
+    .collect()
+    .map(|digits| std::str::from_utf8(digits).ok().and_then(|s| s.parse::<u32>().ok()));
+  let unit = seq(b"minutes").attempt().map(|_| (RateUnit::Minute, true))
+    | seq(b"minute").attempt().map(|_| (RateUnit::Minute, false))
+    | seq(b"hours").attempt().map(|_| (RateUnit::Hour, true))
+    | seq(b"hour").attempt().map(|_| (RateUnit::Hour, false))
+    | seq(b"days").attempt().map(|_| (RateUnit::Day, true))
+    | seq(b"day").map(|_| (RateUnit::Day, false));
+  let fail = |message: &str| {
+    failed(
+      ParseError::of_custom(0, None, message.to_string()),
+      CommittedStatus::Committed,
+    )
+  };
+  (seq(b"rate(") * whitespace() * (value - separator() + unit) - whitespace() - elm(b')')).flat_map(
+    move |(value, (unit, plural))| match value {
+      None | Some(0) => fail("rate: the value must be a positive integer"),
+      Some(1) if plural => fail("rate: the unit must be singular when the value is 1"),
+      Some(value) if value > 1 && !plural => fail("rate: the unit must be plural when the value is greater than 1"),
+      Some(value) => successful(RateExpr { value, unit }),
+    },
+  )
+}
+
+/// Parses an AWS schedule expression, which is `cron(...)`, `rate(...)` or the bare fields of `cron(...)`.<br/>
+/// AWSのスケジュール式を解析する。`cron(...)`、`rate(...)`または`cron(...)`のフィールドのみを受け付ける。
+fn aws_instruction<'a>() -> Parser<'a, u8, Expr> {
+  (seq(b"cron(") * whitespace() * dialect_instruction(Dialect::Aws) - whitespace() - elm(b')'))
+    | rate()
+    | dialect_instruction(Dialect::Aws)
+}
+
+/// Options that control which syntax CronParser accepts.<br/>
+/// CronParserが受け付ける構文を制御するオプション。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CronParserOptions {
+  /// Whether a range whose start is greater than its end, such as `22-2` or `FRI-MON`, is accepted as a wrap-around range.<br/>
+  /// `22-2`や`FRI-MON`のように開始が終了より大きい範囲を、折り返す範囲として受け付けるかどうか。
+  pub allow_wrap_around: bool,
+  /// Whether a step without a range, such as `5/15` or `MON/2`, is accepted.<br/>
+  /// `5/15`や`MON/2`のように範囲を伴わない間隔指定を受け付けるかどうか。
+  pub allow_start_step: bool,
+  /// Whether Jenkins style `H` is accepted.<br/>
+  /// Jenkins形式の`H`を受け付けるかどうか。
+  pub allow_hash: bool,
+  /// Whether `L` is accepted in the days and day of weeks fields, including `nL` in day of weeks.<br/>
+  /// 日と曜日のフィールドで`L`を受け付けるかどうか。曜日の`nL`を含む。
+  pub allow_last: bool,
+  /// Whether `?` is accepted in the days and day of weeks fields.<br/>
+  /// 日と曜日のフィールドで`?`を受け付けるかどうか。
+  pub allow_no_specific_value: bool,
+  /// Whether `nW` and `LW` are accepted in the days field.<br/>
+  /// 日のフィールドで`nW`と`LW`を受け付けるかどうか。
+  pub allow_nearest_weekday: bool,
+  /// Whether `n#k` is accepted in the day of weeks field.<br/>
+  /// 曜日のフィールドで`n#k`を受け付けるかどうか。
+  pub allow_nth_day_of_week: bool,
+}
+
+impl Default for CronParserOptions {
+  fn default() -> Self {
+    Self {
+      allow_wrap_around: true,
+      allow_start_step: true,
+      allow_hash: true,
+      allow_last: true,
+      allow_no_specific_value: false,
+      allow_nearest_weekday: true,
+      allow_nth_day_of_week: true,
+    }
+  }
+}
+
+impl CronParserOptions {
+  /// Returns options that reject everything but the classic syntax.<br/>
+  /// 従来の構文以外を拒否するオプションを返す。
+  pub fn strict() -> Self {
+    Self {
+      allow_wrap_around: false,
+      allow_start_step: false,
+      allow_hash: false,
+      allow_last: true,
+      allow_no_specific_value: false,
+      allow_nearest_weekday: false,
+      allow_nth_day_of_week: false,
+    }
+  }
+}
+
+fn validate_step(field: CronField, expr: &Expr, per: &Expr) -> Result<(), String> {
+  match per {
+    ValueExpr(0) => Err(format!("{}: step of zero in {} is not allowed", field.name(), expr)),
+    NoOp | ValueExpr(_) => Ok(()),
+    _ => Err(format!("{}: invalid step {}", field.name(), expr)),
+  }
+}
+
+fn validate_field(field: CronField, expr: &Expr, options: &CronParserOptions) -> Result<(), String> {
+  match expr {
+    RangeExpr { from, to, .. } if !matches!((from, to), (box ValueExpr(_), box ValueExpr(_))) => {
+      Err(format!("{}: invalid range {}", field.name(), expr))
+    }
+    PerExpr { digit, .. } if !matches!(digit, box ValueExpr(_) | box AnyValueExpr) => {
+      Err(format!("{}: invalid step {}", field.name(), expr))
+    }
+    RangeExpr {
+      from: box ValueExpr(from),
+      to: box ValueExpr(to),
+      ..
+    } if from > to && !options.allow_wrap_around => {
+      Err(format!("{}: wrap-around range {} is not allowed", field.name(), expr))
+    }
+    RangeExpr { per_option, .. } => validate_step(field, expr, per_option),
+    PerExpr {
+      digit: box ValueExpr(_),
+      ..
+    } if !options.allow_start_step => Err(format!(
+      "{}: step without a range {} is not allowed",
+      field.name(),
+      expr
+    )),
+    PerExpr { option, .. } => validate_step(field, expr, option),
+    HashExpr { .. } if !options.allow_hash => Err(format!("{}: H is not allowed", field.name())),
+    HashExpr { per_option, .. } => validate_step(field, expr, per_option),
+    LastValueExpr | LastDayOfWeekExpr(_) if !options.allow_last => Err(format!("{}: L is not allowed", field.name())),
+    NearestWeekdayExpr(_) | LastWeekdayExpr if !options.allow_nearest_weekday => {
+      Err(format!("{}: W is not allowed", field.name()))
+    }
+    NthDayOfWeekExpr { .. } if !options.allow_nth_day_of_week => Err(format!("{}: # is not allowed", field.name())),
+    ListExpr(list)
+      if list
+        .iter()
+        .any(|e| matches!(e, NearestWeekdayExpr(_) | LastWeekdayExpr)) =>
+    {
+      Err(format!("{}: W cannot be used in a list", field.name()))
+    }
+    ListExpr(list) if list.iter().any(|e| matches!(e, NthDayOfWeekExpr { .. })) => {
+      Err(format!("{}: # cannot be used in a list", field.name()))
+    }
+    NoSpecificValueExpr
+      if !options.allow_no_specific_value || !matches!(field, CronField::Day | CronField::DayOfWeek) =>
+    {
+      Err(format!("{}: ? is not allowed", field.name()))
+    }
+    ListExpr(list) => list.iter().try_for_each(|e| validate_field(field, e, options)),
+    _ => Ok(()),
+  }
+}
+
+fn validate(expr: &Expr, options: &CronParserOptions) -> Result<(), String> {
+  match expr {
+    CronExpr {
+      secs,
+      mins,
+      hours,
+      days,
+      months,
+      day_of_weeks,
+      years,
+      ..
+    } => [
+      (CronField::Second, secs),
+      (CronField::Minute, mins),
+      (CronField::Hour, hours),
+      (CronField::Day, days),
+      (CronField::Month, months),
+      (CronField::DayOfWeek, day_of_weeks),
+      (CronField::Year, years),
+    ]
+    .into_iter()
+    .try_for_each(|(field, e)| validate_field(field, e, options)),
+    _ => Ok(()),
+  }
+}
+
+fn validate_dialect(dialect: Dialect, expr: &Expr) -> Result<(), String> {
+  match expr {
+    CronExpr { days, day_of_weeks, .. }
+      if dialect.requires_no_specific_day()
+        && (**days == NoSpecificValueExpr) == (**day_of_weeks == NoSpecificValueExpr) =>
+    {
+      Err("days and day of weeks: exactly one of them must be ?".to_string())
+    }
+    _ => Ok(()),
+  }
+}
+
+/// Converts a day of week numbered from Sunday as 0, where 7 is also Sunday, to the native numbering.<br/>
+/// 日曜日を0(7も日曜日)とする曜日をネイティブの番号に変換する。
+fn from_sunday_zero(expr: &Expr) -> Expr {
+  let day_of_week = |n: u8| if n == 7 { 1 } else { n + 1 };
+  match expr {
+    ValueExpr(n) => ValueExpr(day_of_week(*n)),
+    LastDayOfWeekExpr(n) => LastDayOfWeekExpr(day_of_week(*n)),
+    NthDayOfWeekExpr { day_of_week: n, nth } => NthDayOfWeekExpr {
+      day_of_week: day_of_week(*n),
+      nth: *nth,
+    },
+    // `0-7` covers the whole week, while `n-7` ends on Sunday and becomes a wrap-around range.
+    RangeExpr {
+      from: box ValueExpr(0),
+      to: box ValueExpr(7),
+      per_option,
+    } => RangeExpr {
+      from: Box::from(ValueExpr(1)),
+      to: Box::from(ValueExpr(7)),
+      per_option: per_option.clone(),
+    },
+    RangeExpr { from, to, per_option } => RangeExpr {
+      from: Box::from(from_sunday_zero(from)),
+      to: Box::from(from_sunday_zero(to)),
+      per_option: per_option.clone(),
+    },
+    // `n/step` runs through Sunday as 7, which is the range `n-7/step`.
+    PerExpr {
+      digit: box ValueExpr(start),
+      option,
+    } => from_sunday_zero(&RangeExpr {
+      from: Box::from(ValueExpr(*start)),
+      to: Box::from(ValueExpr(7)),
+      per_option: option.clone(),
+    }),
+    ListExpr(list) => ListExpr(list.iter().map(from_sunday_zero).collect()),
+    e => e.clone(),
+  }
+}
+
+fn normalize(dialect: Dialect, expr: Expr) -> Expr {
+  match expr {
+    CronExpr {
+      secs,
+      mins,
+      hours,
+      days,
+      months,
+      day_of_weeks,
+      years,
+      ..
+    } => CronExpr {
+      day_combination: dialect.day_combination(&days, &day_of_weeks),
+      day_of_weeks: match dialect.sunday() {
+        0 => Box::from(from_sunday_zero(&day_of_weeks)),
+        _ => day_of_weeks,
+      },
+      secs,
+      mins,
+      hours,
+      days,
+      months,
+      years,
+    },
+    e => e,
+  }
 }
 
 pub struct CronParser;
 
 impl CronParser {
-  pub fn parse(source: &str) -> ParseResult<u8, Expr> {
-    (instruction() - end()).parse(source.as_bytes())
+  pub fn parse(source: &str) -> ParseResult<'_, u8, Expr> {
+    Self::parse_with_options(source, CronParserOptions::default())
+  }
+
+  /// Parses a CROND string, accepting only the syntax enabled by the options.<br/>
+  /// オプションで有効にした構文のみを受け付けてCROND文字列を解析する。
+  ///
+  /// Fields may be separated by any run of spaces and tabs, and surrounding whitespace is ignored.
+  /// Error offsets are relative to the original source.<br/>
+  /// フィールドは空白とタブの並びで区切ることができ、前後の空白は無視する。エラーの位置は元の文字列に対する位置となる。
+  pub fn parse_with_options(source: &str, options: CronParserOptions) -> ParseResult<'_, u8, Expr> {
+    Self::parse_dialect(Dialect::Native, source, options)
+  }
+
+  /// Parses a cron string written in the dialect. See `Dialect` for the syntax of each dialect.<br/>
+  /// 方言で書かれたcron文字列を解析する。各方言の構文は`Dialect`を参照。
+  pub fn parse_with(dialect: Dialect, source: &str) -> ParseResult<'_, u8, Expr> {
+    let source = match nickname(source.trim()) {
+      Some(expanded) if dialect.supports_nicknames() => expanded,
+      _ => source,
+    };
+    Self::parse_dialect(dialect, source, dialect.options())
+  }
+
+  fn parse_dialect(dialect: Dialect, source: &str, options: CronParserOptions) -> ParseResult<'_, u8, Expr> {
+    let instruction = match dialect {
+      Dialect::Aws => aws_instruction(),
+      dialect => dialect_instruction(dialect),
+    };
+    match (whitespace() * instruction - whitespace() - end()).parse(source.as_bytes()) {
+      ParseResult::Success { value, length } => {
+        match validate(&value, &options).and_then(|_| validate_dialect(dialect, &value)) {
+          Ok(()) => ParseResult::successful(normalize(dialect, value), length),
+          Err(message) => ParseResult::failed_with_commit(ParseError::of_custom(0, None, message)),
+        }
+      }
+      failure => failure,
+    }
   }
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::redundant_closure)]
 mod tests {
   use super::*;
 
@@ -134,22 +547,28 @@ mod tests {
     assert_eq!(
       result,
       CronExpr {
+        secs: Box::from(NoOp),
         mins: Box::from(AnyValueExpr),
         hours: Box::from(AnyValueExpr),
         days: Box::from(AnyValueExpr),
         months: Box::from(AnyValueExpr),
-        day_of_weeks: Box::from(AnyValueExpr)
+        day_of_weeks: Box::from(AnyValueExpr),
+        years: Box::from(NoOp),
+        day_combination: DayCombination::Both,
       }
     );
     let result = (instruction() - end()).parse(b"1 1 1 1 1").to_result().unwrap();
     assert_eq!(
       result,
       CronExpr {
+        secs: Box::from(NoOp),
         mins: Box::from(ValueExpr(1)),
         hours: Box::from(ValueExpr(1)),
         days: Box::from(ValueExpr(1)),
         months: Box::from(ValueExpr(1)),
-        day_of_weeks: Box::from(ValueExpr(1))
+        day_of_weeks: Box::from(ValueExpr(1)),
+        years: Box::from(NoOp),
+        day_combination: DayCombination::Both,
       }
     );
   }
@@ -196,6 +615,216 @@ mod tests {
     assert_eq!(result, ValueExpr(1));
   }
 
+  #[test]
+  fn test_value_per() {
+    let result = (digit_instruction!(min_digit()) - end())
+      .parse(b"5/15")
+      .to_result()
+      .unwrap();
+    assert_eq!(
+      result,
+      PerExpr {
+        digit: Box::from(ValueExpr(5)),
+        option: Box::from(ValueExpr(15))
+      }
+    );
+    let result = (digit_instruction!(day_of_week(Dialect::Native)) - end())
+      .parse(b"MON/2")
+      .to_result()
+      .unwrap();
+    assert_eq!(
+      result,
+      PerExpr {
+        digit: Box::from(ValueExpr(2)),
+        option: Box::from(ValueExpr(2))
+      }
+    );
+    let result = CronParser::parse("0,5/15 1/6 2/7 3/3 MON/2").to_result().unwrap();
+    assert_eq!(result.to_string(), "0,5/15 1/6 2/7 3/3 2/2");
+    let result = CronParser::parse_with_options("5/15 * * * *", CronParserOptions::strict()).to_result();
+    assert!(result.is_err());
+  }
+
+  #[test]
+  fn test_wrap_around() {
+    let result = CronParser::parse("0 22-2/2 * * FRI-MON").to_result().unwrap();
+    assert_eq!(result.to_string(), "0 22-2/2 * * 6-2");
+    let result = CronParser::parse_with_options("0 22-2 * * *", CronParserOptions::strict()).to_result();
+    assert_eq!(
+      result.unwrap_err().to_string(),
+      "hours: wrap-around range 22-2 is not allowed at 0"
+    );
+    let result = CronParser::parse_with_options("0 1,22-2 * * *", CronParserOptions::strict()).to_result();
+    assert!(result.is_err());
+    let result = CronParser::parse_with_options("0 2-22 * * MON-FRI", CronParserOptions::strict()).to_result();
+    assert!(result.is_ok());
+  }
+
+  #[test]
+  fn test_whitespace() {
+    for source in ["0  9 * *\t1-5", "\t 0 9 * * 1-5  ", "0\t\t9 *  * 1-5"] {
+      let result = CronParser::parse(source).to_result().unwrap();
+      assert_eq!(result.to_string(), "0 9 * * 1-5", "{:?}", source);
+    }
+    assert!(CronParser::parse("0 9 * *").to_result().is_err());
+    assert!(CronParser::parse("0 9 * * ").to_result().is_err());
+    assert!(CronParser::parse("0 9 * *\t").to_result().is_err());
+    assert!(CronParser::parse("0 9 * * 1-5 *").to_result().is_err());
+    // Offsets point into the original source, including the leading whitespace
+    assert_eq!(
+      CronParser::parse("  0 9 * * X").to_result().unwrap_err().to_string(),
+      "Mismatch at 9: expect repeat at least 1 times, found 0 times"
+    );
+  }
+
+  #[test]
+  fn test_calendar_modifiers() {
+    let result = CronParser::parse("0 0 LW * *").to_result().unwrap();
+    assert_eq!(result.to_string(), "0 0 LW * *");
+    for s in [
+      "0 0 15W * *",
+      "0 0 L * *",
+      "0 0 * * 6L",
+      "0 0 * * 6#3",
+      "0 0 * JAN-MAR,DEC *",
+    ] {
+      assert!(CronParser::parse(s).to_result().is_ok(), "{}", s);
+    }
+    assert_eq!(
+      CronParser::parse("0 0 * * FRI#3").to_result().unwrap().to_string(),
+      "0 0 * * 6#3"
+    );
+    assert_eq!(
+      CronParser::parse("0 0 * JAN-MAR,DEC *")
+        .to_result()
+        .unwrap()
+        .to_string(),
+      "0 0 * 1-3,12 *"
+    );
+    for s in [
+      "0 0 1W,15 * *",
+      "0 0 * * 6#3,2",
+      "0 0 * * 6#6",
+      "0 0 L-5 * *",
+      "0 0 1W-5 * *",
+    ] {
+      assert!(CronParser::parse(s).to_result().is_err(), "{}", s);
+    }
+    assert_eq!(
+      CronParser::parse_with_options("0 0 15W * *", CronParserOptions::strict())
+        .to_result()
+        .unwrap_err()
+        .to_string(),
+      "days: W is not allowed at 0"
+    );
+  }
+
+  #[test]
+  fn test_aws() {
+    let parse = |s| {
+      CronParser::parse_with(Dialect::Aws, s)
+        .to_result()
+        .map(|e| e.to_string())
+        .map_err(|e| e.to_string())
+    };
+    assert_eq!(parse("cron(0 12 ? * MON-FRI *)"), Ok("0 12 ? * 2-6 *".to_string()));
+    assert_eq!(
+      parse("cron(15 10 ? * 6L 2022-2023)"),
+      Ok("15 10 ? * 6L 2022-2023".to_string())
+    );
+    assert_eq!(parse("cron(0 8 1W * ? *)"), Ok("0 8 1W * ? *".to_string()));
+    assert_eq!(parse("cron(0 10 ? * 2#1 *)"), Ok("0 10 ? * 2#1 *".to_string()));
+    assert_eq!(parse("rate(5 minutes)"), Ok("rate(5 minutes)".to_string()));
+    assert_eq!(parse("rate(1 hour)"), Ok("rate(1 hour)".to_string()));
+    assert_eq!(parse("rate(7 days)"), Ok("rate(7 days)".to_string()));
+    assert_eq!(
+      parse("rate(0 minutes)"),
+      Err("rate: the value must be a positive integer at 0".to_string())
+    );
+    assert_eq!(
+      parse("rate(1 hours)"),
+      Err("rate: the unit must be singular when the value is 1 at 0".to_string())
+    );
+    assert_eq!(
+      parse("rate(5 hour)"),
+      Err("rate: the unit must be plural when the value is greater than 1 at 0".to_string())
+    );
+    assert_eq!(
+      parse("cron(0 12 * * MON *)"),
+      Err("days and day of weeks: exactly one of them must be ? at 0".to_string())
+    );
+    for s in [
+      "cron(0 12 * * ? *",
+      "cron(0 12 ? * MON)",
+      "cron(0 12 ? * MON-FRI )",
+      "0 12 ? * MON-FRI ",
+      "rate(5 seconds)",
+      "rate(5)",
+      "cron(0 12 ? * 2#6 *)",
+    ] {
+      assert!(parse(s).is_err(), "{}", s);
+    }
+    assert!(CronParser::parse("rate(5 minutes)").to_result().is_err());
+  }
+
+  #[test]
+  fn test_step_bounds() {
+    for source in ["*/0 * * * *", "* 1-5/0 * * *", "* * 5/0 * *", "H/0 * * * *"] {
+      assert!(CronParser::parse(source).to_result().is_err(), "{}", source);
+    }
+    let result = CronParser::parse("*/0 * * * *").to_result();
+    assert_eq!(
+      result.unwrap_err().to_string(),
+      "minutes: step of zero in */0 is not allowed at 0"
+    );
+    assert!(CronParser::parse("* * * */13 *").to_result().is_err());
+    assert!(CronParser::parse("*/59 */23 */31 */12 */7").to_result().is_ok());
+    assert!(CronParser::parse("* * * * */8").to_result().is_err());
+  }
+
+  #[test]
+  fn test_hash() {
+    let result = (digit_instruction!(min_digit()) - end())
+      .parse(b"H")
+      .to_result()
+      .unwrap();
+    assert_eq!(
+      result,
+      HashExpr {
+        range: Box::from(NoOp),
+        per_option: Box::from(NoOp)
+      }
+    );
+    let result = (digit_instruction!(min_digit()) - end())
+      .parse(b"H/15")
+      .to_result()
+      .unwrap();
+    assert_eq!(
+      result,
+      HashExpr {
+        range: Box::from(NoOp),
+        per_option: Box::from(ValueExpr(15))
+      }
+    );
+    let result = (digit_instruction!(min_digit()) - end())
+      .parse(b"H(0-29)/10")
+      .to_result()
+      .unwrap();
+    assert_eq!(
+      result,
+      HashExpr {
+        range: Box::from(RangeExpr {
+          from: Box::from(ValueExpr(0)),
+          to: Box::from(ValueExpr(29)),
+          per_option: Box::from(NoOp)
+        }),
+        per_option: Box::from(ValueExpr(10))
+      }
+    );
+    let result = (digit_instruction!(min_digit()) - end()).parse(b"H(0-60)").to_result();
+    assert!(result.is_err());
+  }
+
   #[test]
   fn test_list() {
     let s = (0..=59).map(|v| v.to_string()).collect::<Vec<_>>().join(",");
@@ -1,10 +1,15 @@
 use std::marker::PhantomData;
 
 use anyhow::{anyhow, Result};
-use chrono::{DateTime, TimeZone};
+use chrono::{DateTime, Duration, TimeZone};
 use intervals_rs::LimitValue;
 
-use crate::{CronInterval, CronIntervalIterator, CronParser, CronSpecification, Expr};
+use crate::compiled_schedule::CompiledSchedule;
+use crate::cron_interval::align_up;
+use crate::{
+  AndSpecification, Clock, CronHashResolver, CronInterval, CronIntervalIterator, CronIntervalReverseIterator,
+  CronParser, CronSpecification, Dialect, Expr, OccurrenceStatistics, Specification, SpecificationExt,
+};
 
 /// Facade that returns a CronInterval or CronIntervalIterator from a CROND string.<br/>
 /// CROND文字列からCronIntervalやCronIntervalIteratorを返すFacade。
@@ -27,26 +32,92 @@ impl<Tz: TimeZone> CronSchedule<Tz> {
   /// # Return values(戻り値)
   ///
   /// * Err:
-  ///   - If CrondParser::parse fails
-  ///   - CrondParser::parseに失敗した場合
+  ///   - If CrondParser::parse fails, or the string contains `H`
+  ///   - CrondParser::parseに失敗した場合、または文字列が`H`を含む場合
   /// * Ok
   ///   - If CrondParser::parse succeeds
   ///   - CrondParser::parseに成功した場合
   pub fn new(crond_string: &str) -> Result<Self> {
-    let result = CronParser::parse(crond_string).to_result();
-    if result.is_err() {
-      return Err(anyhow!("Failed to parse crond string: {}", result.err().unwrap()));
+    Self::with_dialect(Dialect::Native, crond_string)
+  }
+
+  /// The Factory method for a cron string written in a dialect such as `Dialect::Quartz`.<br/>
+  /// `Dialect::Quartz`などの方言で書かれたcron文字列のためのファクトリメソッド。
+  ///
+  /// # Arguments(引数)
+  ///
+  /// * dialect - The dialect of the string(文字列の方言)
+  /// * crond_string - CROND形式文字列
+  ///
+  /// # Return values(戻り値)
+  ///
+  /// * Err:
+  ///   - If CrondParser::parse_with fails, or the string contains `H`
+  ///   - CrondParser::parse_withに失敗した場合、または文字列が`H`を含む場合
+  /// * Ok
+  ///   - If CrondParser::parse_with succeeds
+  ///   - CrondParser::parse_withに成功した場合
+  pub fn with_dialect(dialect: Dialect, crond_string: &str) -> Result<Self> {
+    let expr = Self::parse(dialect, crond_string)?;
+    if CronHashResolver::contains_hash(&expr) {
+      return Err(anyhow!(
+        "Failed to parse crond string: H requires a seed, use CronSchedule::new_with_seed"
+      ));
     }
     Ok(Self {
-      expr: result.unwrap(),
+      expr,
       phantom: PhantomData,
     })
   }
 
+  /// The Factory method that resolves Jenkins style `H` using a seed such as a job name.<br/>
+  /// ジョブ名などのシードを使ってJenkins形式の`H`を解決するファクトリメソッド。
+  ///
+  /// # Arguments(引数)
+  ///
+  /// * crond_string - CROND形式文字列
+  /// * seed - The key the hashed values are derived from(`H`の値を導出するキー)
+  ///
+  /// # Return values(戻り値)
+  ///
+  /// * Err:
+  ///   - If CrondParser::parse fails
+  ///   - CrondParser::parseに失敗した場合
+  /// * Ok
+  ///   - If CrondParser::parse succeeds
+  ///   - CrondParser::parseに成功した場合
+  pub fn new_with_seed(crond_string: &str, seed: &str) -> Result<Self> {
+    let expr = Self::parse(Dialect::Native, crond_string)?;
+    Ok(Self {
+      expr: CronHashResolver::new(seed).resolve(&expr),
+      phantom: PhantomData,
+    })
+  }
+
+  fn parse(dialect: Dialect, crond_string: &str) -> Result<Expr> {
+    CronParser::parse_with(dialect, crond_string)
+      .to_result()
+      .map_err(|e| anyhow!("Failed to parse crond string: {}", e))
+  }
+
+  /// Returns the Expr of this schedule, with any `H` already resolved.<br/>
+  /// このスケジュールのExprを返す。`H`は解決済みである。
+  pub fn expr(&self) -> &Expr {
+    &self.expr
+  }
+
+  /// Returns the CronSpecification of this schedule, which can be composed with other specifications.
+  /// A `rate(...)` schedule counts from the Unix epoch unless anchored with `CronSpecification::anchored_at`.<br/>
+  /// 他の仕様と合成できる、このスケジュールのCronSpecificationを返す。
+  /// `rate(...)`のスケジュールは`CronSpecification::anchored_at`で起点を指定しない限りUnixエポックから数える。
+  pub fn specification(&self) -> CronSpecification {
+    CronSpecification::new(self.expr.clone())
+  }
+
   /// Returns a CronInterval with date and time candidates after the start date and time.<br/>
   /// 開始日時以降の日時候補を持つCronIntervalを返す。
   pub fn cron_interval(&self, start: DateTime<Tz>) -> CronInterval<Tz, CronSpecification> {
-    let spec = CronSpecification::new(self.expr.clone());
+    let spec = self.specification().anchored_at(&start);
     let start = LimitValue::Limit(start);
     let end = LimitValue::Limitless;
     CronInterval::new(start, end, spec)
@@ -57,11 +128,161 @@ impl<Tz: TimeZone> CronSchedule<Tz> {
   pub fn upcoming(&self, start: DateTime<Tz>) -> CronIntervalIterator<Tz, CronSpecification> {
     self.cron_interval(start.clone()).iter(start.timezone())
   }
+
+  /// Returns a CronIntervalReverseIterator with the date and time candidates strictly before the end date and time, latest first.
+  /// A `rate(...)` schedule counts from the Unix epoch.<br/>
+  /// 終了日時より前の日時候補を新しい順に持つCronIntervalReverseIteratorを返す。
+  /// `rate(...)`のスケジュールはUnixエポックから数える。
+  pub fn preceding(&self, end: DateTime<Tz>) -> CronIntervalReverseIterator<Tz, CronSpecification> {
+    let timezone = end.timezone();
+    let end = end - Duration::nanoseconds(1);
+    CronInterval::new(LimitValue::Limitless, LimitValue::Limit(end), self.specification()).iter_rev(timezone)
+  }
+
+  /// Returns the occurrences after the last run up to and including now, oldest first.
+  /// A `rate(...)` schedule counts from the last run.<br/>
+  /// 最後の実行より後、現在日時までの実行日時を古い順に返す。`rate(...)`のスケジュールは最後の実行から数える。
+  pub fn missed(&self, last_run: DateTime<Tz>, now: DateTime<Tz>) -> Vec<DateTime<Tz>> {
+    let spec = self.specification().anchored_at(&last_run);
+    let start = match &self.expr {
+      Expr::RateExpr { .. } => last_run,
+      _ => align_up(
+        last_run + Duration::milliseconds(1),
+        Specification::<DateTime<Tz>>::granularity(&spec),
+      ),
+    };
+    if start > now {
+      return vec![];
+    }
+    let timezone = start.timezone();
+    CronInterval::new(LimitValue::Limit(start), LimitValue::Limit(now), spec)
+      .iter(timezone)
+      .collect()
+  }
+
+  /// Returns the next occurrence after the current date and time of the clock, or `None` if there is none.
+  /// A `rate(...)` schedule counts from now, as `upcoming` does.<br/>
+  /// 時計の現在日時より後の次の実行日時を返す。ない場合は`None`を返す。
+  /// `rate(...)`のスケジュールは`upcoming`と同様に現在日時から数える。
+  pub fn next_after_now<C: Clock<Tz>>(&self, clock: &C) -> Option<DateTime<Tz>> {
+    self.next_after(clock.now())
+  }
+
+  /// Returns whether an occurrence after the last run has come by the current date and time of the clock, which is
+  /// never the case for a schedule with no occurrence after the last run.
+  /// A `rate(...)` schedule counts from the last run, as `missed` does.<br/>
+  /// 最後の実行より後の実行日時が、時計の現在日時までに来ているかどうかを返す。
+  /// 最後の実行より後に実行日時がないスケジュールでは常に`false`となる。
+  /// `rate(...)`のスケジュールは`missed`と同様に最後の実行から数える。
+  pub fn is_due<C: Clock<Tz>>(&self, clock: &C, last_run: DateTime<Tz>) -> bool {
+    self.next_after(last_run).is_some_and(|next| next <= clock.now())
+  }
+
+  /// Returns the first occurrence strictly after the date and time, or `None` if there is none, such as after the
+  /// last year of the years field. Unlike `upcoming`, the search is bounded. A `rate(...)` schedule counts from the
+  /// date and time.<br/>
+  /// 指定した日時より後の最初の実行日時を返す。年のフィールドの最後の年より後など、実行日時がない場合は`None`を返す。
+  /// `upcoming`と異なり探索は有限である。`rate(...)`のスケジュールは指定した日時から数える。
+  pub fn next_after(&self, after: DateTime<Tz>) -> Option<DateTime<Tz>> {
+    self.next_after_anchored(&after, &after)
+  }
+
+  /// Returns the first occurrence strictly after `after`, counting a `rate(...)` from the anchor.
+  pub(crate) fn next_after_anchored(&self, anchor: &DateTime<Tz>, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+    match &self.expr {
+      Expr::RateExpr { value, unit } => {
+        let period = *value as i64 * unit.seconds();
+        let periods = (after.clone() - anchor.clone()).num_seconds().div_euclid(period) + 1;
+        Some(anchor.clone() + Duration::seconds(periods * period))
+      }
+      expr => match CompiledSchedule::new(expr) {
+        Some(compiled) => compiled.next_after(after),
+        None => {
+          let granularity = Specification::<DateTime<Tz>>::granularity(&self.specification());
+          let start = align_up(after.clone() + Duration::milliseconds(1), granularity);
+          self.upcoming(start).next()
+        }
+      },
+    }
+  }
+
+  /// Returns the last occurrence strictly before the date and time, or `None` if there is none, such as before the
+  /// first year of the years field. Unlike `preceding`, the search is bounded. A `rate(...)` schedule counts from the
+  /// Unix epoch, as `preceding` does.<br/>
+  /// 指定した日時より前の最後の実行日時を返す。年のフィールドの最初の年より前など、実行日時がない場合は`None`を返す。
+  /// `preceding`と異なり探索は有限である。`rate(...)`のスケジュールは`preceding`と同様にUnixエポックから数える。
+  pub fn previous_before(&self, before: DateTime<Tz>) -> Option<DateTime<Tz>> {
+    match &self.expr {
+      Expr::RateExpr { value, unit } => {
+        let period = *value as i64 * unit.seconds();
+        let seconds = before.timestamp() - if before.timestamp_subsec_nanos() == 0 { 1 } else { 0 };
+        let periods = seconds.div_euclid(period);
+        (periods > 0).then(|| before.timezone().timestamp_opt(periods * period, 0).unwrap())
+      }
+      expr => match CompiledSchedule::new(expr) {
+        Some(compiled) => compiled.previous_before(&before),
+        None => self.preceding(before).next(),
+      },
+    }
+  }
+
+  /// Returns the number of occurrences in `[start, end)`, counted arithmetically from the days and times of day of
+  /// the expression, so that long windows take no longer than short ones.
+  /// A `rate(...)` schedule counts from the start, as `upcoming` does.<br/>
+  /// `[start, end)`の実行回数を返す。式の日と時刻から算術的に数えるため、長い期間でも短い期間と同程度の時間で済む。
+  /// `rate(...)`のスケジュールは`upcoming`と同様に開始日時から数える。
+  pub fn count_between(&self, start: DateTime<Tz>, end: DateTime<Tz>) -> u64 {
+    match &self.expr {
+      Expr::RateExpr { value, unit } => {
+        let span = (end - start).num_milliseconds();
+        let period = *value as i64 * unit.seconds() * 1000;
+        if span > 0 {
+          ((span - 1) / period) as u64
+        } else {
+          0
+        }
+      }
+      expr => CompiledSchedule::new(expr).map_or(0, |compiled| compiled.count_between(&start, &end)),
+    }
+  }
+
+  /// Returns the number, spacing and distribution of the occurrences in `[start, end)`, computed from the days and
+  /// times of day of the expression rather than by iterating over every occurrence.<br/>
+  /// `[start, end)`の実行回数、間隔、分布を返す。全ての実行日時を列挙せず、式の日と時刻から計算する。
+  ///
+  /// # Return values(戻り値)
+  ///
+  /// * Err:
+  ///   - If the schedule is a `rate(...)`
+  ///   - スケジュールが`rate(...)`の場合
+  /// * Ok
+  ///   - Otherwise
+  ///   - それ以外の場合
+  pub fn statistics(&self, start: DateTime<Tz>, end: DateTime<Tz>) -> Result<OccurrenceStatistics<Tz>> {
+    let compiled = CompiledSchedule::new(&self.expr)
+      .ok_or_else(|| anyhow!("Failed to compute statistics: {} is not a cron expression", self.expr))?;
+    Ok(OccurrenceStatistics::compute(&compiled, &start, &end))
+  }
+
+  /// Returns a CronIntervalIterator with the date and time candidates after the start date and time that also satisfy the filter, e.g. a Calendar.<br/>
+  /// 開始日時以降の日時候補のうち、Calendarなどのフィルタも満たすものを持つCronIntervalIteratorを返す。
+  pub fn upcoming_filtered<S>(
+    &self,
+    start: DateTime<Tz>,
+    filter: S,
+  ) -> CronIntervalIterator<Tz, AndSpecification<CronSpecification, S>>
+  where
+    S: Specification<DateTime<Tz>>, {
+    let spec = self.specification().anchored_at(&start).and(filter);
+    let interval = CronInterval::new(LimitValue::Limit(start.clone()), LimitValue::Limitless, spec);
+    interval.iter(start.timezone())
+  }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::ManualClock;
   use chrono::{TimeZone, Utc};
 
   #[test]
@@ -82,4 +303,177 @@ mod tests {
     // 2021-01-01T04:30:00Z
     // 2021-01-01T06:00:00Z
   }
+
+  #[test]
+  fn test_new_with_seed() {
+    assert!(CronSchedule::<Utc>::new("H * * * *").is_err());
+    let schedule = CronSchedule::<Utc>::new_with_seed("H/15 * * * *", "nightly-backup").unwrap();
+    assert_eq!(schedule.expr().to_string(), "1-59/15 * * * *");
+    let dt = Utc.with_ymd_and_hms(2021, 1, 1, 1, 0, 0).unwrap();
+    let dt_vec = schedule.upcoming(dt).take(2).collect::<Vec<_>>();
+    assert_eq!(dt_vec[0], Utc.with_ymd_and_hms(2021, 1, 1, 1, 1, 0).unwrap());
+    assert_eq!(dt_vec[1], Utc.with_ymd_and_hms(2021, 1, 1, 1, 16, 0).unwrap());
+  }
+
+  #[test]
+  fn test_aws() {
+    // 2021-01-01 is a Friday
+    let dt = Utc.with_ymd_and_hms(2021, 1, 1, 12, 0, 0).unwrap();
+    let schedule = CronSchedule::<Utc>::with_dialect(Dialect::Aws, "cron(0 12 ? * MON-FRI *)").unwrap();
+    let dt_vec = schedule.upcoming(dt).take(2).collect::<Vec<_>>();
+    assert_eq!(dt_vec[0], Utc.with_ymd_and_hms(2021, 1, 1, 12, 0, 0).unwrap());
+    assert_eq!(dt_vec[1], Utc.with_ymd_and_hms(2021, 1, 4, 12, 0, 0).unwrap());
+
+    let schedule = CronSchedule::<Utc>::with_dialect(Dialect::Aws, "rate(90 minutes)").unwrap();
+    let dt = Utc.with_ymd_and_hms(2021, 1, 1, 12, 7, 30).unwrap();
+    let dt_vec = schedule.upcoming(dt).take(2).collect::<Vec<_>>();
+    assert_eq!(dt_vec[0], Utc.with_ymd_and_hms(2021, 1, 1, 13, 37, 30).unwrap());
+    assert_eq!(dt_vec[1], Utc.with_ymd_and_hms(2021, 1, 1, 15, 7, 30).unwrap());
+  }
+
+  #[test]
+  fn test_next_after_now_and_is_due() {
+    let clock = ManualClock::new(Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap());
+    let schedule = CronSchedule::<Utc>::new("30 9 * * 2-6").unwrap();
+    let mut runs = vec![];
+    let mut last_run = clock.now();
+    // Two weeks in steps of ten minutes
+    for _ in 0..(14 * 24 * 6) {
+      clock.advance(Duration::minutes(10));
+      if schedule.is_due(&clock, last_run) {
+        last_run = clock.now();
+        runs.push(last_run);
+      }
+    }
+    assert_eq!(runs.len(), 10);
+    assert_eq!(runs[0], Utc.with_ymd_and_hms(2021, 1, 1, 9, 30, 0).unwrap());
+    assert_eq!(runs[1], Utc.with_ymd_and_hms(2021, 1, 4, 9, 30, 0).unwrap());
+    assert_eq!(
+      schedule.next_after_now(&clock),
+      Some(Utc.with_ymd_and_hms(2021, 1, 15, 9, 30, 0).unwrap())
+    );
+
+    // Exactly at an occurrence, the next one is the one after it
+    clock.set(Utc.with_ymd_and_hms(2021, 1, 15, 9, 30, 0).unwrap());
+    assert_eq!(
+      schedule.next_after_now(&clock),
+      Some(Utc.with_ymd_and_hms(2021, 1, 18, 9, 30, 0).unwrap())
+    );
+    assert!(!schedule.is_due(&clock, clock.now()));
+
+    let rate = CronSchedule::<Utc>::with_dialect(Dialect::Aws, "rate(5 minutes)").unwrap();
+    let last_run = clock.now();
+    clock.advance(Duration::minutes(4));
+    assert!(!rate.is_due(&clock, last_run));
+    assert_eq!(rate.next_after_now(&clock), Some(clock.now() + Duration::minutes(5)));
+    clock.advance(Duration::minutes(1));
+    assert!(rate.is_due(&clock, last_run));
+
+    // Schedules with no further occurrence
+    let past = CronSchedule::<Utc>::with_dialect(Dialect::Quartz, "0 0 0 1 1 ? 2020").unwrap();
+    let never = CronSchedule::<Utc>::new("0 0 30 2 *").unwrap();
+    for schedule in [past, never] {
+      assert_eq!(schedule.next_after_now(&clock), None);
+      assert!(!schedule.is_due(&clock, clock.now() - Duration::days(365)));
+    }
+  }
+
+  #[test]
+  fn test_preceding() {
+    let schedule = CronSchedule::<Utc>::new("0 9 * * *").unwrap();
+    let dt = Utc.with_ymd_and_hms(2021, 1, 2, 9, 0, 0).unwrap();
+    let dt_vec = schedule.preceding(dt).take(2).collect::<Vec<_>>();
+    assert_eq!(dt_vec[0], Utc.with_ymd_and_hms(2021, 1, 1, 9, 0, 0).unwrap());
+    assert_eq!(dt_vec[1], Utc.with_ymd_and_hms(2020, 12, 31, 9, 0, 0).unwrap());
+
+    let dt = Utc.with_ymd_and_hms(2021, 1, 2, 9, 0, 30).unwrap();
+    let dt_vec = schedule.preceding(dt).take(1).collect::<Vec<_>>();
+    assert_eq!(dt_vec[0], Utc.with_ymd_and_hms(2021, 1, 2, 9, 0, 0).unwrap());
+  }
+
+  #[test]
+  fn test_previous_before() {
+    let schedule = CronSchedule::<Utc>::new("0 9 * * *").unwrap();
+    let dt = Utc.with_ymd_and_hms(2021, 1, 2, 9, 0, 0).unwrap();
+    assert_eq!(schedule.previous_before(dt), schedule.preceding(dt).next());
+    assert_eq!(
+      schedule.previous_before(dt + Duration::seconds(30)),
+      Some(Utc.with_ymd_and_hms(2021, 1, 2, 9, 0, 0).unwrap())
+    );
+    assert_eq!(
+      CronSchedule::<Utc>::new("0 0 30 2 *").unwrap().previous_before(dt),
+      None
+    );
+
+    let schedule = CronSchedule::<Utc>::with_dialect(Dialect::Aws, "rate(5 minutes)").unwrap();
+    assert_eq!(schedule.previous_before(dt), schedule.preceding(dt).next());
+    assert_eq!(
+      schedule.previous_before(dt + Duration::seconds(1)),
+      Some(Utc.with_ymd_and_hms(2021, 1, 2, 9, 0, 0).unwrap())
+    );
+    assert_eq!(schedule.previous_before(Utc.timestamp_opt(300, 0).unwrap()), None);
+  }
+
+  #[test]
+  fn test_count_between() {
+    // Checks the count against the iterator on windows that start and end mid-day and mid-month
+    let windows = [
+      (
+        Utc.with_ymd_and_hms(2021, 1, 15, 10, 7, 0).unwrap(),
+        Utc.with_ymd_and_hms(2021, 4, 2, 3, 0, 0).unwrap(),
+      ),
+      (
+        Utc.with_ymd_and_hms(2024, 2, 27, 0, 0, 0).unwrap(),
+        Utc.with_ymd_and_hms(2024, 3, 2, 0, 0, 0).unwrap(),
+      ),
+    ];
+    for s in [
+      "0 */4 * * *",
+      "15,45 9-17 * * 2-6",
+      "0 0 29-31 * *",
+      "0 12 L * *",
+      "0 12 15W * *",
+      "0 8 * * 6#2",
+      "0 0 1-10/3,28-31 * *",
+    ] {
+      let schedule = CronSchedule::<Utc>::new(s).unwrap();
+      for (start, end) in windows {
+        let expected = schedule.upcoming(start).take_while(|d| *d < end).count() as u64;
+        assert_eq!(schedule.count_between(start, end), expected, "{}", s);
+      }
+    }
+    let schedule = CronSchedule::<Utc>::with_dialect(Dialect::Vixie, "0 0 13 * 5").unwrap();
+    let (start, end) = windows[0];
+    let expected = schedule.upcoming(start).take_while(|d| *d < end).count() as u64;
+    assert_eq!(schedule.count_between(start, end), expected);
+
+    let start = Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap();
+    let end = Utc.with_ymd_and_hms(2031, 1, 1, 0, 0, 0).unwrap();
+    assert_eq!(
+      CronSchedule::<Utc>::new("* * * * *").unwrap().count_between(start, end),
+      3652 * 24 * 60
+    );
+    let schedule = CronSchedule::<Utc>::with_dialect(Dialect::Aws, "rate(90 minutes)").unwrap();
+    assert_eq!(schedule.count_between(start, start + Duration::hours(3)), 1);
+    assert_eq!(schedule.count_between(start, start + Duration::minutes(181)), 2);
+  }
+
+  #[test]
+  fn test_count_between_dst() {
+    use chrono_tz::Europe::Berlin;
+    let schedule = CronSchedule::<chrono_tz::Tz>::new("30 * * * *").unwrap();
+    for (start, end) in [
+      (
+        Berlin.with_ymd_and_hms(2021, 3, 20, 12, 0, 0).unwrap(),
+        Berlin.with_ymd_and_hms(2021, 4, 3, 0, 0, 0).unwrap(),
+      ),
+      (
+        Berlin.with_ymd_and_hms(2021, 10, 1, 0, 0, 0).unwrap(),
+        Berlin.with_ymd_and_hms(2021, 11, 1, 0, 0, 0).unwrap(),
+      ),
+    ] {
+      let expected = schedule.upcoming(start).take_while(|d| *d < end).count() as u64;
+      assert_eq!(schedule.count_between(start, end), expected);
+    }
+  }
 }
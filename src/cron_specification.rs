@@ -1,24 +1,286 @@
-use chrono::{DateTime, TimeZone};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
 
-use crate::{CronEvaluator, Expr};
+use anyhow::{anyhow, Result};
+
+use crate::{CronEvaluator, CronField, CronHashResolver, Expr, YEAR_BASE};
 
 pub trait Specification<T>: Clone {
   fn is_satisfied_by(&self, arg: &T) -> bool;
+
+  /// Returns the step an iterator takes between the candidates it tests, one minute unless seconds matter.<br/>
+  /// イテレータが判定する候補の間隔を返す。秒が関係しない限り1分となる。
+  fn granularity(&self) -> Duration {
+    Duration::minutes(1)
+  }
+}
+
+/// Combinators for building composite specifications.<br/>
+/// 仕様を合成するためのコンビネータ。
+///
+/// This is separate from `Specification` because a specification such as `CronSpecification` is
+/// implemented for every `DateTime<Tz>`, which would leave the type parameter ambiguous at the call site.<br/>
+/// `CronSpecification`のように全ての`DateTime<Tz>`に対して実装される仕様では、
+/// 呼び出し側で型パラメータが曖昧になるため`Specification`とは分けている。
+pub trait SpecificationExt: Sized {
+  /// Returns a specification satisfied when both specifications are satisfied.<br/>
+  /// 両方の仕様を満たす場合に満たされる仕様を返す。
+  fn and<S>(self, other: S) -> AndSpecification<Self, S> {
+    AndSpecification::new(self, other)
+  }
+
+  /// Returns a specification satisfied when either specification is satisfied.<br/>
+  /// いずれかの仕様を満たす場合に満たされる仕様を返す。
+  fn or<S>(self, other: S) -> OrSpecification<Self, S> {
+    OrSpecification::new(self, other)
+  }
+
+  /// Returns a specification satisfied when this specification is not satisfied.<br/>
+  /// この仕様を満たさない場合に満たされる仕様を返す。
+  fn not(self) -> NotSpecification<Self> {
+    NotSpecification::new(self)
+  }
+}
+
+/// A specification that is the conjunction of two specifications.<br/>
+/// 2つの仕様の論理積を表す仕様。
+#[derive(Debug, Clone)]
+pub struct AndSpecification<L, R> {
+  left: L,
+  right: R,
+}
+
+impl<L, R> AndSpecification<L, R> {
+  pub fn new(left: L, right: R) -> Self {
+    Self { left, right }
+  }
+}
+
+impl<L, R> SpecificationExt for AndSpecification<L, R> {}
+
+impl<T, L: Specification<T>, R: Specification<T>> Specification<T> for AndSpecification<L, R> {
+  fn is_satisfied_by(&self, arg: &T) -> bool {
+    self.left.is_satisfied_by(arg) && self.right.is_satisfied_by(arg)
+  }
+
+  fn granularity(&self) -> Duration {
+    self.left.granularity().min(self.right.granularity())
+  }
+}
+
+/// A specification that is the disjunction of two specifications.<br/>
+/// 2つの仕様の論理和を表す仕様。
+#[derive(Debug, Clone)]
+pub struct OrSpecification<L, R> {
+  left: L,
+  right: R,
+}
+
+impl<L, R> OrSpecification<L, R> {
+  pub fn new(left: L, right: R) -> Self {
+    Self { left, right }
+  }
+}
+
+impl<L, R> SpecificationExt for OrSpecification<L, R> {}
+
+impl<T, L: Specification<T>, R: Specification<T>> Specification<T> for OrSpecification<L, R> {
+  fn is_satisfied_by(&self, arg: &T) -> bool {
+    self.left.is_satisfied_by(arg) || self.right.is_satisfied_by(arg)
+  }
+
+  fn granularity(&self) -> Duration {
+    self.left.granularity().min(self.right.granularity())
+  }
+}
+
+/// A specification that is the negation of a specification.<br/>
+/// 仕様の否定を表す仕様。
+#[derive(Debug, Clone)]
+pub struct NotSpecification<S> {
+  underlying: S,
+}
+
+impl<S> NotSpecification<S> {
+  pub fn new(underlying: S) -> Self {
+    Self { underlying }
+  }
+}
+
+impl<S> SpecificationExt for NotSpecification<S> {}
+
+impl<T, S: Specification<T>> Specification<T> for NotSpecification<S> {
+  fn is_satisfied_by(&self, arg: &T) -> bool {
+    !self.underlying.is_satisfied_by(arg)
+  }
+
+  fn granularity(&self) -> Duration {
+    self.underlying.granularity()
+  }
 }
 
 #[derive(Debug, Clone)]
 pub struct CronSpecification {
   expr: Expr,
+  anchor: i64,
 }
 
 impl CronSpecification {
+  /// The factory method. The expression must not contain an unresolved `H`, which never matches, so an iterator
+  /// over it would search forever. Use `CronSchedule::new_with_seed` or `CronSpecification::try_new` for
+  /// expressions that may contain one.<br/>
+  /// ファクトリメソッド。式は未解決の`H`を含んではならない。`H`はマッチしないため、イテレータが永久に探索する。
+  /// `H`を含みうる式には`CronSchedule::new_with_seed`または`CronSpecification::try_new`を使う。
   pub fn new(expr: Expr) -> Self {
-    Self { expr }
+    Self { expr, anchor: 0 }
+  }
+
+  /// The factory method that rejects an expression containing an unresolved `H`.<br/>
+  /// 未解決の`H`を含む式を拒否するファクトリメソッド。
+  ///
+  /// # Return values(戻り値)
+  ///
+  /// * Err:
+  ///   - If the expression contains `H`
+  ///   - 式が`H`を含む場合
+  /// * Ok
+  ///   - Otherwise
+  ///   - それ以外の場合
+  pub fn try_new(expr: Expr) -> Result<Self> {
+    if CronHashResolver::contains_hash(&expr) {
+      return Err(anyhow!(
+        "Failed to create specification: H requires a seed, resolve it with CronHashResolver"
+      ));
+    }
+    Ok(Self::new(expr))
+  }
+
+  /// Returns a specification whose `rate(...)` counts from the instant instead of the Unix epoch.<br/>
+  /// `rate(...)`をUnixエポックではなく指定した日時から数える仕様を返す。
+  pub fn anchored_at<Tz: TimeZone>(self, instant: &DateTime<Tz>) -> Self {
+    Self {
+      anchor: instant.timestamp(),
+      ..self
+    }
   }
 }
 
+impl SpecificationExt for CronSpecification {}
+
 impl<Tz: TimeZone> Specification<DateTime<Tz>> for CronSpecification {
   fn is_satisfied_by(&self, datetime: &DateTime<Tz>) -> bool {
-    CronEvaluator::new(datetime).eval(&self.expr)
+    match &self.expr {
+      // AWS fires a rate one period after it starts, not at the start itself.
+      Expr::RateExpr { value, unit } => {
+        let elapsed = datetime.timestamp() - self.anchor;
+        elapsed > 0 && elapsed % (*value as i64 * unit.seconds()) == 0
+      }
+      expr => CronEvaluator::new(datetime).eval(expr),
+    }
+  }
+
+  fn granularity(&self) -> Duration {
+    match &self.expr {
+      Expr::CronExpr { secs, .. } if **secs != Expr::NoOp => Duration::seconds(1),
+      _ => Duration::minutes(1),
+    }
+  }
+}
+
+/// Whether a date satisfies all of the expressions, ignoring the time of day. It is checked day by day over the years
+/// the expressions allow, or a whole 400-year cycle of the Gregorian calendar when the years are not restricted.<br/>
+/// 時刻を無視して、全ての式を満たす日付があるかどうか。式が許す年、年が制限されていない場合はグレゴリオ暦の
+/// 400年周期全体を1日ずつ調べる。
+pub(crate) fn matches_any_date(exprs: &[&Expr]) -> bool {
+  let mut years: Option<Vec<i32>> = None;
+  let mut specs = vec![];
+  for expr in exprs {
+    if let Expr::CronExpr {
+      days,
+      months,
+      day_of_weeks,
+      years: year_expr,
+      day_combination,
+      ..
+    } = expr
+    {
+      if **year_expr != Expr::NoOp {
+        let allowed = CronField::Year.values(year_expr).unwrap_or_default();
+        let mut allowed = allowed.iter().map(|y| YEAR_BASE as i32 + *y as i32).collect::<Vec<_>>();
+        if let Some(years) = &years {
+          allowed.retain(|y| years.contains(y));
+        }
+        years = Some(allowed);
+      }
+      specs.push(CronSpecification::new(Expr::CronExpr {
+        secs: Box::new(Expr::NoOp),
+        mins: Box::new(Expr::AnyValueExpr),
+        hours: Box::new(Expr::AnyValueExpr),
+        days: days.clone(),
+        months: months.clone(),
+        day_of_weeks: day_of_weeks.clone(),
+        years: year_expr.clone(),
+        day_combination: *day_combination,
+      }));
+    }
+  }
+  let years = years.unwrap_or_else(|| (2000..2400).collect());
+  years.into_iter().any(|year| {
+    let mut date = NaiveDate::from_ymd_opt(year, 1, 1).unwrap();
+    while date.year() == year {
+      let date_time = Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap());
+      if specs.iter().all(|spec| spec.is_satisfied_by(&date_time)) {
+        return true;
+      }
+      date = date.succ_opt().unwrap();
+    }
+    false
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::{TimeZone, Utc};
+  use intervals_rs::LimitValue;
+
+  use super::*;
+  use crate::{CronInterval, CronParser};
+
+  fn spec(s: &str) -> CronSpecification {
+    CronSpecification::new(CronParser::parse(s).to_result().unwrap())
+  }
+
+  #[test]
+  fn test_combinators() {
+    let dt = Utc.with_ymd_and_hms(2021, 1, 4, 9, 30, 0).unwrap();
+    assert!(spec("30 * * * *").and(spec("* 9 * * *")).is_satisfied_by(&dt));
+    assert!(!spec("30 * * * *").and(spec("* 10 * * *")).is_satisfied_by(&dt));
+    assert!(spec("0 * * * *").or(spec("* 9 * * *")).is_satisfied_by(&dt));
+    assert!(!spec("0 * * * *").or(spec("* 10 * * *")).is_satisfied_by(&dt));
+    assert!(spec("0 * * * *").not().is_satisfied_by(&dt));
+    assert!(!spec("30 * * * *").not().is_satisfied_by(&dt));
+  }
+
+  #[test]
+  fn test_try_new() {
+    let expr = CronParser::parse("H * * * *").to_result().unwrap();
+    assert!(CronSpecification::try_new(expr.clone()).is_err());
+    let resolved = CronHashResolver::new("job").resolve(&expr);
+    assert!(CronSpecification::try_new(resolved).is_ok());
+    assert!(CronSpecification::try_new(CronParser::parse("*/5 * * * *").to_result().unwrap()).is_ok());
+  }
+
+  #[test]
+  fn test_iterate_composed() {
+    // Every 15 minutes during business hours except on the first of the month
+    let spec = spec("*/15 * * * *")
+      .and(spec("* 9-17 * * MON-FRI"))
+      .and(spec("* * 1 * *").not());
+    // 2021-01-01 is a Friday
+    let dt = Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap();
+    let interval = CronInterval::new(LimitValue::Limit(dt), LimitValue::Limitless, spec);
+    let dt_vec = interval.iter(Utc).take(3).collect::<Vec<_>>();
+    assert_eq!(dt_vec[0], Utc.with_ymd_and_hms(2021, 1, 4, 9, 0, 0).unwrap());
+    assert_eq!(dt_vec[1], Utc.with_ymd_and_hms(2021, 1, 4, 9, 15, 0).unwrap());
+    assert_eq!(dt_vec[2], Utc.with_ymd_and_hms(2021, 1, 4, 9, 30, 0).unwrap());
   }
 }
@@ -0,0 +1,335 @@
+use std::fmt;
+
+use crate::dialect::nickname;
+use crate::{CronParser, Dialect, Expr};
+
+/// The kind of crontab file, which decides whether a user column is present.<br/>
+/// crontabファイルの種類。ユーザ列の有無を決める。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrontabKind {
+  /// A per-user crontab (`crontab -e`), without a user column.<br/>
+  /// ユーザ毎のcrontab(ユーザ列なし)。
+  User,
+  /// A system crontab (`/etc/crontab`, `/etc/cron.d/*`), with a user column.<br/>
+  /// システムcrontab(ユーザ列あり)。
+  System,
+}
+
+/// The schedule part of a crontab entry.<br/>
+/// crontabエントリのスケジュール部分。
+#[derive(Debug, Clone, PartialEq)]
+pub enum CrontabSchedule {
+  /// A CROND expression in the Vixie dialect, including the expansion of nicknames such as `@daily`.<br/>
+  /// Vixie方言のCROND式(`@daily`などの別名を展開したものを含む)。
+  Cron(Expr),
+  /// `@reboot`, which runs once at daemon start-up.<br/>
+  /// デーモン起動時に一度だけ実行する`@reboot`。
+  Reboot,
+}
+
+/// An environment assignment such as `MAILTO=ops@example.com`.<br/>
+/// `MAILTO=ops@example.com`のような環境変数の代入。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrontabVariable {
+  pub line: usize,
+  pub name: String,
+  pub value: String,
+}
+
+/// A job line of a crontab file.<br/>
+/// crontabファイルのジョブ行。
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrontabEntry {
+  pub line: usize,
+  pub schedule: CrontabSchedule,
+  /// The user column, only present in system crontabs.<br/>
+  /// ユーザ列(システムcrontabのみ)。
+  pub user: Option<String>,
+  /// The command text before the first unescaped `%`.<br/>
+  /// 最初のエスケープされていない`%`より前のコマンド文字列。
+  pub command: String,
+  /// The text after the first unescaped `%`, with the remaining `%`s turned into newlines.<br/>
+  /// 最初の`%`より後ろの標準入力。残りの`%`は改行に変換される。
+  pub stdin: Option<String>,
+  /// The `CRON_TZ` in effect for this entry.<br/>
+  /// このエントリに適用される`CRON_TZ`。
+  pub cron_tz: Option<String>,
+}
+
+/// A parsed crontab file.<br/>
+/// 解析済みのcrontabファイル。
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Crontab {
+  pub env: Vec<CrontabVariable>,
+  pub entries: Vec<CrontabEntry>,
+}
+
+/// An error while parsing a crontab file, with the 1-based line number.<br/>
+/// crontabファイルの解析エラー。行番号は1始まり。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrontabError {
+  line: usize,
+  message: String,
+}
+
+impl CrontabError {
+  fn new(line: usize, message: impl Into<String>) -> Self {
+    Self {
+      line,
+      message: message.into(),
+    }
+  }
+
+  /// Returns the line number.<br/>
+  /// 行番号を返す。
+  pub fn line(&self) -> usize {
+    self.line
+  }
+
+  /// Returns the error message.<br/>
+  /// エラーメッセージを返す。
+  pub fn message(&self) -> &str {
+    &self.message
+  }
+}
+
+impl fmt::Display for CrontabError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "line {}: {}", self.line, self.message)
+  }
+}
+
+impl std::error::Error for CrontabError {}
+
+impl Crontab {
+  /// Parses a crontab file.<br/>
+  /// crontabファイルを解析する。
+  ///
+  /// # Arguments(引数)
+  ///
+  /// * kind - The kind of crontab file(crontabファイルの種類)
+  /// * source - The file contents(ファイルの内容)
+  pub fn parse(kind: CrontabKind, source: &str) -> Result<Self, CrontabError> {
+    let mut crontab = Crontab::default();
+    let mut cron_tz = None;
+    for (index, text) in source.lines().enumerate() {
+      let line = index + 1;
+      let trimmed = text.trim();
+      if trimmed.is_empty() || trimmed.starts_with('#') {
+        continue;
+      }
+      if let Some(variable) = parse_variable(line, trimmed) {
+        if variable.name == "CRON_TZ" {
+          cron_tz = Some(variable.value.clone());
+        }
+        crontab.env.push(variable);
+        continue;
+      }
+      let mut entry = parse_entry(kind, line, trimmed)?;
+      entry.cron_tz = cron_tz.clone();
+      crontab.entries.push(entry);
+    }
+    Ok(crontab)
+  }
+
+  /// Returns the value of the last assignment to the variable.<br/>
+  /// 変数への最後の代入値を返す。
+  pub fn env_value(&self, name: &str) -> Option<&str> {
+    self.env.iter().rev().find(|v| v.name == name).map(|v| v.value.as_str())
+  }
+}
+
+fn parse_variable(line: usize, text: &str) -> Option<CrontabVariable> {
+  let (name, value) = text.split_once('=')?;
+  let name = name.trim_end();
+  let mut chars = name.chars();
+  let head = chars.next()?;
+  if !(head.is_ascii_alphabetic() || head == '_') || !chars.all(|c| c.is_ascii_alphanumeric() || c == '_') {
+    return None;
+  }
+  let value = value.trim();
+  let value = match value.as_bytes() {
+    [b'"', .., b'"'] | [b'\'', .., b'\''] if value.len() >= 2 => &value[1..value.len() - 1],
+    _ => value,
+  };
+  Some(CrontabVariable {
+    line,
+    name: name.to_string(),
+    value: value.to_string(),
+  })
+}
+
+fn next_field(text: &str) -> Option<(&str, &str)> {
+  let text = text.trim_start();
+  if text.is_empty() {
+    return None;
+  }
+  match text.find(char::is_whitespace) {
+    Some(n) => Some((&text[..n], text[n..].trim_start())),
+    None => Some((text, "")),
+  }
+}
+
+fn parse_schedule(line: usize, text: &str) -> Result<(CrontabSchedule, &str), CrontabError> {
+  let (first, rest) = next_field(text).ok_or_else(|| CrontabError::new(line, "missing schedule"))?;
+  if first == "@reboot" {
+    return Ok((CrontabSchedule::Reboot, rest));
+  }
+  if first.starts_with('@') {
+    if nickname(first).is_none() {
+      return Err(CrontabError::new(line, format!("unknown nickname: {}", first)));
+    }
+    let expr = CronParser::parse_with(Dialect::Vixie, first).to_result().unwrap();
+    return Ok((CrontabSchedule::Cron(expr), rest));
+  }
+  let mut fields = vec![first];
+  let mut rest = rest;
+  while fields.len() < 5 {
+    let (field, remaining) = next_field(rest)
+      .ok_or_else(|| CrontabError::new(line, format!("expected 5 schedule fields, found {}", fields.len())))?;
+    fields.push(field);
+    rest = remaining;
+  }
+  let source = fields.join(" ");
+  let expr = CronParser::parse_with(Dialect::Vixie, &source)
+    .to_result()
+    .map_err(|e| CrontabError::new(line, format!("invalid schedule '{}': {}", source, e)))?;
+  Ok((CrontabSchedule::Cron(expr), rest))
+}
+
+fn split_command(text: &str) -> (String, Option<String>) {
+  let mut command = String::new();
+  let mut stdin: Option<String> = None;
+  let mut chars = text.chars().peekable();
+  while let Some(c) = chars.next() {
+    if c == '%' && stdin.is_none() {
+      stdin = Some(String::new());
+      continue;
+    }
+    let target = stdin.as_mut().unwrap_or(&mut command);
+    match c {
+      '\\' if chars.peek() == Some(&'%') => {
+        chars.next();
+        target.push('%');
+      }
+      '%' => target.push('\n'),
+      c => target.push(c),
+    }
+  }
+  (command, stdin)
+}
+
+fn parse_entry(kind: CrontabKind, line: usize, text: &str) -> Result<CrontabEntry, CrontabError> {
+  let (schedule, rest) = parse_schedule(line, text)?;
+  let (user, rest) = match kind {
+    CrontabKind::User => (None, rest),
+    CrontabKind::System => {
+      let (user, rest) = next_field(rest).ok_or_else(|| CrontabError::new(line, "missing user"))?;
+      (Some(user.to_string()), rest)
+    }
+  };
+  if rest.is_empty() {
+    return Err(CrontabError::new(line, "missing command"));
+  }
+  let (command, stdin) = split_command(rest);
+  Ok(CrontabEntry {
+    line,
+    schedule,
+    user,
+    command,
+    stdin,
+    cron_tz: None,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::DayCombination;
+
+  fn vixie(s: &str) -> CrontabSchedule {
+    CrontabSchedule::Cron(CronParser::parse_with(Dialect::Vixie, s).to_result().unwrap())
+  }
+
+  #[test]
+  fn test_user_crontab() {
+    let source = "# nightly jobs\n\
+                  MAILTO=\"ops@example.com\"\n\
+                  \n\
+                  30 2 * * * /usr/bin/backup --full\n\
+                  @reboot /usr/bin/warmup\n";
+    let crontab = Crontab::parse(CrontabKind::User, source).unwrap();
+    assert_eq!(
+      crontab.env,
+      vec![CrontabVariable {
+        line: 2,
+        name: "MAILTO".to_string(),
+        value: "ops@example.com".to_string(),
+      }]
+    );
+    assert_eq!(crontab.entries.len(), 2);
+    assert_eq!(crontab.entries[0].line, 4);
+    assert_eq!(crontab.entries[0].schedule, vixie("30 2 * * *"));
+    assert_eq!(crontab.entries[0].user, None);
+    assert_eq!(crontab.entries[0].command, "/usr/bin/backup --full");
+    assert_eq!(crontab.entries[1].schedule, CrontabSchedule::Reboot);
+    assert_eq!(crontab.env_value("MAILTO"), Some("ops@example.com"));
+  }
+
+  #[test]
+  fn test_system_crontab() {
+    let source = "SHELL=/bin/sh\n\
+                  CRON_TZ=Asia/Tokyo\n\
+                  17 *\t* * *  root  cd / && run-parts --report /etc/cron.hourly\n\
+                  @daily www-data /usr/bin/rotate\n";
+    let crontab = Crontab::parse(CrontabKind::System, source).unwrap();
+    assert_eq!(crontab.env_value("SHELL"), Some("/bin/sh"));
+    let entry = &crontab.entries[0];
+    assert_eq!(entry.user.as_deref(), Some("root"));
+    assert_eq!(entry.command, "cd / && run-parts --report /etc/cron.hourly");
+    assert_eq!(entry.cron_tz.as_deref(), Some("Asia/Tokyo"));
+    assert_eq!(crontab.entries[1].schedule, vixie("0 0 * * *"));
+    assert_eq!(crontab.entries[1].user.as_deref(), Some("www-data"));
+  }
+
+  #[test]
+  fn test_vixie_semantics() {
+    let source = "0 0 * * 0 /usr/bin/sunday\n\
+                  0 9 * * 1-5 /usr/bin/weekdays\n\
+                  0 0 1 * 1 /usr/bin/first-or-monday\n";
+    let crontab = Crontab::parse(CrontabKind::User, source).unwrap();
+    let schedules = crontab.entries.iter().map(|e| e.schedule.clone()).collect::<Vec<_>>();
+    let native = |s: &str| CrontabSchedule::Cron(CronParser::parse(s).to_result().unwrap());
+    assert_eq!(schedules[0], native("0 0 * * 1"));
+    assert_eq!(schedules[1], native("0 9 * * 2-6"));
+    match &schedules[2] {
+      CrontabSchedule::Cron(Expr::CronExpr { day_combination, .. }) => {
+        assert_eq!(*day_combination, DayCombination::Either)
+      }
+      schedule => panic!("unexpected schedule: {:?}", schedule),
+    }
+  }
+
+  #[test]
+  fn test_percent() {
+    let crontab = Crontab::parse(CrontabKind::User, "0 0 * * * date +\\%F >> log%line1%line2").unwrap();
+    let entry = &crontab.entries[0];
+    assert_eq!(entry.command, "date +%F >> log");
+    assert_eq!(entry.stdin.as_deref(), Some("line1\nline2"));
+  }
+
+  #[test]
+  fn test_errors() {
+    let error = Crontab::parse(CrontabKind::User, "# header\n0 0 * *\n").unwrap_err();
+    assert_eq!(error.line(), 2);
+    let error = Crontab::parse(CrontabKind::User, "\n\n0 0 * * *\n").unwrap_err();
+    assert_eq!(error.line(), 3);
+    assert_eq!(error.message(), "missing command");
+    let error = Crontab::parse(CrontabKind::User, "0 99 * * * echo\n").unwrap_err();
+    assert_eq!(error.line(), 1);
+    let error = Crontab::parse(CrontabKind::System, "@hourly\n").unwrap_err();
+    assert_eq!(error.to_string(), "line 1: missing user");
+    let error = Crontab::parse(CrontabKind::User, "@fortnightly echo\n").unwrap_err();
+    assert_eq!(error.to_string(), "line 1: unknown nickname: @fortnightly");
+  }
+}
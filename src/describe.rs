@@ -0,0 +1,669 @@
+use crate::ast::FieldDisplay;
+use crate::{CronField, Expr, YEAR_BASE};
+
+/// Upper bound on the number of `HH:MM` pairs rendered as explicit times.<br/>
+/// 時刻として列挙する`HH:MM`の組み合わせ数の上限。
+const MAX_TIMES: usize = 8;
+
+/// An element of a field in a locale independent form.<br/>
+/// ロケールに依存しない形式のフィールド要素。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldItem {
+  Value(u8),
+  Last,
+  LastDayOffset(u8),
+  NearestWeekday(u8),
+  LastWeekday,
+  LastDayOfWeek(u8),
+  NthDayOfWeek {
+    day_of_week: u8,
+    nth: u8,
+  },
+  Range {
+    from: u8,
+    to: u8,
+  },
+  Step {
+    range: Option<(u8, u8)>,
+    step: u8,
+  },
+  /// A construct with no wording, such as an unresolved `H`, kept as the text of the field.<br/>
+  /// 未解決の`H`など言い回しのない構文。フィールドの文字列のまま保持する。
+  Expression(String),
+}
+
+/// Renders the pieces of a schedule description in a natural language.<br/>
+/// スケジュールの説明を自然言語で組み立てるロケール。
+pub trait Locale {
+  /// Renders a single value, e.g. a month or weekday name.<br/>
+  /// 月名や曜日名などの単一の値を返す。
+  fn value(&self, field: CronField, value: u8) -> String;
+
+  /// Renders an `HH:MM` time.<br/>
+  /// `HH:MM`形式の時刻を返す。
+  fn time(&self, hour: u8, minute: u8) -> String {
+    format!("{:02}:{:02}", hour, minute)
+  }
+
+  /// Renders `L` of the field as a whole phrase.<br/>
+  /// フィールドの`L`を句として返す。
+  fn last(&self, field: CronField) -> String;
+
+  /// Renders a range of two already rendered values.<br/>
+  /// 描画済みの2つの値の範囲を返す。
+  fn range(&self, from: String, to: String) -> String;
+
+  /// Renders a step, optionally restricted to a range.<br/>
+  /// 範囲付きの場合もある間隔指定を返す。
+  fn step(&self, field: CronField, step: u8, range: Option<String>) -> String;
+
+  /// Joins rendered items into a list.<br/>
+  /// 描画済みの要素をリストとして連結する。
+  fn list(&self, items: Vec<String>) -> String;
+
+  /// Renders the values of a field with the field's wording, e.g. `at minutes 0 and 30`.<br/>
+  /// フィールドの値を、フィールド固有の言い回しで返す。
+  fn field(&self, field: CronField, items: String, plural: bool) -> String;
+
+  /// Renders the text of a field that has no wording, e.g. an unresolved `H`.<br/>
+  /// 未解決の`H`など、言い回しのないフィールドの文字列を返す。
+  fn expression(&self, field: CronField, text: String) -> String {
+    format!("{} {}", field.name(), text)
+  }
+
+  /// Renders the explicit times of day.<br/>
+  /// 明示的な時刻の一覧を返す。
+  fn at_times(&self, times: Vec<String>) -> String;
+
+  /// Renders a schedule that fires every minute.<br/>
+  /// 毎分実行を返す。
+  fn every_minute(&self) -> String;
+
+  /// Renders `L-n`, `nW`, `LW`, `nL` or `n#k` as a whole phrase, in cron syntax unless overridden.<br/>
+  /// `L-n`、`nW`、`LW`、`nL`、`n#k`を句として返す。上書きしない場合はcronの構文で返す。
+  fn calendar(&self, item: &FieldItem) -> String {
+    match item {
+      FieldItem::LastDayOffset(offset) => format!("L-{}", offset),
+      FieldItem::NearestWeekday(day) => format!("{}W", day),
+      FieldItem::LastWeekday => "LW".to_string(),
+      FieldItem::LastDayOfWeek(day_of_week) => format!("{}L", day_of_week),
+      FieldItem::NthDayOfWeek { day_of_week, nth } => format!("{}#{}", day_of_week, nth),
+      _ => String::new(),
+    }
+  }
+
+  /// Joins the phrases, given from the smallest unit to the largest, into a sentence.<br/>
+  /// 小さい単位から順に並んだ句を文に連結する。
+  fn sentence(&self, phrases: Vec<String>) -> String;
+}
+
+/// The English locale.<br/>
+/// 英語ロケール。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct English;
+
+/// The Japanese locale.<br/>
+/// 日本語ロケール。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Japanese;
+
+const ENGLISH_MONTHS: [&str; 12] = [
+  "January",
+  "February",
+  "March",
+  "April",
+  "May",
+  "June",
+  "July",
+  "August",
+  "September",
+  "October",
+  "November",
+  "December",
+];
+
+const ENGLISH_DAY_OF_WEEKS: [&str; 7] = [
+  "Sunday",
+  "Monday",
+  "Tuesday",
+  "Wednesday",
+  "Thursday",
+  "Friday",
+  "Saturday",
+];
+
+const JAPANESE_DAY_OF_WEEKS: [&str; 7] = ["日曜日", "月曜日", "火曜日", "水曜日", "木曜日", "金曜日", "土曜日"];
+
+fn name<'a>(names: &[&'a str], value: u8) -> Option<&'a str> {
+  names.get((value as usize).checked_sub(1)?).copied()
+}
+
+impl Locale for English {
+  fn value(&self, field: CronField, value: u8) -> String {
+    match field {
+      CronField::Hour => Some(format!("{:02}", value)),
+      CronField::Month => name(&ENGLISH_MONTHS, value).map(str::to_string),
+      CronField::DayOfWeek => name(&ENGLISH_DAY_OF_WEEKS, value).map(str::to_string),
+      CronField::Year => Some((YEAR_BASE + value as u16).to_string()),
+      _ => None,
+    }
+    .unwrap_or_else(|| value.to_string())
+  }
+
+  fn last(&self, field: CronField) -> String {
+    match field {
+      CronField::Second => "at the last second",
+      CronField::Minute => "at the last minute",
+      CronField::Hour => "during the last hour",
+      CronField::Day => "on the last day of the month",
+      CronField::Month => "in December",
+      CronField::DayOfWeek => "on the last day of the week",
+      CronField::Year => "in the last year",
+    }
+    .to_string()
+  }
+
+  fn range(&self, from: String, to: String) -> String {
+    format!("{} through {}", from, to)
+  }
+
+  fn step(&self, field: CronField, step: u8, range: Option<String>) -> String {
+    let unit = match field {
+      CronField::Second => "second",
+      CronField::Minute => "minute",
+      CronField::Hour => "hour",
+      CronField::Day => "day",
+      CronField::Month => "month",
+      CronField::DayOfWeek => "day of the week",
+      CronField::Year => "year",
+    };
+    let every = match step {
+      1 => format!("every {}", unit),
+      n if field == CronField::DayOfWeek => format!("every {} days of the week", n),
+      n => format!("every {} {}s", n, unit),
+    };
+    match range {
+      Some(range) => format!("{}, {}", every, self.field(field, range, true)),
+      None => every,
+    }
+  }
+
+  fn list(&self, mut items: Vec<String>) -> String {
+    match items.len() {
+      0 => String::new(),
+      1 => items.remove(0),
+      _ => {
+        let last = items.pop().unwrap();
+        format!("{} and {}", items.join(", "), last)
+      }
+    }
+  }
+
+  fn field(&self, field: CronField, items: String, plural: bool) -> String {
+    let s = if plural { "s" } else { "" };
+    match field {
+      CronField::Second => format!("at second{} {}", s, items),
+      CronField::Minute => format!("at minute{} {}", s, items),
+      CronField::Hour => format!("during hour{} {}", s, items),
+      CronField::Day => format!("on day{} {} of the month", s, items),
+      CronField::Month => format!("in {}", items),
+      CronField::DayOfWeek => items,
+      CronField::Year => format!("in {}", items),
+    }
+  }
+
+  fn expression(&self, field: CronField, text: String) -> String {
+    let name = match field {
+      CronField::Second => "second",
+      CronField::Minute => "minute",
+      CronField::Hour => "hour",
+      CronField::Day => "day of the month",
+      CronField::Month => "month",
+      CronField::DayOfWeek => "day of the week",
+      CronField::Year => "year",
+    };
+    format!("where the {} is {}", name, text)
+  }
+
+  fn at_times(&self, times: Vec<String>) -> String {
+    format!("at {}", self.list(times))
+  }
+
+  fn every_minute(&self) -> String {
+    "every minute".to_string()
+  }
+
+  fn calendar(&self, item: &FieldItem) -> String {
+    const ORDINALS: [&str; 5] = ["first", "second", "third", "fourth", "fifth"];
+    let day_of_week = |n: u8| self.value(CronField::DayOfWeek, n);
+    match item {
+      FieldItem::LastDayOffset(1) => "on the day before the last day of the month".to_string(),
+      FieldItem::LastDayOffset(offset) => format!("on the day {} days before the last day of the month", offset),
+      FieldItem::NearestWeekday(day) => format!("on the weekday nearest day {} of the month", day),
+      FieldItem::LastWeekday => "on the last weekday of the month".to_string(),
+      FieldItem::LastDayOfWeek(n) => format!("on the last {} of the month", day_of_week(*n)),
+      FieldItem::NthDayOfWeek { day_of_week: n, nth } => format!(
+        "on the {} {} of the month",
+        name(&ORDINALS, *nth).unwrap_or_default(),
+        day_of_week(*n)
+      ),
+      _ => String::new(),
+    }
+  }
+
+  fn sentence(&self, phrases: Vec<String>) -> String {
+    let sentence = phrases.join(", ");
+    let mut chars = sentence.chars();
+    match chars.next() {
+      Some(head) => head.to_uppercase().chain(chars).collect(),
+      None => sentence,
+    }
+  }
+}
+
+impl Locale for Japanese {
+  fn value(&self, field: CronField, value: u8) -> String {
+    match field {
+      CronField::Second => format!("{}秒", value),
+      CronField::Minute => format!("{}分", value),
+      CronField::Hour => format!("{}時", value),
+      CronField::Day => format!("{}日", value),
+      CronField::Month => format!("{}月", value),
+      CronField::DayOfWeek => name(&JAPANESE_DAY_OF_WEEKS, value)
+        .map(str::to_string)
+        .unwrap_or_else(|| value.to_string()),
+      CronField::Year => format!("{}年", YEAR_BASE + value as u16),
+    }
+  }
+
+  fn last(&self, field: CronField) -> String {
+    match field {
+      CronField::Second => "最終秒",
+      CronField::Minute => "最終分",
+      CronField::Hour => "最終時",
+      CronField::Day => "月末",
+      CronField::Month => "12月",
+      CronField::DayOfWeek => "週の最終日",
+      CronField::Year => "最終年",
+    }
+    .to_string()
+  }
+
+  fn range(&self, from: String, to: String) -> String {
+    format!("{}から{}まで", from, to)
+  }
+
+  fn step(&self, field: CronField, step: u8, range: Option<String>) -> String {
+    let unit = match field {
+      CronField::Second => "秒",
+      CronField::Minute => "分",
+      CronField::Hour => "時間",
+      CronField::Day => "日",
+      CronField::Month => "か月",
+      CronField::DayOfWeek => "曜日",
+      CronField::Year => "年",
+    };
+    let every = match step {
+      1 if field == CronField::Minute => "毎分".to_string(),
+      1 => format!("1{}ごと", unit),
+      n => format!("{}{}ごと", n, unit),
+    };
+    match range {
+      Some(range) => format!("{}の{}", range, every),
+      None => every,
+    }
+  }
+
+  fn list(&self, items: Vec<String>) -> String {
+    items.join("と")
+  }
+
+  fn field(&self, _field: CronField, items: String, _plural: bool) -> String {
+    items
+  }
+
+  fn expression(&self, field: CronField, text: String) -> String {
+    let name = match field {
+      CronField::Second => "秒",
+      CronField::Minute => "分",
+      CronField::Hour => "時",
+      CronField::Day => "日",
+      CronField::Month => "月",
+      CronField::DayOfWeek => "曜日",
+      CronField::Year => "年",
+    };
+    format!("{}が{}", name, text)
+  }
+
+  fn at_times(&self, times: Vec<String>) -> String {
+    self.list(times)
+  }
+
+  fn every_minute(&self) -> String {
+    "毎分".to_string()
+  }
+
+  fn calendar(&self, item: &FieldItem) -> String {
+    let day_of_week = |n: u8| self.value(CronField::DayOfWeek, n);
+    match item {
+      FieldItem::LastDayOffset(offset) => format!("月末の{}日前", offset),
+      FieldItem::NearestWeekday(day) => format!("{}日に最も近い平日", day),
+      FieldItem::LastWeekday => "月の最終平日".to_string(),
+      FieldItem::LastDayOfWeek(n) => format!("最終{}", day_of_week(*n)),
+      FieldItem::NthDayOfWeek { day_of_week: n, nth } => format!("第{}{}", nth, day_of_week(*n)),
+      _ => String::new(),
+    }
+  }
+
+  fn sentence(&self, mut phrases: Vec<String>) -> String {
+    phrases.reverse();
+    phrases.join("、")
+  }
+}
+
+/// Collects the items of a field, returning `false` if it matches any value or contains a construct with no item.
+fn collect_items(field: CronField, expr: &Expr, items: &mut Vec<FieldItem>) -> bool {
+  match expr {
+    Expr::NoOp | Expr::AnyValueExpr | Expr::NoSpecificValueExpr => return false,
+    Expr::ValueExpr(v) => items.push(FieldItem::Value(*v)),
+    Expr::LastValueExpr => items.push(FieldItem::Last),
+    Expr::LastDayOffsetExpr(offset) => items.push(FieldItem::LastDayOffset(*offset)),
+    Expr::NearestWeekdayExpr(day) => items.push(FieldItem::NearestWeekday(*day)),
+    Expr::LastWeekdayExpr => items.push(FieldItem::LastWeekday),
+    Expr::LastDayOfWeekExpr(day_of_week) => items.push(FieldItem::LastDayOfWeek(*day_of_week)),
+    Expr::NthDayOfWeekExpr { day_of_week, nth } => items.push(FieldItem::NthDayOfWeek {
+      day_of_week: *day_of_week,
+      nth: *nth,
+    }),
+    // Steps over the days of the week read better as the days themselves.
+    Expr::RangeExpr {
+      per_option: box Expr::ValueExpr(_),
+      ..
+    }
+    | Expr::PerExpr { .. }
+      if field == CronField::DayOfWeek =>
+    {
+      match field.values(expr) {
+        Some(values) => items.extend(values.into_iter().map(FieldItem::Value)),
+        None => return false,
+      }
+    }
+    Expr::RangeExpr {
+      from: box Expr::ValueExpr(from),
+      to: box Expr::ValueExpr(to),
+      per_option,
+    } => match per_option {
+      box Expr::ValueExpr(step) => items.push(FieldItem::Step {
+        range: Some((*from, *to)),
+        step: *step,
+      }),
+      _ => items.push(FieldItem::Range { from: *from, to: *to }),
+    },
+    Expr::PerExpr {
+      digit: box Expr::AnyValueExpr,
+      option: box Expr::ValueExpr(step),
+    } => items.push(FieldItem::Step {
+      range: None,
+      step: *step,
+    }),
+    Expr::PerExpr {
+      digit: box Expr::ValueExpr(start),
+      option: box Expr::ValueExpr(step),
+    } => items.push(FieldItem::Step {
+      range: Some((*start, field.max())),
+      step: *step,
+    }),
+    Expr::ListExpr(list) => {
+      for e in list {
+        if !collect_items(field, e, items) {
+          return false;
+        }
+      }
+    }
+    _ => return false,
+  }
+  true
+}
+
+fn matches_any(expr: &Expr) -> bool {
+  match expr {
+    Expr::NoOp | Expr::AnyValueExpr | Expr::NoSpecificValueExpr => true,
+    Expr::ListExpr(list) => list.iter().any(matches_any),
+    _ => false,
+  }
+}
+
+/// Returns the items of a field, or `None` if the field matches any value.
+/// A field with a construct that has no item is returned as its text.<br/>
+/// フィールドの要素を返す。任意の値にマッチする場合は`None`を返す。
+/// 要素にできない構文を含むフィールドは文字列として返す。
+fn field_items(field: CronField, expr: &Expr) -> Option<Vec<FieldItem>> {
+  let mut items = vec![];
+  if collect_items(field, expr, &mut items) {
+    Some(items)
+  } else if matches_any(expr) {
+    None
+  } else {
+    Some(vec![FieldItem::Expression(FieldDisplay(field, expr).to_string())])
+  }
+}
+
+fn values_only(items: &[FieldItem]) -> Option<Vec<u8>> {
+  items
+    .iter()
+    .map(|item| match item {
+      FieldItem::Value(v) => Some(*v),
+      _ => None,
+    })
+    .collect()
+}
+
+fn describe_field<L: Locale + ?Sized>(locale: &L, field: CronField, items: &[FieldItem]) -> String {
+  let mut plain = vec![];
+  let mut phrases = vec![];
+  let mut plural = false;
+  for item in items {
+    match item {
+      FieldItem::Value(v) => plain.push(locale.value(field, *v)),
+      FieldItem::Last => phrases.push(locale.last(field)),
+      FieldItem::LastDayOffset(_)
+      | FieldItem::NearestWeekday(_)
+      | FieldItem::LastWeekday
+      | FieldItem::LastDayOfWeek(_)
+      | FieldItem::NthDayOfWeek { .. } => phrases.push(locale.calendar(item)),
+      FieldItem::Range { from, to } => {
+        plural = true;
+        plain.push(locale.range(locale.value(field, *from), locale.value(field, *to)))
+      }
+      FieldItem::Step { range, step } => {
+        let range = range.map(|(from, to)| locale.range(locale.value(field, from), locale.value(field, to)));
+        phrases.push(locale.step(field, *step, range))
+      }
+      FieldItem::Expression(text) => phrases.push(locale.expression(field, text.clone())),
+    }
+  }
+  plural |= plain.len() > 1;
+  if !plain.is_empty() {
+    phrases.insert(0, locale.field(field, locale.list(plain), plural));
+  }
+  locale.list(phrases)
+}
+
+/// Describes a `CronExpr` in natural language using the given locale.<br/>
+/// 指定したロケールで`CronExpr`を自然言語で説明する。
+///
+/// # Arguments(引数)
+///
+/// * expr - The `CronExpr` to describe(説明する`CronExpr`)
+/// * locale - The locale such as `English` or `Japanese`(`English`や`Japanese`などのロケール)
+pub fn describe<L: Locale + ?Sized>(expr: &Expr, locale: &L) -> String {
+  let (secs, mins, hours, days, months, day_of_weeks, years) = match expr {
+    Expr::CronExpr {
+      secs,
+      mins,
+      hours,
+      days,
+      months,
+      day_of_weeks,
+      years,
+      ..
+    } => (secs, mins, hours, days, months, day_of_weeks, years),
+    _ => return String::new(),
+  };
+  let mins = field_items(CronField::Minute, mins);
+  let hours = field_items(CronField::Hour, hours);
+  let mut phrases = vec![];
+  if let Some(items) = field_items(CronField::Second, secs) {
+    phrases.push(describe_field(locale, CronField::Second, &items));
+  }
+  let times = match (&mins, &hours) {
+    (Some(m), Some(h)) => values_only(m).zip(values_only(h)),
+    _ => None,
+  };
+  match times {
+    Some((m, h)) if m.len() * h.len() <= MAX_TIMES => {
+      let times = h
+        .iter()
+        .flat_map(|h| m.iter().map(move |m| (*h, *m)))
+        .map(|(h, m)| locale.time(h, m))
+        .collect();
+      phrases.push(locale.at_times(times));
+    }
+    _ => {
+      match &mins {
+        Some(items) => phrases.push(describe_field(locale, CronField::Minute, items)),
+        None => phrases.push(locale.every_minute()),
+      }
+      if let Some(items) = &hours {
+        phrases.push(describe_field(locale, CronField::Hour, items));
+      }
+    }
+  }
+  for (field, expr) in [
+    (CronField::Day, days),
+    (CronField::Month, months),
+    (CronField::DayOfWeek, day_of_weeks),
+    (CronField::Year, years),
+  ] {
+    if let Some(items) = field_items(field, expr) {
+      phrases.push(describe_field(locale, field, &items));
+    }
+  }
+  locale.sentence(phrases)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{CronParser, Dialect};
+
+  fn english(s: &str) -> String {
+    describe(&CronParser::parse(s).to_result().unwrap(), &English)
+  }
+
+  fn japanese(s: &str) -> String {
+    describe(&CronParser::parse(s).to_result().unwrap(), &Japanese)
+  }
+
+  #[test]
+  fn test_english() {
+    assert_eq!(english("* * * * *"), "Every minute");
+    assert_eq!(
+      english("0,30 2 * * MON-FRI"),
+      "At 02:00 and 02:30, Monday through Friday"
+    );
+    assert_eq!(english("*/15 * * * *"), "Every 15 minutes");
+    let quartz = CronParser::parse_with(Dialect::Quartz, "*/10 0 9 ? * * 2030")
+      .to_result()
+      .unwrap();
+    assert_eq!(describe(&quartz, &English), "Every 10 seconds, at 09:00, in 2030");
+    assert_eq!(
+      english("0 9 15W * *"),
+      "At 09:00, on the weekday nearest day 15 of the month"
+    );
+    assert_eq!(english("0 9 * * 6#3"), "At 09:00, on the third Friday of the month");
+    assert_eq!(
+      english("0 9 LW * 6L"),
+      "At 09:00, on the last weekday of the month, on the last Friday of the month"
+    );
+    assert_eq!(english("5/15 * * * *"), "Every 15 minutes, at minutes 5 through 59");
+    assert_eq!(
+      english("5 0-23/2 * * *"),
+      "At minute 5, every 2 hours, during hours 00 through 23"
+    );
+    assert_eq!(
+      english("0,30 9-17 1,15 1,7 *"),
+      "At minutes 0 and 30, during hours 09 through 17, on days 1 and 15 of the month, in January and July"
+    );
+    assert_eq!(
+      english("0 0 1 * L"),
+      "At 00:00, on day 1 of the month, on the last day of the week"
+    );
+    assert_eq!(
+      english("0 9 * * */2"),
+      "At 09:00, Sunday, Tuesday, Thursday and Saturday"
+    );
+    assert_eq!(english("0 9 * * 6-2/2"), "At 09:00, Sunday and Friday");
+    assert_eq!(english("0 22-2 * * *"), "At minute 0, during hours 22 through 02");
+    assert_eq!(english("H 9 * * *"), "Where the minute is H, during hour 09");
+    assert_eq!(
+      english("0 9 H(1-15) * *"),
+      "At 09:00, where the day of the month is H(1-15)"
+    );
+  }
+
+  #[test]
+  fn test_japanese() {
+    assert_eq!(japanese("* * * * *"), "毎分");
+    assert_eq!(japanese("0,30 2 * * MON-FRI"), "月曜日から金曜日まで、02:00と02:30");
+    assert_eq!(japanese("*/10 * 1 * *"), "1日、10分ごと");
+    assert_eq!(japanese("0 9 * * 6#3"), "第3金曜日、09:00");
+    assert_eq!(japanese("0 9 LW * *"), "月の最終平日、09:00");
+    assert_eq!(japanese("0 */2 * 3-5 L"), "週の最終日、3月から5月まで、2時間ごと、0分");
+    assert_eq!(japanese("H 9 * * *"), "9時、分がH");
+  }
+
+  #[test]
+  fn test_custom_locale() {
+    struct Upper;
+    impl Locale for Upper {
+      fn value(&self, field: CronField, value: u8) -> String {
+        English.value(field, value).to_uppercase()
+      }
+
+      fn last(&self, field: CronField) -> String {
+        English.last(field)
+      }
+
+      fn range(&self, from: String, to: String) -> String {
+        format!("{}-{}", from, to)
+      }
+
+      fn step(&self, field: CronField, step: u8, range: Option<String>) -> String {
+        English.step(field, step, range)
+      }
+
+      fn list(&self, items: Vec<String>) -> String {
+        items.join("/")
+      }
+
+      fn field(&self, _field: CronField, items: String, _plural: bool) -> String {
+        items
+      }
+
+      fn at_times(&self, times: Vec<String>) -> String {
+        self.list(times)
+      }
+
+      fn every_minute(&self) -> String {
+        "*".to_string()
+      }
+
+      fn sentence(&self, phrases: Vec<String>) -> String {
+        phrases.join(" | ")
+      }
+    }
+    let expr = CronParser::parse("0 12 * * SAT,SUN").to_result().unwrap();
+    assert_eq!(describe(&expr, &Upper), "12:00 | SATURDAY/SUNDAY");
+  }
+}
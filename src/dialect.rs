@@ -0,0 +1,337 @@
+use crate::{CronParserOptions, DayCombination, Expr};
+
+/// A flavour of cron syntax accepted by `CronParser::parse_with`.<br/>
+/// `CronParser::parse_with`が受け付けるcron構文の方言。
+///
+/// | Dialect      | Fields                                  | Day of weeks       | Extra syntax                              | Days and day of weeks              |
+/// |--------------|-----------------------------------------|--------------------|-------------------------------------------|------------------------------------|
+/// | `Native`     | min hour day month dow                  | 1-7, SUN=1         | `L` `W` `#`, `H`, `n/step`, wrap-around ranges | both must match               |
+/// | `Vixie`      | min hour day month dow                  | 0-7, SUN=0 or 7    | `@daily` etc.                             | either matches, unless one starts with `*` |
+/// | `Kubernetes` | min hour day month dow                  | 0-6, SUN=0         | `?`, `n/step`, `@daily` etc.              | either matches, unless one is `*` or `?` |
+/// | `Quartz`     | sec min hour day month dow [year]       | 1-7, SUN=1         | `?`, `L` `W` `#`, `n/step`, wrap-around ranges | exactly one must be `?`       |
+/// | `Spring`     | sec min hour day month dow              | 0-7, SUN=0 or 7    | `?`, `L` `W` `#`, `n/step`                | both must match                    |
+/// | `Aws`        | min hour day month dow year             | 1-7, SUN=1         | `?`, `L` `W` `#`, `n/step`, `cron(...)`, `rate(...)` | exactly one must be `?` |
+///
+/// Every dialect accepts `*`, lists, ranges, `*/step`, `range/step` and the names `SUN`-`SAT` and `JAN`-`DEC`.
+/// `L` is the last day in the days field and `nL` the last day of week `n` of the month, `nW` is the weekday
+/// nearest to day `n` and `LW` the last weekday, and `n#k` is the `k`th day of week `n` of the month.
+/// Parsed day of weeks are always converted to the native numbering, so `Display` writes the native syntax
+/// with seconds and years when present.<br/>
+/// 全ての方言は`*`、リスト、範囲、`*/step`、`range/step`と`SUN`-`SAT`の名前を受け付ける。
+/// 解析した曜日は常にネイティブの番号に変換されるため、`Display`は秒と年がある場合はそれらを含めてネイティブの構文で出力する。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dialect {
+  /// The syntax of `CronParser::parse`.<br/>
+  /// `CronParser::parse`の構文。
+  #[default]
+  Native,
+  /// Linux crontabs as read by Vixie cron and cronie.<br/>
+  /// Vixie cronやcronieが読むLinuxのcrontab。
+  Vixie,
+  /// Kubernetes CronJob schedules.<br/>
+  /// KubernetesのCronJobのスケジュール。
+  Kubernetes,
+  /// Quartz Scheduler cron expressions.<br/>
+  /// Quartz Schedulerのcron式。
+  Quartz,
+  /// Spring Framework cron expressions.<br/>
+  /// Spring Frameworkのcron式。
+  Spring,
+  /// Amazon EventBridge (CloudWatch Events) schedule expressions: `cron(...)`, its six fields alone, or `rate(...)`.<br/>
+  /// Amazon EventBridge(CloudWatch Events)のスケジュール式。`cron(...)`、その6つのフィールドのみ、または`rate(...)`。
+  Aws,
+}
+
+/// Whether a dialect has an optional field.<br/>
+/// 方言が任意のフィールドを持つかどうか。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FieldPresence {
+  Absent,
+  Optional,
+  Required,
+}
+
+impl Dialect {
+  /// Returns the special syntax this dialect accepts.<br/>
+  /// この方言が受け付ける特殊な構文を返す。
+  pub fn options(&self) -> CronParserOptions {
+    match self {
+      Dialect::Native => CronParserOptions::default(),
+      Dialect::Vixie => CronParserOptions {
+        allow_last: false,
+        ..CronParserOptions::strict()
+      },
+      Dialect::Kubernetes => CronParserOptions {
+        allow_start_step: true,
+        allow_last: false,
+        allow_no_specific_value: true,
+        ..CronParserOptions::strict()
+      },
+      Dialect::Quartz => CronParserOptions {
+        allow_wrap_around: true,
+        allow_start_step: true,
+        allow_no_specific_value: true,
+        allow_nearest_weekday: true,
+        allow_nth_day_of_week: true,
+        ..CronParserOptions::strict()
+      },
+      Dialect::Spring | Dialect::Aws => CronParserOptions {
+        allow_start_step: true,
+        allow_no_specific_value: true,
+        allow_nearest_weekday: true,
+        allow_nth_day_of_week: true,
+        ..CronParserOptions::strict()
+      },
+    }
+  }
+
+  pub(crate) fn seconds(&self) -> FieldPresence {
+    match self {
+      Dialect::Quartz | Dialect::Spring => FieldPresence::Required,
+      _ => FieldPresence::Absent,
+    }
+  }
+
+  pub(crate) fn years(&self) -> FieldPresence {
+    match self {
+      Dialect::Quartz => FieldPresence::Optional,
+      Dialect::Aws => FieldPresence::Required,
+      _ => FieldPresence::Absent,
+    }
+  }
+
+  /// Returns the number of Sunday, which is 0 or 1.<br/>
+  /// 日曜日の番号を返す。0または1となる。
+  pub(crate) fn sunday(&self) -> u8 {
+    match self {
+      Dialect::Vixie | Dialect::Kubernetes | Dialect::Spring => 0,
+      Dialect::Native | Dialect::Quartz | Dialect::Aws => 1,
+    }
+  }
+
+  pub(crate) fn day_of_week_digits(&self) -> &'static [u8] {
+    match self {
+      Dialect::Vixie | Dialect::Spring => b"01234567",
+      Dialect::Kubernetes => b"0123456",
+      Dialect::Native | Dialect::Quartz | Dialect::Aws => b"1234567",
+    }
+  }
+
+  pub(crate) fn supports_nicknames(&self) -> bool {
+    matches!(self, Dialect::Vixie | Dialect::Kubernetes)
+  }
+
+  /// Returns whether exactly one of the days and day of weeks fields must be `?`.<br/>
+  /// 日と曜日のフィールドのうち、ちょうど一方が`?`でなければならないかどうかを返す。
+  pub(crate) fn requires_no_specific_day(&self) -> bool {
+    matches!(self, Dialect::Quartz | Dialect::Aws)
+  }
+
+  /// Returns how the days and day of weeks fields are combined, given the fields as written.<br/>
+  /// 記述された日と曜日のフィールドから、それらの組み合わせ方を返す。
+  pub(crate) fn day_combination(&self, days: &Expr, day_of_weeks: &Expr) -> DayCombination {
+    // Vixie cron treats a field starting with `*` as unrestricted, including `*/2`,
+    // while Kubernetes (robfig/cron) only does so for `*`, `?` and `*/1`.
+    let unrestricted = |e: &Expr| match self {
+      Dialect::Vixie => matches!(
+        e,
+        Expr::AnyValueExpr
+          | Expr::PerExpr {
+            digit: box Expr::AnyValueExpr,
+            ..
+          }
+      ),
+      _ => matches!(
+        e,
+        Expr::AnyValueExpr
+          | Expr::NoSpecificValueExpr
+          | Expr::PerExpr {
+            digit: box Expr::AnyValueExpr,
+            option: box Expr::ValueExpr(1),
+          }
+      ),
+    };
+    match self {
+      Dialect::Vixie | Dialect::Kubernetes if !unrestricted(days) && !unrestricted(day_of_weeks) => {
+        DayCombination::Either
+      }
+      _ => DayCombination::Both,
+    }
+  }
+}
+
+/// Returns the expression a nickname such as `@daily` stands for, in the syntax of every dialect with nicknames.<br/>
+/// `@daily`などのニックネームが表す式を、ニックネームを持つ全ての方言の構文で返す。
+pub(crate) fn nickname(name: &str) -> Option<&'static str> {
+  match name {
+    "@yearly" | "@annually" => Some("0 0 1 1 *"),
+    "@monthly" => Some("0 0 1 * *"),
+    "@weekly" => Some("0 0 * * SUN"),
+    "@daily" | "@midnight" => Some("0 0 * * *"),
+    "@hourly" => Some("0 * * * *"),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::{DateTime, TimeZone, Utc};
+
+  use super::*;
+  use crate::{CronParser, CronSchedule, CronSpecification, Specification};
+
+  fn parse(dialect: Dialect, s: &str) -> Result<String, String> {
+    CronParser::parse_with(dialect, s)
+      .to_result()
+      .map(|e| e.to_string())
+      .map_err(|e| e.to_string())
+  }
+
+  fn upcoming(dialect: Dialect, s: &str, n: usize) -> Vec<DateTime<Utc>> {
+    // 2021-01-01 is a Friday
+    let start = Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap();
+    CronSchedule::with_dialect(dialect, s)
+      .unwrap()
+      .upcoming(start)
+      .take(n)
+      .collect()
+  }
+
+  fn at(d: u32, h: u32, m: u32, s: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2021, 1, d, h, m, s).unwrap()
+  }
+
+  #[test]
+  fn test_native() {
+    assert_eq!(parse(Dialect::Native, "H 0 * * 2-6"), Ok("H 0 * * 2-6".to_string()));
+    assert!(parse(Dialect::Native, "0 0 ? * *").is_err());
+    assert!(parse(Dialect::Native, "0 0 0 * * *").is_err());
+    assert!(parse(Dialect::Native, "0 0 * * 0").is_err());
+  }
+
+  #[test]
+  fn test_vixie() {
+    assert_eq!(parse(Dialect::Vixie, "0 0 * * 0"), Ok("0 0 * * 1".to_string()));
+    assert_eq!(parse(Dialect::Vixie, "0 0 * * 7"), Ok("0 0 * * 1".to_string()));
+    assert_eq!(parse(Dialect::Vixie, "0 0 * * 1-5"), Ok("0 0 * * 2-6".to_string()));
+    assert_eq!(parse(Dialect::Vixie, "0 0 * * 5-7"), Ok("0 0 * * 6-1".to_string()));
+    assert_eq!(parse(Dialect::Vixie, "0 0 * * 0-7"), Ok("0 0 * * 1-7".to_string()));
+    assert_eq!(parse(Dialect::Vixie, "0 0 * * SAT"), Ok("0 0 * * 7".to_string()));
+    assert_eq!(parse(Dialect::Vixie, "@weekly"), Ok("0 0 * * 1".to_string()));
+    for s in [
+      "0 0 ? * *",
+      "0 0 * * L",
+      "H 0 * * *",
+      "5/15 * * * *",
+      "0 22-2 * * *",
+      "0 0 0 * * *",
+    ] {
+      assert!(parse(Dialect::Vixie, s).is_err(), "{}", s);
+    }
+    // The 13th or any Friday, as both day fields are restricted
+    assert_eq!(
+      upcoming(Dialect::Vixie, "0 0 13 * 5", 3),
+      vec![at(1, 0, 0, 0), at(8, 0, 0, 0), at(13, 0, 0, 0)]
+    );
+    // Odd days that are Fridays, as the days field starts with `*`
+    assert_eq!(
+      upcoming(Dialect::Vixie, "0 0 */2 * 5", 2),
+      vec![at(1, 0, 0, 0), at(15, 0, 0, 0)]
+    );
+    // Monday, Wednesday, Friday and Sunday
+    assert_eq!(
+      upcoming(Dialect::Vixie, "0 0 * * 1-7/2", 4),
+      vec![at(1, 0, 0, 0), at(3, 0, 0, 0), at(4, 0, 0, 0), at(6, 0, 0, 0)]
+    );
+  }
+
+  #[test]
+  fn test_kubernetes() {
+    assert_eq!(parse(Dialect::Kubernetes, "0 0 ? * 0"), Ok("0 0 ? * 1".to_string()));
+    assert_eq!(parse(Dialect::Kubernetes, "@hourly"), Ok("0 * * * *".to_string()));
+    assert_eq!(
+      parse(Dialect::Kubernetes, "5/15 * * * *"),
+      Ok("5/15 * * * *".to_string())
+    );
+    for s in ["0 0 * * 7", "0 0 * * L", "0 22-2 * * *"] {
+      assert!(parse(Dialect::Kubernetes, s).is_err(), "{}", s);
+    }
+    // Unlike Vixie cron, `*/2` restricts the days field, so the 1st, 3rd, ... or any Friday
+    assert_eq!(
+      upcoming(Dialect::Kubernetes, "0 0 */2 * 5", 3),
+      vec![at(1, 0, 0, 0), at(3, 0, 0, 0), at(5, 0, 0, 0)]
+    );
+  }
+
+  #[test]
+  fn test_quartz() {
+    assert_eq!(
+      parse(Dialect::Quartz, "0 15 10 ? * MON-FRI"),
+      Ok("0 15 10 ? * 2-6".to_string())
+    );
+    assert_eq!(
+      parse(Dialect::Quartz, "0 0 12 1 * ? 2021"),
+      Ok("0 0 12 1 * ? 2021".to_string())
+    );
+    assert_eq!(
+      parse(Dialect::Quartz, "0/30 0 0 ? * L 2020-2030"),
+      Ok("*/30 0 0 ? * L 2020-2030".to_string())
+    );
+    for s in [
+      "0 0 12 * * *",
+      "0 0 12 ? * ?",
+      "0 0 12 1 * 1",
+      "0 12 * * ?",
+      "0 0 12 ? * 0",
+      "0 0 12 1 * ? 1969",
+    ] {
+      assert!(parse(Dialect::Quartz, s).is_err(), "{}", s);
+    }
+    assert_eq!(
+      upcoming(Dialect::Quartz, "*/20 0 0 ? * *", 4),
+      vec![at(1, 0, 0, 0), at(1, 0, 0, 20), at(1, 0, 0, 40), at(2, 0, 0, 0)]
+    );
+    let spec = CronSpecification::new(
+      CronParser::parse_with(Dialect::Quartz, "0 0 0 1 1 ? 2023")
+        .to_result()
+        .unwrap(),
+    );
+    assert!(spec.is_satisfied_by(&Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap()));
+    assert!(!spec.is_satisfied_by(&Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap()));
+  }
+
+  #[test]
+  fn test_spring() {
+    assert_eq!(
+      parse(Dialect::Spring, "0 0 9 * * MON-FRI"),
+      Ok("0 0 9 * * 2-6".to_string())
+    );
+    assert_eq!(parse(Dialect::Spring, "0 0 9 ? * 7"), Ok("0 0 9 ? * 1".to_string()));
+    assert_eq!(parse(Dialect::Spring, "0 0 9 * * 5L"), Ok("0 0 9 * * 6L".to_string()));
+    assert_eq!(
+      parse(Dialect::Spring, "0 0 9 * * FRI#2"),
+      Ok("0 0 9 * * 6#2".to_string())
+    );
+    for s in ["0 9 * * *", "0 0 9 * * 1#6", "0 0 9 * * * 2021"] {
+      assert!(parse(Dialect::Spring, s).is_err(), "{}", s);
+    }
+    // Both day fields must match, so the first Friday from the 2nd to the 9th
+    assert_eq!(upcoming(Dialect::Spring, "0 0 0 2-9 * 5", 1), vec![at(8, 0, 0, 0)]);
+  }
+
+  #[test]
+  fn test_aws() {
+    assert_eq!(parse(Dialect::Aws, "0 10 * * ? *"), Ok("0 10 * * ? *".to_string()));
+    assert_eq!(
+      parse(Dialect::Aws, "0/15 * ? * MON-FRI *"),
+      Ok("*/15 * ? * 2-6 *".to_string())
+    );
+    for s in ["0 10 * * *", "0 10 * * * *", "0 0 10 * * ? *", "0 10 * * ? 2200"] {
+      assert!(parse(Dialect::Aws, s).is_err(), "{}", s);
+    }
+    assert_eq!(
+      upcoming(Dialect::Aws, "0 10 ? * SUN 2021", 2),
+      vec![at(3, 10, 0, 0), at(10, 10, 0, 0)]
+    );
+  }
+}
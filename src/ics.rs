@@ -0,0 +1,323 @@
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::{OffsetComponents, OffsetName};
+use intervals_rs::LimitValue;
+
+use crate::{CronInterval, CronSchedule};
+
+/// The longest content line in octets, excluding the line break.<br/>
+/// 改行を除いたコンテンツ行の最大オクテット数。
+const MAX_LINE_OCTETS: usize = 75;
+
+/// A time zone that can be written to an iCalendar file.<br/>
+/// iCalendarファイルに書き出せるタイムゾーン。
+pub trait IcsTimeZone: TimeZone {
+  /// Returns the `TZID` of the zone, or `None` to write the times in UTC.<br/>
+  /// ゾーンの`TZID`を返す。UTCで書き出す場合は`None`を返す。
+  fn tzid(&self) -> Option<String> {
+    None
+  }
+
+  /// Returns the `VTIMEZONE` lines covering the window, or none if the times are written in UTC.<br/>
+  /// 期間を網羅する`VTIMEZONE`の行を返す。UTCで書き出す場合は返さない。
+  fn vtimezone(&self, _start: &DateTime<Utc>, _end: &DateTime<Utc>) -> Vec<String> {
+    vec![]
+  }
+}
+
+impl IcsTimeZone for Utc {}
+
+impl IcsTimeZone for FixedOffset {}
+
+impl IcsTimeZone for Local {}
+
+impl IcsTimeZone for chrono_tz::Tz {
+  fn tzid(&self) -> Option<String> {
+    Some(self.name().to_string())
+  }
+
+  fn vtimezone(&self, start: &DateTime<Utc>, end: &DateTime<Utc>) -> Vec<String> {
+    let offset = |t: &DateTime<Utc>| self.offset_from_utc_datetime(&t.naive_utc());
+    let mut lines = vec!["BEGIN:VTIMEZONE".to_string(), format!("TZID:{}", self.name())];
+    let mut observance = |at: &DateTime<Utc>, from: &<chrono_tz::Tz as TimeZone>::Offset| {
+      let to = offset(at);
+      let kind = if to.dst_offset().is_zero() {
+        "STANDARD"
+      } else {
+        "DAYLIGHT"
+      };
+      let local = at.naive_utc() + Duration::seconds(from.fix().local_minus_utc() as i64);
+      lines.push(format!("BEGIN:{}", kind));
+      lines.push(format!("DTSTART:{}", format_local(&local)));
+      lines.push(format!("TZOFFSETFROM:{}", format_offset(from.fix())));
+      lines.push(format!("TZOFFSETTO:{}", format_offset(to.fix())));
+      if let Some(name) = to.abbreviation() {
+        lines.push(format!("TZNAME:{}", name));
+      }
+      lines.push(format!("END:{}", kind));
+    };
+    observance(start, &offset(start));
+    // Offsets change at most a few times a year, so scan by day and bisect the day that changes.
+    let mut t = *start;
+    while t < *end {
+      let next = t + Duration::days(1);
+      if offset(&t) != offset(&next) {
+        let (mut before, mut after) = (t, next);
+        while after - before > Duration::seconds(1) {
+          let middle = before + (after - before) / 2;
+          if offset(&middle) == offset(&before) {
+            before = middle;
+          } else {
+            after = middle;
+          }
+        }
+        if after < *end {
+          observance(&after, &offset(&before));
+        }
+      }
+      t = next;
+    }
+    lines.push("END:VTIMEZONE".to_string());
+    lines
+  }
+}
+
+/// A named schedule to write as events, optionally with a duration.<br/>
+/// イベントとして書き出す名前付きのスケジュール。期間を持つこともできる。
+#[derive(Debug, Clone)]
+pub struct IcsSchedule<'a, Tz: TimeZone> {
+  name: String,
+  schedule: &'a CronSchedule<Tz>,
+  duration: Option<Duration>,
+}
+
+impl<'a, Tz: TimeZone> IcsSchedule<'a, Tz> {
+  /// The Factory method.<br/>
+  /// ファクトリメソッド。
+  pub fn new(name: impl Into<String>, schedule: &'a CronSchedule<Tz>) -> Self {
+    Self {
+      name: name.into(),
+      schedule,
+      duration: None,
+    }
+  }
+
+  /// Returns a schedule whose events last for the duration instead of being instants.<br/>
+  /// イベントが瞬間ではなく指定した期間続くスケジュールを返す。
+  pub fn with_duration(self, duration: Duration) -> Self {
+    Self {
+      duration: Some(duration),
+      ..self
+    }
+  }
+}
+
+/// Writes the occurrences of the schedules in `[start, end)` as an iCalendar (RFC 5545) file of VEVENTs.<br/>
+/// `[start, end)`のスケジュールの発生日時を、VEVENTからなるiCalendar(RFC 5545)ファイルとして書き出す。
+///
+/// The times are written with the `TZID` of the window's zone and its `VTIMEZONE` when the zone has one,
+/// such as `chrono_tz::Tz`, and in UTC otherwise.<br/>
+/// 時刻は、`chrono_tz::Tz`のように期間のゾーンが`TZID`を持つ場合はその`TZID`と`VTIMEZONE`を使い、
+/// それ以外の場合はUTCで書き出す。
+///
+/// # Arguments(引数)
+///
+/// * schedules - The named schedules(名前付きのスケジュール)
+/// * start - The start of the window, inclusive(期間の開始日時。含む)
+/// * end - The end of the window, exclusive(期間の終了日時。含まない)
+pub fn to_ics<Tz: IcsTimeZone>(schedules: &[IcsSchedule<'_, Tz>], start: DateTime<Tz>, end: DateTime<Tz>) -> String {
+  let timezone = start.timezone();
+  let tzid = timezone.tzid();
+  let stamp = format_utc(&Utc::now());
+  let format_time = |name: &str, t: &DateTime<Tz>| match &tzid {
+    Some(tzid) => format!("{};TZID={}:{}", name, tzid, format_local(&t.naive_local())),
+    None => format!("{}:{}", name, format_utc(&t.with_timezone(&Utc))),
+  };
+  let mut lines = vec![
+    "BEGIN:VCALENDAR".to_string(),
+    "VERSION:2.0".to_string(),
+    "PRODID:-//chronos-parser-rs//EN".to_string(),
+    "CALSCALE:GREGORIAN".to_string(),
+  ];
+  lines.extend(timezone.vtimezone(&start.with_timezone(&Utc), &end.with_timezone(&Utc)));
+  for (index, ics_schedule) in schedules.iter().enumerate() {
+    let spec = ics_schedule.schedule.specification().anchored_at(&start);
+    let interval = CronInterval::new(LimitValue::Limit(start.clone()), LimitValue::Limit(end.clone()), spec);
+    for occurrence in interval.iter(timezone.clone()).filter(|t| *t < end) {
+      lines.push("BEGIN:VEVENT".to_string());
+      lines.push(format!(
+        "UID:{}-{}-{}@chronos-parser-rs",
+        index,
+        uid_name(&ics_schedule.name),
+        format_utc(&occurrence.with_timezone(&Utc))
+      ));
+      lines.push(format!("DTSTAMP:{}", stamp));
+      lines.push(format_time("DTSTART", &occurrence));
+      if let Some(duration) = ics_schedule.duration {
+        lines.push(format_time("DTEND", &(occurrence.clone() + duration)));
+      }
+      lines.push(format!("SUMMARY:{}", escape(&ics_schedule.name)));
+      lines.push("END:VEVENT".to_string());
+    }
+  }
+  lines.push("END:VCALENDAR".to_string());
+  lines.iter().map(|line| fold(line)).collect()
+}
+
+fn format_local(t: &NaiveDateTime) -> String {
+  t.format("%Y%m%dT%H%M%S").to_string()
+}
+
+fn format_utc(t: &DateTime<Utc>) -> String {
+  t.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn format_offset(offset: FixedOffset) -> String {
+  let seconds = offset.local_minus_utc();
+  let sign = if seconds < 0 { '-' } else { '+' };
+  let seconds = seconds.abs();
+  let hhmm = format!("{}{:02}{:02}", sign, seconds / 3600, seconds % 3600 / 60);
+  match seconds % 60 {
+    0 => hhmm,
+    s => format!("{}{:02}", hhmm, s),
+  }
+}
+
+fn uid_name(name: &str) -> String {
+  name
+    .chars()
+    .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+    .collect()
+}
+
+/// Escapes a TEXT value.<br/>
+/// TEXTの値をエスケープする。
+fn escape(text: &str) -> String {
+  text
+    .replace('\\', "\\\\")
+    .replace(';', "\\;")
+    .replace(',', "\\,")
+    .replace('\n', "\\n")
+}
+
+/// Folds a content line into lines of at most 75 octets, each ending with CRLF.<br/>
+/// コンテンツ行を75オクテット以下の行に折り返す。各行はCRLFで終わる。
+fn fold(line: &str) -> String {
+  let mut folded = String::new();
+  let mut octets = 0;
+  for c in line.chars() {
+    if octets + c.len_utf8() > MAX_LINE_OCTETS {
+      folded.push_str("\r\n ");
+      octets = 1;
+    }
+    folded.push(c);
+    octets += c.len_utf8();
+  }
+  folded.push_str("\r\n");
+  folded
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::{TimeZone, Utc};
+  use chrono_tz::Europe::Berlin;
+
+  use super::*;
+
+  /// Unfolds the file and returns the properties of each component as `(name, value)` pairs.<br/>
+  /// ファイルの折り返しを戻し、各コンポーネントのプロパティを`(名前, 値)`の組で返す。
+  fn parse(ics: &str) -> Vec<(String, Vec<(String, String)>)> {
+    assert!(ics.ends_with("\r\n"));
+    let unfolded = ics.replace("\r\n ", "");
+    let mut stack: Vec<(String, Vec<(String, String)>)> = vec![];
+    let mut components = vec![];
+    for line in unfolded.split_terminator("\r\n") {
+      let (name, value) = line.split_once(':').unwrap();
+      match name {
+        "BEGIN" => stack.push((value.to_string(), vec![])),
+        "END" => {
+          let component = stack.pop().unwrap();
+          assert_eq!(component.0, value);
+          components.push(component);
+        }
+        _ => stack.last_mut().unwrap().1.push((name.to_string(), value.to_string())),
+      }
+    }
+    assert!(stack.is_empty());
+    components
+  }
+
+  fn property<'a>(properties: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    properties.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+  }
+
+  #[test]
+  fn test_to_ics() {
+    let backup = CronSchedule::<Utc>::new("0 */6 * * *").unwrap();
+    let report = CronSchedule::<Utc>::new("30 9 * * *").unwrap();
+    let start = Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap();
+    let end = Utc.with_ymd_and_hms(2021, 1, 2, 0, 0, 0).unwrap();
+    let ics = to_ics(
+      &[
+        IcsSchedule::new("backup", &backup).with_duration(Duration::minutes(45)),
+        IcsSchedule::new("report, daily", &report),
+      ],
+      start,
+      end,
+    );
+    let components = parse(&ics);
+    let calendar = components.last().unwrap();
+    assert_eq!(calendar.0, "VCALENDAR");
+    assert_eq!(property(&calendar.1, "VERSION"), Some("2.0"));
+    let events = components
+      .iter()
+      .filter(|(name, _)| name == "VEVENT")
+      .collect::<Vec<_>>();
+    assert_eq!(events.len(), 5);
+    let starts = events
+      .iter()
+      .filter(|(_, p)| property(p, "SUMMARY") == Some("backup"))
+      .map(|(_, p)| property(p, "DTSTART").unwrap())
+      .collect::<Vec<_>>();
+    let expected = backup
+      .upcoming(start)
+      .take(4)
+      .map(|t| format_utc(&t))
+      .collect::<Vec<_>>();
+    assert_eq!(starts, expected);
+    assert_eq!(property(&events[0].1, "DTEND"), Some("20210101T004500Z"));
+    assert_eq!(property(&events[4].1, "SUMMARY"), Some("report\\, daily"));
+    assert_eq!(property(&events[4].1, "DTEND"), None);
+    assert!(ics.split("\r\n").all(|line| line.len() <= MAX_LINE_OCTETS));
+  }
+
+  #[test]
+  fn test_vtimezone() {
+    let schedule = CronSchedule::<chrono_tz::Tz>::new("0 9 * * *").unwrap();
+    let start = Berlin.with_ymd_and_hms(2021, 3, 27, 0, 0, 0).unwrap();
+    let end = Berlin.with_ymd_and_hms(2021, 3, 29, 0, 0, 0).unwrap();
+    let components = parse(&to_ics(&[IcsSchedule::new("job", &schedule)], start, end));
+    let (_, standard) = components.iter().find(|(name, _)| name == "STANDARD").unwrap();
+    assert_eq!(property(standard, "TZOFFSETTO"), Some("+0100"));
+    let (_, daylight) = components.iter().find(|(name, _)| name == "DAYLIGHT").unwrap();
+    assert_eq!(property(daylight, "DTSTART"), Some("20210328T020000"));
+    assert_eq!(property(daylight, "TZOFFSETFROM"), Some("+0100"));
+    assert_eq!(property(daylight, "TZOFFSETTO"), Some("+0200"));
+    assert_eq!(property(daylight, "TZNAME"), Some("CEST"));
+    let (_, vtimezone) = components.iter().find(|(name, _)| name == "VTIMEZONE").unwrap();
+    assert_eq!(property(vtimezone, "TZID"), Some("Europe/Berlin"));
+    let starts = components
+      .iter()
+      .filter(|(name, _)| name == "VEVENT")
+      .map(|(_, p)| property(p, "DTSTART;TZID=Europe/Berlin").unwrap())
+      .collect::<Vec<_>>();
+    assert_eq!(starts, vec!["20210327T090000", "20210328T090000"]);
+  }
+
+  #[test]
+  fn test_fold() {
+    let line = format!("SUMMARY:{}", "あ".repeat(40));
+    let folded = fold(&line);
+    assert!(folded.split("\r\n").all(|l| l.len() <= MAX_LINE_OCTETS));
+    assert_eq!(folded.replace("\r\n ", "").trim_end(), line);
+  }
+}
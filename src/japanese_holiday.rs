@@ -0,0 +1,342 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Weekday};
+
+use crate::Calendar;
+
+/// A calendar that excludes the national holidays of Japan (国民の祝日・休日).<br/>
+/// 日本の国民の祝日・休日を除外するカレンダー。
+///
+/// The holidays are generated from the rules of the Act on National Holidays (1948-07-20 onwards),
+/// including 振替休日, 国民の休日, the one-off holidays of the imperial family and the 2020/2021 Olympic moves.
+/// The vernal and autumnal equinox days use the usual approximation formula, valid from 1900 to 2150.<br/>
+/// 祝日法(1948年7月20日施行)の規則から祝日を生成する。振替休日、国民の休日、皇室行事による祝日、
+/// 2020年・2021年の東京オリンピックに伴う移動にも対応する。春分の日・秋分の日は1900年から2150年まで有効な近似式で求める。
+#[derive(Debug, Clone, Default)]
+pub struct JapaneseHolidayCalendar {
+  exclude_weekends: bool,
+}
+
+/// The date the Act on National Holidays came into force.<br/>
+/// 祝日法の施行日。
+fn enforcement_date() -> NaiveDate {
+  NaiveDate::from_ymd_opt(1948, 7, 20).unwrap()
+}
+
+fn nth_weekday(year: i32, month: u32, n: u32) -> u32 {
+  NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Mon, n as u8)
+    .unwrap()
+    .day()
+}
+
+/// Returns the day of the vernal equinox in March.<br/>
+/// 3月の春分日を返す。
+fn vernal_equinox_day(year: i32) -> Option<u32> {
+  let y = (year - 1980) as f64;
+  let day = match year {
+    1900..=1979 => 20.8357 + 0.242194 * y - ((year - 1983) / 4) as f64,
+    1980..=2099 => 20.8431 + 0.242194 * y - ((year - 1980) / 4) as f64,
+    2100..=2150 => 21.8510 + 0.242194 * y - ((year - 1980) / 4) as f64,
+    _ => return None,
+  };
+  Some(day.floor() as u32)
+}
+
+/// Returns the day of the autumnal equinox in September.<br/>
+/// 9月の秋分日を返す。
+fn autumnal_equinox_day(year: i32) -> Option<u32> {
+  let y = (year - 1980) as f64;
+  let day = match year {
+    1900..=1979 => 23.2588 + 0.242194 * y - ((year - 1983) / 4) as f64,
+    1980..=2099 => 23.2488 + 0.242194 * y - ((year - 1980) / 4) as f64,
+    2100..=2150 => 24.2488 + 0.242194 * y - ((year - 1980) / 4) as f64,
+    _ => return None,
+  };
+  Some(day.floor() as u32)
+}
+
+/// Returns the name of the 国民の祝日 on the date, excluding 振替休日 and 国民の休日.<br/>
+/// 振替休日と国民の休日を除いた、その日の国民の祝日の名前を返す。
+fn national_holiday_name(date: NaiveDate) -> Option<&'static str> {
+  if date < enforcement_date() {
+    return None;
+  }
+  let (year, month, day) = (date.year(), date.month(), date.day());
+  let name = match (month, day) {
+    (1, 1) if year >= 1949 => "元日",
+    (1, 15) if (1949..=1999).contains(&year) => "成人の日",
+    (1, d) if year >= 2000 && d == nth_weekday(year, 1, 2) => "成人の日",
+    (2, 11) if year >= 1967 => "建国記念の日",
+    (2, 23) if year >= 2020 => "天皇誕生日",
+    (2, 24) if year == 1989 => "昭和天皇の大喪の礼",
+    (3, d) if vernal_equinox_day(year) == Some(d) && year >= 1949 => "春分の日",
+    (4, 10) if year == 1959 => "皇太子明仁親王の結婚の儀",
+    (4, 29) if (1949..=1988).contains(&year) => "天皇誕生日",
+    (4, 29) if (1989..=2006).contains(&year) => "みどりの日",
+    (4, 29) if year >= 2007 => "昭和の日",
+    (5, 1) if year == 2019 => "天皇の即位の日",
+    (5, 3) if year >= 1949 => "憲法記念日",
+    (5, 4) if year >= 2007 => "みどりの日",
+    (5, 5) if year >= 1949 => "こどもの日",
+    (6, 9) if year == 1993 => "皇太子徳仁親王の結婚の儀",
+    (7, 20) if (1996..=2002).contains(&year) => "海の日",
+    (7, 23) if year == 2020 => "海の日",
+    (7, 22) if year == 2021 => "海の日",
+    (7, d) if year >= 2003 && year != 2020 && year != 2021 && d == nth_weekday(year, 7, 3) => "海の日",
+    (7, 24) if year == 2020 => "スポーツの日",
+    (7, 23) if year == 2021 => "スポーツの日",
+    (8, 10) if year == 2020 => "山の日",
+    (8, 8) if year == 2021 => "山の日",
+    (8, 11) if year >= 2016 && year != 2020 && year != 2021 => "山の日",
+    (9, 15) if (1966..=2002).contains(&year) => "敬老の日",
+    (9, d) if year >= 2003 && d == nth_weekday(year, 9, 3) => "敬老の日",
+    (9, d) if autumnal_equinox_day(year) == Some(d) => "秋分の日",
+    (10, 10) if (1966..=1999).contains(&year) => "体育の日",
+    (10, d) if (2000..=2019).contains(&year) && d == nth_weekday(year, 10, 2) => "体育の日",
+    (10, d) if year >= 2022 && d == nth_weekday(year, 10, 2) => "スポーツの日",
+    (10, 22) if year == 2019 => "即位礼正殿の儀",
+    (11, 3) => "文化の日",
+    (11, 12) if year == 1990 => "即位礼正殿の儀",
+    (11, 23) => "勤労感謝の日",
+    (12, 23) if (1989..=2018).contains(&year) => "天皇誕生日",
+    _ => return None,
+  };
+  Some(name)
+}
+
+/// Returns whether the date is a 振替休日 (substitute holiday).<br/>
+/// 振替休日かどうかを返す。
+fn is_substitute_holiday(date: NaiveDate) -> bool {
+  if date.year() < 2007 {
+    let previous = date - Duration::days(1);
+    previous >= NaiveDate::from_ymd_opt(1973, 4, 12).unwrap()
+      && previous.weekday() == Weekday::Sun
+      && national_holiday_name(previous).is_some()
+  } else {
+    let mut previous = date - Duration::days(1);
+    while national_holiday_name(previous).is_some() {
+      if previous.weekday() == Weekday::Sun {
+        return true;
+      }
+      previous -= Duration::days(1);
+    }
+    false
+  }
+}
+
+/// Returns whether the date is a 国民の休日 (a day sandwiched between two national holidays).<br/>
+/// 国民の休日(前日と翌日が国民の祝日である日)かどうかを返す。
+fn is_citizens_holiday(date: NaiveDate) -> bool {
+  date >= NaiveDate::from_ymd_opt(1985, 12, 27).unwrap()
+    && !(date.year() < 2007 && date.weekday() == Weekday::Sun)
+    && national_holiday_name(date - Duration::days(1)).is_some()
+    && national_holiday_name(date + Duration::days(1)).is_some()
+}
+
+impl JapaneseHolidayCalendar {
+  /// Returns a calendar that excludes the national holidays.<br/>
+  /// 国民の祝日・休日を除外するカレンダーを返す。
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Returns a calendar that excludes the national holidays, Saturdays and Sundays.<br/>
+  /// 国民の祝日・休日と土曜日・日曜日を除外するカレンダーを返す。
+  pub fn business_days() -> Self {
+    Self { exclude_weekends: true }
+  }
+
+  /// Returns the name of the holiday on the date.<br/>
+  /// その日の祝日・休日の名前を返す。
+  pub fn holiday_name(date: NaiveDate) -> Option<&'static str> {
+    national_holiday_name(date).or_else(|| {
+      if is_substitute_holiday(date) {
+        Some("振替休日")
+      } else if is_citizens_holiday(date) {
+        Some("国民の休日")
+      } else {
+        None
+      }
+    })
+  }
+
+  /// Returns whether the date is a holiday.<br/>
+  /// その日が祝日・休日かどうかを返す。
+  pub fn is_holiday(date: NaiveDate) -> bool {
+    Self::holiday_name(date).is_some()
+  }
+
+  /// Returns the holidays of the year in chronological order.<br/>
+  /// その年の祝日・休日を日付順に返す。
+  pub fn holidays(year: i32) -> Vec<(NaiveDate, &'static str)> {
+    NaiveDate::from_ymd_opt(year, 1, 1)
+      .unwrap()
+      .iter_days()
+      .take_while(|d| d.year() == year)
+      .filter_map(|d| Self::holiday_name(d).map(|name| (d, name)))
+      .collect()
+  }
+}
+
+impl Calendar for JapaneseHolidayCalendar {
+  fn is_time_included(&self, date_time: &NaiveDateTime) -> bool {
+    let date = date_time.date();
+    let weekend = matches!(date.weekday(), Weekday::Sat | Weekday::Sun);
+    !(self.exclude_weekends && weekend) && !Self::is_holiday(date)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::{TimeZone, Utc};
+
+  use super::*;
+  use crate::CronSchedule;
+
+  fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+  }
+
+  fn days(year: i32) -> Vec<(u32, u32, &'static str)> {
+    JapaneseHolidayCalendar::holidays(year)
+      .into_iter()
+      .map(|(d, name)| (d.month(), d.day(), name))
+      .collect()
+  }
+
+  #[test]
+  fn test_holidays_2019() {
+    assert_eq!(
+      days(2019),
+      vec![
+        (1, 1, "元日"),
+        (1, 14, "成人の日"),
+        (2, 11, "建国記念の日"),
+        (3, 21, "春分の日"),
+        (4, 29, "昭和の日"),
+        (4, 30, "国民の休日"),
+        (5, 1, "天皇の即位の日"),
+        (5, 2, "国民の休日"),
+        (5, 3, "憲法記念日"),
+        (5, 4, "みどりの日"),
+        (5, 5, "こどもの日"),
+        (5, 6, "振替休日"),
+        (7, 15, "海の日"),
+        (8, 11, "山の日"),
+        (8, 12, "振替休日"),
+        (9, 16, "敬老の日"),
+        (9, 23, "秋分の日"),
+        (10, 14, "体育の日"),
+        (10, 22, "即位礼正殿の儀"),
+        (11, 3, "文化の日"),
+        (11, 4, "振替休日"),
+        (11, 23, "勤労感謝の日"),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_holidays_2020() {
+    assert_eq!(
+      days(2020),
+      vec![
+        (1, 1, "元日"),
+        (1, 13, "成人の日"),
+        (2, 11, "建国記念の日"),
+        (2, 23, "天皇誕生日"),
+        (2, 24, "振替休日"),
+        (3, 20, "春分の日"),
+        (4, 29, "昭和の日"),
+        (5, 3, "憲法記念日"),
+        (5, 4, "みどりの日"),
+        (5, 5, "こどもの日"),
+        (5, 6, "振替休日"),
+        (7, 23, "海の日"),
+        (7, 24, "スポーツの日"),
+        (8, 10, "山の日"),
+        (9, 21, "敬老の日"),
+        (9, 22, "秋分の日"),
+        (11, 3, "文化の日"),
+        (11, 23, "勤労感謝の日"),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_holidays_2024() {
+    assert_eq!(
+      days(2024),
+      vec![
+        (1, 1, "元日"),
+        (1, 8, "成人の日"),
+        (2, 11, "建国記念の日"),
+        (2, 12, "振替休日"),
+        (2, 23, "天皇誕生日"),
+        (3, 20, "春分の日"),
+        (4, 29, "昭和の日"),
+        (5, 3, "憲法記念日"),
+        (5, 4, "みどりの日"),
+        (5, 5, "こどもの日"),
+        (5, 6, "振替休日"),
+        (7, 15, "海の日"),
+        (8, 11, "山の日"),
+        (8, 12, "振替休日"),
+        (9, 16, "敬老の日"),
+        (9, 22, "秋分の日"),
+        (9, 23, "振替休日"),
+        (10, 14, "スポーツの日"),
+        (11, 3, "文化の日"),
+        (11, 4, "振替休日"),
+        (11, 23, "勤労感謝の日"),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_historical_rules() {
+    // 国民の休日 before 2007 did not apply to Sundays, and a Monday after a Sunday holiday became 振替休日
+    assert_eq!(JapaneseHolidayCalendar::holiday_name(date(1986, 5, 4)), None);
+    assert_eq!(
+      JapaneseHolidayCalendar::holiday_name(date(1987, 5, 4)),
+      Some("振替休日")
+    );
+    assert_eq!(
+      JapaneseHolidayCalendar::holiday_name(date(1988, 5, 4)),
+      Some("国民の休日")
+    );
+    // 振替休日 started on 1973-04-12
+    assert_eq!(
+      JapaneseHolidayCalendar::holiday_name(date(1973, 4, 30)),
+      Some("振替休日")
+    );
+    assert_eq!(JapaneseHolidayCalendar::holiday_name(date(1973, 2, 12)), None);
+    // Silver week
+    assert_eq!(
+      JapaneseHolidayCalendar::holiday_name(date(2009, 9, 22)),
+      Some("国民の休日")
+    );
+    assert_eq!(
+      JapaneseHolidayCalendar::holiday_name(date(1960, 3, 20)),
+      Some("春分の日")
+    );
+    assert_eq!(
+      JapaneseHolidayCalendar::holiday_name(date(1960, 9, 23)),
+      Some("秋分の日")
+    );
+    assert_eq!(
+      JapaneseHolidayCalendar::holiday_name(date(2021, 8, 9)),
+      Some("振替休日")
+    );
+    assert_eq!(JapaneseHolidayCalendar::holiday_name(date(1948, 7, 19)), None);
+  }
+
+  #[test]
+  fn test_business_days() {
+    let start = Utc.with_ymd_and_hms(2024, 5, 2, 10, 0, 0).unwrap();
+    let dt_vec = CronSchedule::new("0 9 * * *")
+      .unwrap()
+      .upcoming_filtered(start, JapaneseHolidayCalendar::business_days().to_specification())
+      .take(2)
+      .collect::<Vec<_>>();
+    assert_eq!(dt_vec[0], Utc.with_ymd_and_hms(2024, 5, 7, 9, 0, 0).unwrap());
+    assert_eq!(dt_vec[1], Utc.with_ymd_and_hms(2024, 5, 8, 9, 0, 0).unwrap());
+  }
+}
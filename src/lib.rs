@@ -3,20 +3,59 @@
 #![feature(box_patterns)]
 
 pub use ast::*;
+pub use calendar::*;
+pub use clock::*;
+pub use collision::*;
 pub use cron_evaluator::*;
+pub use cron_hash_resolver::*;
 pub use cron_interval::*;
 pub use cron_interval_iterator::*;
+pub use cron_interval_reverse_iterator::*;
 pub use cron_parser::*;
 pub use cron_schedule::*;
 pub use cron_specification::*;
+pub use crontab::*;
+pub use describe::*;
+pub use dialect::*;
+pub use ics::*;
+pub use japanese_holiday::*;
+pub use lint::*;
+pub use misfire::*;
+pub use occurrence_statistics::*;
+pub use on_calendar::*;
+pub use on_calendar_export::*;
+pub use rrule::*;
+pub use schedule_set::*;
+#[cfg(feature = "scheduler")]
+pub use scheduler::*;
 
 mod ast;
+mod calendar;
+mod clock;
+mod collision;
+mod compiled_schedule;
 mod cron_evaluator;
+mod cron_hash_resolver;
 mod cron_interval;
 mod cron_interval_iterator;
+mod cron_interval_reverse_iterator;
 mod cron_parser;
 mod cron_schedule;
 mod cron_specification;
+mod crontab;
+mod describe;
+mod dialect;
+mod ics;
+mod japanese_holiday;
+mod lint;
+mod misfire;
+mod occurrence_statistics;
+mod on_calendar;
+mod on_calendar_export;
+mod rrule;
+mod schedule_set;
+#[cfg(feature = "scheduler")]
+mod scheduler;
 
 #[cfg(test)]
 mod tests {
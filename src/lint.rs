@@ -0,0 +1,477 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{NaiveDate, Offset, TimeZone};
+
+use crate::cron_specification::matches_any_date;
+use crate::{CronField, Crontab, CrontabEntry, CrontabSchedule, DayCombination, Expr, FieldDisplay, RateUnit};
+
+/// How serious a lint diagnostic is.<br/>
+/// lint診断の深刻度。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+  Info,
+  Warning,
+  Error,
+}
+
+impl fmt::Display for Severity {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let s = match self {
+      Severity::Info => "info",
+      Severity::Warning => "warning",
+      Severity::Error => "error",
+    };
+    write!(f, "{}", s)
+  }
+}
+
+/// A lint rule, identified by a machine-readable code such as `never-fires`.<br/>
+/// `never-fires`のような機械可読なコードで識別されるlintルール。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LintCode {
+  /// The schedule matches no date at all, e.g. `0 0 30 2 *`.<br/>
+  /// `0 0 30 2 *`のように、どの日付にもマッチしない。
+  NeverFires,
+  /// The schedule fires more often per hour than the configured limit.<br/>
+  /// 1時間あたりの実行回数が設定した上限を超える。
+  TooFrequent,
+  /// The schedule runs at local times between 01:00 and 03:00, which daylight saving time changes may skip or repeat.<br/>
+  /// 夏時間の切り替えで飛ばされたり繰り返されたりする01:00から03:00の間の時刻に実行する。
+  DstSensitive,
+  /// Day 29, 30 or 31 is skipped in shorter months, where `L` may have been meant.<br/>
+  /// 29日、30日、31日は短い月では飛ばされる。`L`を意図している可能性がある。
+  MonthEndDay,
+  /// Two crontab entries run the same command on equivalent schedules.<br/>
+  /// 2つのcrontabエントリが同等のスケジュールで同じコマンドを実行する。
+  DuplicateEntry,
+  /// Both the days and the day of weeks fields are restricted, whose combination differs between cron implementations.<br/>
+  /// 日と曜日の両方のフィールドが制限されている。その組み合わせ方はcronの実装によって異なる。
+  AmbiguousDays,
+}
+
+impl LintCode {
+  /// Returns the machine-readable code.<br/>
+  /// 機械可読なコードを返す。
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      LintCode::NeverFires => "never-fires",
+      LintCode::TooFrequent => "too-frequent",
+      LintCode::DstSensitive => "dst-sensitive",
+      LintCode::MonthEndDay => "month-end-day",
+      LintCode::DuplicateEntry => "duplicate-entry",
+      LintCode::AmbiguousDays => "ambiguous-days",
+    }
+  }
+
+  /// Returns the severity used unless configured otherwise.<br/>
+  /// 設定しない場合に使われる深刻度を返す。
+  pub fn default_severity(&self) -> Severity {
+    match self {
+      LintCode::NeverFires => Severity::Error,
+      _ => Severity::Warning,
+    }
+  }
+}
+
+impl fmt::Display for LintCode {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+/// A problem found by the `Linter`.<br/>
+/// `Linter`が見つけた問題。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+  pub code: LintCode,
+  pub severity: Severity,
+  pub message: String,
+  /// The 1-based line number in the crontab file, if linted from one.<br/>
+  /// crontabファイルから検査した場合の行番号(1始まり)。
+  pub line: Option<usize>,
+}
+
+impl fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if let Some(line) = self.line {
+      write!(f, "line {}: ", line)?;
+    }
+    write!(f, "{}[{}]: {}", self.severity, self.code, self.message)
+  }
+}
+
+/// Checks expressions and crontab files for risky schedules.<br/>
+/// 式やcrontabファイルに危険なスケジュールがないか検査する。
+#[derive(Debug, Clone)]
+pub struct Linter {
+  max_per_hour: u32,
+  severities: HashMap<LintCode, Option<Severity>>,
+}
+
+impl Default for Linter {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Linter {
+  /// The factory method, with every rule enabled and at most 12 runs per hour.<br/>
+  /// 全てのルールを有効にし、1時間あたりの実行回数の上限を12回とするファクトリメソッド。
+  pub fn new() -> Self {
+    Self {
+      max_per_hour: 12,
+      severities: HashMap::new(),
+    }
+  }
+
+  /// Sets how many runs per hour are allowed before `too-frequent` is reported.<br/>
+  /// `too-frequent`を報告するまでに許容する1時間あたりの実行回数を設定する。
+  pub fn with_max_per_hour(mut self, max_per_hour: u32) -> Self {
+    self.max_per_hour = max_per_hour;
+    self
+  }
+
+  /// Overrides the severity of a rule.<br/>
+  /// ルールの深刻度を上書きする。
+  pub fn with_severity(mut self, code: LintCode, severity: Severity) -> Self {
+    self.severities.insert(code, Some(severity));
+    self
+  }
+
+  /// Disables a rule.<br/>
+  /// ルールを無効にする。
+  pub fn without(mut self, code: LintCode) -> Self {
+    self.severities.insert(code, None);
+    self
+  }
+
+  fn severity(&self, code: LintCode) -> Option<Severity> {
+    match self.severities.get(&code) {
+      Some(severity) => *severity,
+      None => Some(code.default_severity()),
+    }
+  }
+
+  fn report(&self, diagnostics: &mut Vec<Diagnostic>, code: LintCode, message: String) {
+    if let Some(severity) = self.severity(code) {
+      diagnostics.push(Diagnostic {
+        code,
+        severity,
+        message,
+        line: None,
+      });
+    }
+  }
+
+  /// Checks an expression.<br/>
+  /// 式を検査する。
+  pub fn lint_expr(&self, expr: &Expr) -> Vec<Diagnostic> {
+    self.lint(expr, true)
+  }
+
+  /// Checks the entries of a crontab file, skipping `dst-sensitive` for entries whose `CRON_TZ` has no daylight
+  /// saving time.<br/>
+  /// crontabファイルのエントリを検査する。`CRON_TZ`が夏時間を持たないエントリでは`dst-sensitive`を検査しない。
+  pub fn lint_crontab(&self, crontab: &Crontab) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    for (index, entry) in crontab.entries.iter().enumerate() {
+      let mut entry_diagnostics = match &entry.schedule {
+        CrontabSchedule::Cron(expr) => self.lint(expr, observes_dst(entry.cron_tz.as_deref())),
+        CrontabSchedule::Reboot => vec![],
+      };
+      if let Some(original) = crontab.entries[..index].iter().find(|e| is_duplicate(e, entry)) {
+        self.report(
+          &mut entry_diagnostics,
+          LintCode::DuplicateEntry,
+          format!("duplicates the entry on line {}", original.line),
+        );
+      }
+      for diagnostic in &mut entry_diagnostics {
+        diagnostic.line = Some(entry.line);
+      }
+      diagnostics.extend(entry_diagnostics);
+    }
+    diagnostics
+  }
+
+  fn lint(&self, expr: &Expr, dst: bool) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let (secs, mins, hours, days, months, day_of_weeks, day_combination) = match expr {
+      Expr::CronExpr {
+        secs,
+        mins,
+        hours,
+        days,
+        months,
+        day_of_weeks,
+        day_combination,
+        ..
+      } => (secs, mins, hours, days, months, day_of_weeks, day_combination),
+      Expr::RateExpr { value, unit } => {
+        let per_hour = match unit {
+          RateUnit::Minute => 60_u32.div_ceil(*value),
+          _ => 1,
+        };
+        if per_hour > self.max_per_hour {
+          self.report(
+            &mut diagnostics,
+            LintCode::TooFrequent,
+            format!("fires {} times per hour, more than {}", per_hour, self.max_per_hour),
+          );
+        }
+        return diagnostics;
+      }
+      _ => return diagnostics,
+    };
+
+    if !matches_any_date(&[expr]) {
+      self.report(
+        &mut diagnostics,
+        LintCode::NeverFires,
+        format!("{} matches no date", expr),
+      );
+      return diagnostics;
+    }
+
+    let count = |field: CronField, e: &Expr| field.values(e).map(|v| v.len() as u32);
+    let secs_count = if **secs == Expr::NoOp {
+      Some(1)
+    } else {
+      count(CronField::Second, secs)
+    };
+    if let Some(per_hour) = secs_count.zip(count(CronField::Minute, mins)).map(|(s, m)| s * m) {
+      if per_hour > self.max_per_hour {
+        self.report(
+          &mut diagnostics,
+          LintCode::TooFrequent,
+          format!("fires {} times per hour, more than {}", per_hour, self.max_per_hour),
+        );
+      }
+    }
+
+    if let Some(values) = CronField::Hour
+      .values(hours)
+      .filter(|_| dst && is_restricted(CronField::Hour, hours))
+    {
+      let sensitive = values.iter().filter(|h| (1..3).contains(*h)).collect::<Vec<_>>();
+      if !sensitive.is_empty() {
+        let hours = sensitive.iter().map(|h| h.to_string()).collect::<Vec<_>>().join(",");
+        self.report(
+          &mut diagnostics,
+          LintCode::DstSensitive,
+          format!(
+            "hours: {} falls between 01:00 and 03:00, where daylight saving time changes may skip or repeat runs",
+            hours
+          ),
+        );
+      }
+    }
+
+    if let (Some(day_values), Some(month_values)) = (CronField::Day.values(days), CronField::Month.values(months)) {
+      let shortest = month_values.iter().map(|m| days_in_month(*m)).min().unwrap_or(31);
+      let skipped = day_values
+        .iter()
+        .filter(|d| **d > shortest)
+        .map(|d| d.to_string())
+        .collect::<Vec<_>>();
+      if is_restricted(CronField::Day, days) && !skipped.is_empty() {
+        self.report(
+          &mut diagnostics,
+          LintCode::MonthEndDay,
+          format!(
+            "days: {} is skipped in shorter months, use L for the last day of the month",
+            skipped.join(",")
+          ),
+        );
+      }
+    }
+
+    if is_restricted(CronField::Day, days) && is_restricted(CronField::DayOfWeek, day_of_weeks) {
+      let semantics = match day_combination {
+        DayCombination::Both => "only when both match",
+        DayCombination::Either => "when either matches",
+      };
+      self.report(
+        &mut diagnostics,
+        LintCode::AmbiguousDays,
+        format!(
+          "days {} and day of weeks {} are both restricted, this schedule fires {}",
+          FieldDisplay(CronField::Day, days),
+          FieldDisplay(CronField::DayOfWeek, day_of_weeks),
+          semantics
+        ),
+      );
+    }
+    diagnostics
+  }
+}
+
+/// Whether the field matches fewer than all of its values.
+fn is_restricted(field: CronField, expr: &Expr) -> bool {
+  match field.values(expr) {
+    Some(values) => values.len() < (field.max() - field.min() + 1) as usize,
+    None => true,
+  }
+}
+
+/// The days of the month in a non-leap year, since a day that only exists in leap years is skipped in the others.
+fn days_in_month(month: u8) -> u8 {
+  match month {
+    2 => 28,
+    4 | 6 | 9 | 11 => 30,
+    _ => 31,
+  }
+}
+
+/// Whether the timezone may observe daylight saving time, which is assumed when it is unknown.
+fn observes_dst(cron_tz: Option<&str>) -> bool {
+  let Some(tz) = cron_tz.and_then(|name| name.parse::<chrono_tz::Tz>().ok()) else {
+    return true;
+  };
+  let offset = |month: u32| {
+    tz.offset_from_utc_datetime(
+      &NaiveDate::from_ymd_opt(2024, month, 15)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap(),
+    )
+    .fix()
+  };
+  offset(1) != offset(7)
+}
+
+fn equivalent(a: &Expr, b: &Expr) -> bool {
+  match (a, b) {
+    (
+      Expr::CronExpr {
+        secs,
+        mins,
+        hours,
+        days,
+        months,
+        day_of_weeks,
+        years,
+        day_combination,
+      },
+      Expr::CronExpr {
+        secs: secs2,
+        mins: mins2,
+        hours: hours2,
+        days: days2,
+        months: months2,
+        day_of_weeks: day_of_weeks2,
+        years: years2,
+        day_combination: day_combination2,
+      },
+    ) => {
+      let same = |field: CronField, a: &Expr, b: &Expr| match (field.values(a), field.values(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+      };
+      (secs == secs2 || same(CronField::Second, secs, secs2))
+        && same(CronField::Minute, mins, mins2)
+        && same(CronField::Hour, hours, hours2)
+        && same(CronField::Day, days, days2)
+        && same(CronField::Month, months, months2)
+        && same(CronField::DayOfWeek, day_of_weeks, day_of_weeks2)
+        && (years == years2 || same(CronField::Year, years, years2))
+        && day_combination == day_combination2
+    }
+    _ => a == b,
+  }
+}
+
+fn is_duplicate(a: &CrontabEntry, b: &CrontabEntry) -> bool {
+  let schedules = match (&a.schedule, &b.schedule) {
+    (CrontabSchedule::Cron(a), CrontabSchedule::Cron(b)) => equivalent(a, b),
+    (a, b) => a == b,
+  };
+  schedules && a.command.trim() == b.command.trim() && a.user == b.user && a.cron_tz == b.cron_tz
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{CronParser, CrontabKind, Dialect};
+
+  fn codes(s: &str) -> Vec<LintCode> {
+    codes_with(Dialect::Native, s)
+  }
+
+  fn codes_with(dialect: Dialect, s: &str) -> Vec<LintCode> {
+    let expr = CronParser::parse_with(dialect, s).to_result().unwrap();
+    Linter::new().lint_expr(&expr).into_iter().map(|d| d.code).collect()
+  }
+
+  #[test]
+  fn test_lint_expr() {
+    assert_eq!(codes("0 9 * * *"), vec![]);
+    assert_eq!(codes("0 0 30 2 *"), vec![LintCode::NeverFires]);
+    assert_eq!(codes("0 0 29 2 *"), vec![LintCode::MonthEndDay]);
+    assert_eq!(codes("0 0 31 1,3 *"), vec![]);
+    assert_eq!(codes("0 0 L * *"), vec![]);
+    assert_eq!(codes("*/5 * * * *"), vec![]);
+    assert_eq!(codes("* * * * *"), vec![LintCode::TooFrequent]);
+    assert_eq!(codes("30 2 * * *"), vec![LintCode::DstSensitive]);
+    assert_eq!(codes("0 9 13 * 6"), vec![LintCode::AmbiguousDays]);
+    assert_eq!(codes("0 9 * * 6#5"), vec![]);
+    assert_eq!(codes_with(Dialect::Quartz, "0 0 0 1 1 ? 2020"), vec![]);
+    assert_eq!(
+      codes_with(Dialect::Quartz, "0 0 0 29 2 ? 2021-2023"),
+      vec![LintCode::NeverFires]
+    );
+
+    let expr = CronParser::parse_with(Dialect::Vixie, "0 9 13 * 5")
+      .to_result()
+      .unwrap();
+    let diagnostics = Linter::new().lint_expr(&expr);
+    assert_eq!(
+      diagnostics[0].to_string(),
+      "warning[ambiguous-days]: days 13 and day of weeks 6 are both restricted, this schedule fires when either matches"
+    );
+
+    let expr = CronParser::parse_with(Dialect::Aws, "rate(1 minute)")
+      .to_result()
+      .unwrap();
+    let diagnostics = Linter::new().lint_expr(&expr);
+    assert_eq!(diagnostics[0].message, "fires 60 times per hour, more than 12");
+  }
+
+  #[test]
+  fn test_configuration() {
+    let expr = CronParser::parse("* * * * *").to_result().unwrap();
+    assert!(Linter::new().with_max_per_hour(60).lint_expr(&expr).is_empty());
+    assert!(Linter::new().without(LintCode::TooFrequent).lint_expr(&expr).is_empty());
+    let diagnostics = Linter::new()
+      .with_severity(LintCode::TooFrequent, Severity::Error)
+      .lint_expr(&expr);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+  }
+
+  #[test]
+  fn test_lint_crontab() {
+    let source = "30 2 * * * /usr/bin/backup\n\
+                  0 0 30 2 * /usr/bin/never\n\
+                  30 2 * * 0-6 /usr/bin/backup\n\
+                  0 0 1 * 1 /usr/bin/monthly\n\
+                  CRON_TZ=Asia/Tokyo\n\
+                  30 2 * * * /usr/bin/report\n";
+    let crontab = Crontab::parse(CrontabKind::User, source).unwrap();
+    let diagnostics = Linter::new()
+      .lint_crontab(&crontab)
+      .iter()
+      .map(|d| d.to_string())
+      .collect::<Vec<_>>();
+    assert_eq!(
+      diagnostics,
+      vec![
+        "line 1: warning[dst-sensitive]: hours: 2 falls between 01:00 and 03:00, where daylight saving time changes may skip or repeat runs",
+        "line 2: error[never-fires]: 0 0 30 2 * matches no date",
+        "line 3: warning[dst-sensitive]: hours: 2 falls between 01:00 and 03:00, where daylight saving time changes may skip or repeat runs",
+        "line 3: warning[duplicate-entry]: duplicates the entry on line 1",
+        "line 4: warning[ambiguous-days]: days 1 and day of weeks 2 are both restricted, this schedule fires when either matches",
+      ]
+    );
+  }
+}
@@ -0,0 +1,135 @@
+use chrono::{DateTime, Duration, TimeZone};
+use intervals_rs::LimitValue;
+
+use crate::{CronInterval, CronSchedule, Expr};
+
+/// What to do with the runs a schedule missed while its scheduler was not running, after Quartz's misfire
+/// instructions.<br/>
+/// スケジューラが停止している間にスケジュールが逃した実行をどう扱うか。Quartzのミスファイア指示に倣う。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MisfirePolicy {
+  /// Runs once now if any run was missed.<br/>
+  /// 逃した実行があれば、現在日時に1回だけ実行する。
+  FireOnceNow,
+  /// Runs every missed run, oldest first.<br/>
+  /// 逃した全ての実行を古い順に実行する。
+  FireAllMissed,
+  /// Runs nothing and waits for the next occurrence.<br/>
+  /// 何も実行せず、次の実行日時を待つ。
+  SkipToNext,
+  /// Runs the latest missed run if it is no older than the grace period, and skips the others.<br/>
+  /// 最後に逃した実行が猶予期間内であればそれを実行し、他は飛ばす。
+  FireWithinGrace(Duration),
+}
+
+impl MisfirePolicy {
+  /// Returns the instants to execute for the runs missed after the last successful run up to now.<br/>
+  /// 最後に成功した実行から現在日時までに逃した実行について、実行すべき日時を返す。
+  ///
+  /// # Arguments(引数)
+  ///
+  /// * schedule - The schedule that misfired(ミスファイアしたスケジュール)
+  /// * last_run - The last successful run(最後に成功した実行の日時)
+  /// * now - The current date and time(現在日時)
+  pub fn resolve<Tz: TimeZone>(
+    &self,
+    schedule: &CronSchedule<Tz>,
+    last_run: DateTime<Tz>,
+    now: DateTime<Tz>,
+  ) -> Vec<DateTime<Tz>> {
+    match self {
+      MisfirePolicy::FireOnceNow => match latest_missed(schedule, &last_run, &now) {
+        Some(_) => vec![now],
+        None => vec![],
+      },
+      MisfirePolicy::FireAllMissed => schedule.missed(last_run, now),
+      MisfirePolicy::SkipToNext => vec![],
+      MisfirePolicy::FireWithinGrace(grace) => latest_missed(schedule, &last_run, &now)
+        .filter(|missed| now.clone() - missed.clone() <= *grace)
+        .into_iter()
+        .collect(),
+    }
+  }
+}
+
+/// Looks back from now for the latest run after the last run, counting a `rate(...)` from the last run.
+fn latest_missed<Tz: TimeZone>(
+  schedule: &CronSchedule<Tz>,
+  last_run: &DateTime<Tz>,
+  now: &DateTime<Tz>,
+) -> Option<DateTime<Tz>> {
+  if now <= last_run {
+    return None;
+  }
+  match schedule.expr() {
+    Expr::RateExpr { value, unit } => {
+      let period = *value as i64 * unit.seconds();
+      let periods = (now.clone() - last_run.clone()).num_seconds() / period;
+      (periods > 0).then(|| last_run.clone() + Duration::seconds(periods * period))
+    }
+    _ => CronInterval::new(
+      LimitValue::Limit(last_run.clone()),
+      LimitValue::Limit(now.clone()),
+      schedule.specification(),
+    )
+    .iter_rev(now.timezone())
+    .next()
+    .filter(|missed| missed > last_run),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::Dialect;
+  use chrono::Utc;
+
+  fn at(h: u32, m: u32, s: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2021, 1, 1, h, m, s).unwrap()
+  }
+
+  #[test]
+  fn test_resolve() {
+    let schedule = CronSchedule::<Utc>::new("0 * * * *").unwrap();
+    let (last_run, now) = (at(1, 0, 0), at(4, 30, 0));
+    assert_eq!(MisfirePolicy::FireOnceNow.resolve(&schedule, last_run, now), vec![now]);
+    assert_eq!(
+      MisfirePolicy::FireAllMissed.resolve(&schedule, last_run, now),
+      vec![at(2, 0, 0), at(3, 0, 0), at(4, 0, 0)]
+    );
+    assert!(MisfirePolicy::SkipToNext.resolve(&schedule, last_run, now).is_empty());
+    assert_eq!(
+      MisfirePolicy::FireWithinGrace(Duration::minutes(45)).resolve(&schedule, last_run, now),
+      vec![at(4, 0, 0)]
+    );
+    assert!(MisfirePolicy::FireWithinGrace(Duration::minutes(15))
+      .resolve(&schedule, last_run, now)
+      .is_empty());
+
+    // A last run that took a while to finish, and nothing missed since
+    assert_eq!(
+      MisfirePolicy::FireAllMissed.resolve(&schedule, at(1, 0, 30), at(2, 0, 0)),
+      vec![at(2, 0, 0)]
+    );
+    assert!(MisfirePolicy::FireOnceNow
+      .resolve(&schedule, at(4, 0, 0), now)
+      .is_empty());
+    assert!(MisfirePolicy::FireAllMissed
+      .resolve(&schedule, at(4, 0, 0), now)
+      .is_empty());
+  }
+
+  #[test]
+  fn test_rate() {
+    let schedule = CronSchedule::<Utc>::with_dialect(Dialect::Aws, "rate(90 minutes)").unwrap();
+    let (last_run, now) = (at(0, 0, 0), at(4, 0, 0));
+    assert_eq!(
+      MisfirePolicy::FireAllMissed.resolve(&schedule, last_run, now),
+      vec![at(1, 30, 0), at(3, 0, 0)]
+    );
+    assert_eq!(
+      MisfirePolicy::FireWithinGrace(Duration::hours(2)).resolve(&schedule, last_run, now),
+      vec![at(3, 0, 0)]
+    );
+  }
+}
@@ -0,0 +1,197 @@
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Offset, TimeZone, Timelike};
+
+use crate::compiled_schedule::CompiledSchedule;
+
+/// Counts and spacing of the occurrences of a schedule in a window, returned by `CronSchedule::statistics`.<br/>
+/// `CronSchedule::statistics`が返す、期間内のスケジュールの実行回数と間隔。
+#[derive(Debug, Clone, PartialEq)]
+pub struct OccurrenceStatistics<Tz: TimeZone> {
+  pub count: u64,
+  pub first: Option<DateTime<Tz>>,
+  pub last: Option<DateTime<Tz>>,
+  pub min_gap: Option<Duration>,
+  pub max_gap: Option<Duration>,
+  pub mean_gap: Option<Duration>,
+  /// The number of occurrences per local hour of the day.<br/>
+  /// ローカル時刻の時ごとの実行回数。
+  pub per_hour: [u64; 24],
+  /// The number of occurrences per weekday, starting from Sunday.<br/>
+  /// 曜日ごとの実行回数。日曜日から始まる。
+  pub per_weekday: [u64; 7],
+}
+
+impl<Tz: TimeZone> OccurrenceStatistics<Tz> {
+  fn empty() -> Self {
+    Self {
+      count: 0,
+      first: None,
+      last: None,
+      min_gap: None,
+      max_gap: None,
+      mean_gap: None,
+      per_hour: [0; 24],
+      per_weekday: [0; 7],
+    }
+  }
+
+  fn gap(&mut self, gap: Duration) {
+    self.min_gap = Some(self.min_gap.map_or(gap, |g| g.min(gap)));
+    self.max_gap = Some(self.max_gap.map_or(gap, |g| g.max(gap)));
+  }
+
+  fn record(&mut self, date_time: DateTime<Tz>) {
+    if let Some(last) = &self.last {
+      self.gap(date_time.clone() - last.clone());
+    }
+    self.count += 1;
+    self.per_hour[date_time.hour() as usize] += 1;
+    self.per_weekday[date_time.weekday().num_days_from_sunday() as usize] += 1;
+    if self.first.is_none() {
+      self.first = Some(date_time.clone());
+    }
+    self.last = Some(date_time);
+  }
+
+  /// Computes the statistics day by day over the matching days of the window. Days whose UTC offset does not change
+  /// and that lie entirely in the window are added from the times of day at once, and the others time by time.
+  pub(crate) fn compute(compiled: &CompiledSchedule, start: &DateTime<Tz>, end: &DateTime<Tz>) -> Self {
+    let mut statistics = Self::empty();
+    let times = compiled.times();
+    if start >= end || times.is_empty() {
+      return statistics;
+    }
+    let timezone = start.timezone();
+    let mut per_hour = [0; 24];
+    for time in times {
+      per_hour[(*time / 3600) as usize] += 1;
+    }
+    let gaps = times.windows(2).map(|w| w[1] - w[0]).collect::<Vec<_>>();
+    let (min_gap, max_gap) = (gaps.iter().min(), gaps.iter().max());
+
+    let first_date = start.naive_local().date();
+    let last_date = end.naive_local().date();
+    let mut month = first_date.with_day(1).unwrap();
+    while month <= last_date {
+      let mask = compiled.day_mask(month.year(), month.month());
+      for day in (1..=31).filter(|d| mask & (1 << d) != 0) {
+        let date = month.with_day(day).unwrap();
+        if date < first_date || date > last_date {
+          continue;
+        }
+        let midnight = |date: NaiveDate| {
+          timezone
+            .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+            .single()
+        };
+        let regular = match (midnight(date), date.succ_opt().and_then(midnight)) {
+          (Some(from), Some(to)) => from.offset().fix() == to.offset().fix() && from >= *start && to <= *end,
+          _ => false,
+        };
+        if regular {
+          let midnight = midnight(date).unwrap();
+          let at = |time: u32| midnight.clone() + Duration::seconds(time as i64);
+          let (first, last) = (at(times[0]), at(times[times.len() - 1]));
+          if let Some(previous) = &statistics.last {
+            statistics.gap(first.clone() - previous.clone());
+          }
+          if let (Some(min_gap), Some(max_gap)) = (min_gap, max_gap) {
+            statistics.gap(Duration::seconds(*min_gap as i64));
+            statistics.gap(Duration::seconds(*max_gap as i64));
+          }
+          statistics.count += times.len() as u64;
+          for (hour, count) in per_hour.iter().enumerate() {
+            statistics.per_hour[hour] += count;
+          }
+          statistics.per_weekday[date.weekday().num_days_from_sunday() as usize] += times.len() as u64;
+          statistics.first.get_or_insert(first);
+          statistics.last = Some(last);
+        } else {
+          let date_times = compiled.occurrences_on(date, &timezone);
+          for date_time in date_times.into_iter().filter(|d| d >= start && d < end) {
+            statistics.record(date_time);
+          }
+        }
+      }
+      month = month.checked_add_months(Months::new(1)).unwrap();
+    }
+    if let (Some(first), Some(last)) = (&statistics.first, &statistics.last) {
+      if statistics.count > 1 {
+        let span = (last.clone() - first.clone()).num_milliseconds();
+        statistics.mean_gap = Some(Duration::milliseconds(span / (statistics.count as i64 - 1)));
+      }
+    }
+    statistics
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::{TimeZone, Utc};
+  use chrono_tz::Europe::Berlin;
+
+  use crate::CronSchedule;
+
+  /// Checks the statistics against the occurrences the iterator yields.
+  fn assert_consistent<Tz: TimeZone>(s: &str, start: chrono::DateTime<Tz>, end: chrono::DateTime<Tz>) {
+    let schedule = CronSchedule::<Tz>::new(s).unwrap();
+    let statistics = schedule.statistics(start.clone(), end.clone()).unwrap();
+    let occurrences = schedule.upcoming(start).take_while(|d| *d < end).collect::<Vec<_>>();
+    assert_eq!(statistics.count, occurrences.len() as u64, "{}", s);
+    assert_eq!(statistics.first, occurrences.first().cloned(), "{}", s);
+    assert_eq!(statistics.last, occurrences.last().cloned(), "{}", s);
+    let gaps = occurrences
+      .windows(2)
+      .map(|w| w[1].clone() - w[0].clone())
+      .collect::<Vec<_>>();
+    assert_eq!(statistics.min_gap, gaps.iter().min().cloned(), "{}", s);
+    assert_eq!(statistics.max_gap, gaps.iter().max().cloned(), "{}", s);
+  }
+
+  #[test]
+  fn test_statistics() {
+    let start = Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap();
+    let end = Utc.with_ymd_and_hms(2021, 2, 1, 0, 0, 0).unwrap();
+    let statistics = CronSchedule::<Utc>::new("0 9,17 * * 2-6")
+      .unwrap()
+      .statistics(start, end)
+      .unwrap();
+    // January 2021 has 21 weekdays
+    assert_eq!(statistics.count, 42);
+    assert_eq!(statistics.per_hour[9], 21);
+    assert_eq!(statistics.per_hour[17], 21);
+    assert_eq!(statistics.per_weekday, [0, 8, 8, 8, 8, 10, 0]);
+    assert_eq!(
+      statistics.first,
+      Some(Utc.with_ymd_and_hms(2021, 1, 1, 9, 0, 0).unwrap())
+    );
+    assert_eq!(
+      statistics.last,
+      Some(Utc.with_ymd_and_hms(2021, 1, 29, 17, 0, 0).unwrap())
+    );
+    assert_eq!(statistics.min_gap, Some(chrono::Duration::hours(8)));
+    assert_eq!(statistics.max_gap, Some(chrono::Duration::hours(64)));
+
+    for s in ["*/20 * * * *", "0 0 L * *", "30 8-10 * * 6#2", "0 0 1-10/3,28-31 * *"] {
+      assert_consistent(s, start, end);
+    }
+    let start = Utc.with_ymd_and_hms(2021, 1, 1, 10, 7, 0).unwrap();
+    let end = Utc.with_ymd_and_hms(2021, 1, 3, 10, 30, 0).unwrap();
+    assert_consistent("*/30 * * * *", start, end);
+  }
+
+  #[test]
+  fn test_dst() {
+    // Clocks go forward at 02:00 on 2021-03-28 and back at 03:00 on 2021-10-31 in Berlin
+    let start = Berlin.with_ymd_and_hms(2021, 3, 27, 0, 0, 0).unwrap();
+    let end = Berlin.with_ymd_and_hms(2021, 3, 30, 0, 0, 0).unwrap();
+    assert_consistent("30 * * * *", start, end);
+    let start = Berlin.with_ymd_and_hms(2021, 10, 30, 0, 0, 0).unwrap();
+    let end = Berlin.with_ymd_and_hms(2021, 11, 2, 0, 0, 0).unwrap();
+    assert_consistent("30 * * * *", start, end);
+    let statistics = CronSchedule::<chrono_tz::Tz>::new("30 2 * * *")
+      .unwrap()
+      .statistics(start, end)
+      .unwrap();
+    assert_eq!(statistics.count, 4);
+  }
+}
@@ -0,0 +1,474 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, TimeZone};
+use intervals_rs::LimitValue;
+
+use crate::compiled_schedule::CompiledSchedule;
+use crate::cron_interval::align_up;
+use crate::{
+  CronField, CronInterval, CronIntervalIterator, CronSpecification, DayCombination, Expr, Specification,
+  SpecificationExt, YEAR_BASE,
+};
+
+/// The shorthands systemd expands before parsing a calendar event.<br/>
+/// systemdがカレンダーイベントの解析前に展開する省略形。
+const SHORTHANDS: [(&str, &str); 9] = [
+  ("minutely", "*-*-* *:*:00"),
+  ("hourly", "*-*-* *:00:00"),
+  ("daily", "*-*-* 00:00:00"),
+  ("monthly", "*-*-01 00:00:00"),
+  ("weekly", "Mon *-*-* 00:00:00"),
+  ("yearly", "*-01-01 00:00:00"),
+  ("annually", "*-01-01 00:00:00"),
+  ("quarterly", "*-01,04,07,10-01 00:00:00"),
+  ("semiannually", "*-01,07-01 00:00:00"),
+];
+
+/// Weekday names in the native numbering, where Sunday is 1.<br/>
+/// ネイティブの番号付け(日曜日が1)による曜日名。
+const WEEKDAYS: [(&str, &str, u8); 7] = [
+  ("mon", "monday", 2),
+  ("tue", "tuesday", 3),
+  ("wed", "wednesday", 4),
+  ("thu", "thursday", 5),
+  ("fri", "friday", 6),
+  ("sat", "saturday", 7),
+  ("sun", "sunday", 1),
+];
+
+/// A systemd `OnCalendar=` calendar event such as `Mon..Fri *-*-* 09:00:00`.<br/>
+/// `Mon..Fri *-*-* 09:00:00`のようなsystemdの`OnCalendar=`カレンダーイベント。
+///
+/// The event is held as a `CronExpr` with seconds and years, whose day fields must both match
+/// as systemd requires.<br/>
+/// イベントは秒と年を持つ`CronExpr`として保持する。systemdと同様に日と曜日の両方がマッチする必要がある。
+#[derive(Debug, Clone, PartialEq)]
+pub struct OnCalendar {
+  expr: Expr,
+  timezone: Option<chrono_tz::Tz>,
+}
+
+impl OnCalendar {
+  /// Parses a calendar event.<br/>
+  /// カレンダーイベントを解析する。
+  ///
+  /// # Arguments(引数)
+  ///
+  /// * source - The value of `OnCalendar=`(`OnCalendar=`の値)
+  ///
+  /// # Return values(戻り値)
+  ///
+  /// * Err:
+  ///   - If the event is malformed or a value is out of range
+  ///   - イベントの形式が不正な場合、または値が範囲外の場合
+  /// * Ok
+  ///   - If the event is valid
+  ///   - イベントが正しい場合
+  pub fn parse(source: &str) -> Result<Self> {
+    Self::parse_tokens(source.split_whitespace().collect())
+      .map_err(|e| anyhow!("Failed to parse calendar event: {}", e))
+  }
+
+  fn parse_tokens(mut tokens: Vec<&str>) -> Result<Self, String> {
+    if tokens.is_empty() {
+      return Err("empty calendar event".to_string());
+    }
+    let timezone = match tokens.as_slice() {
+      [.., last] if tokens.len() > 1 => last.parse::<chrono_tz::Tz>().ok(),
+      _ => None,
+    };
+    if timezone.is_some() {
+      tokens.pop();
+    }
+    if let [token] = tokens.as_slice() {
+      if let Some((_, expanded)) = SHORTHANDS.iter().find(|(name, _)| name.eq_ignore_ascii_case(token)) {
+        tokens = expanded.split_whitespace().collect();
+      }
+    }
+    let mut tokens = tokens.into_iter().peekable();
+    let day_of_weeks = match tokens.next_if(|t| t.starts_with(|c: char| c.is_ascii_alphabetic())) {
+      Some(token) => weekdays(token)?,
+      None => Expr::AnyValueExpr,
+    };
+    let (years, months, days) = match tokens.next_if(|t| !t.contains(':')) {
+      Some(token) => date(token)?,
+      None => (Expr::NoOp, Expr::AnyValueExpr, Expr::AnyValueExpr),
+    };
+    let (hours, mins, secs) = match tokens.next_if(|t| t.contains(':')) {
+      Some(token) => time(token)?,
+      None => (Expr::ValueExpr(0), Expr::ValueExpr(0), Expr::ValueExpr(0)),
+    };
+    if let Some(token) = tokens.next() {
+      return Err(format!("unexpected {}", token));
+    }
+    Ok(Self {
+      expr: Expr::CronExpr {
+        secs: Box::new(secs),
+        mins: Box::new(mins),
+        hours: Box::new(hours),
+        days: Box::new(days),
+        months: Box::new(months),
+        day_of_weeks: Box::new(day_of_weeks),
+        years: Box::new(years),
+        day_combination: DayCombination::Both,
+      },
+      timezone,
+    })
+  }
+
+  /// Returns the event as a `CronExpr`.<br/>
+  /// イベントを`CronExpr`として返す。
+  pub fn expr(&self) -> &Expr {
+    &self.expr
+  }
+
+  /// Returns the trailing time zone, if any.<br/>
+  /// 末尾のタイムゾーンがあれば返す。
+  pub fn timezone(&self) -> Option<chrono_tz::Tz> {
+    self.timezone
+  }
+
+  /// Returns the specification of this event, evaluated in the trailing time zone if any.<br/>
+  /// このイベントの仕様を返す。末尾のタイムゾーンがあればそのタイムゾーンで評価する。
+  pub fn specification(&self) -> OnCalendarSpecification {
+    let granularity = match &self.expr {
+      Expr::CronExpr { secs, .. } if **secs == Expr::ValueExpr(0) => Duration::minutes(1),
+      _ => Duration::seconds(1),
+    };
+    OnCalendarSpecification {
+      underlying: CronSpecification::new(self.expr.clone()),
+      timezone: self.timezone,
+      granularity,
+    }
+  }
+
+  /// Returns a CronInterval with date and time candidates after the start date and time.<br/>
+  /// 開始日時以降の日時候補を持つCronIntervalを返す。
+  pub fn cron_interval<Tz: TimeZone>(&self, start: DateTime<Tz>) -> CronInterval<Tz, OnCalendarSpecification> {
+    CronInterval::new(LimitValue::Limit(start), LimitValue::Limitless, self.specification())
+  }
+
+  /// Returns a CronIntervalIterator with the date and time candidates after the start date and time,
+  /// rounded up to the granularity of the specification.<br/>
+  /// 開始日時以降の日時候補を持つCronIntervalIteratorを返す。開始日時は仕様の粒度に切り上げる。
+  pub fn upcoming<Tz: TimeZone>(&self, start: DateTime<Tz>) -> CronIntervalIterator<Tz, OnCalendarSpecification> {
+    let start = align_up(start, Specification::<DateTime<Tz>>::granularity(&self.specification()));
+    self.cron_interval(start.clone()).iter(start.timezone())
+  }
+
+  /// Returns the first occurrence strictly after `after`, evaluated in the trailing time zone if any. Matching days
+  /// are searched month by month, so an event with no further occurrence such as `*-02-30` returns `None`.<br/>
+  /// `after`より後の最初の実行日時を返す。末尾のタイムゾーンがあればそのタイムゾーンで評価する。マッチする日を
+  /// 月ごとに探すため、`*-02-30`のように以降の実行日時がないイベントでは`None`を返す。
+  pub fn next_after<Tz: TimeZone>(&self, after: DateTime<Tz>) -> Option<DateTime<Tz>> {
+    let compiled = CompiledSchedule::new(&self.expr)?;
+    let next = match self.timezone {
+      Some(timezone) => compiled
+        .next_after(&after.with_timezone(&timezone))?
+        .with_timezone(&after.timezone()),
+      None => compiled.next_after(&after)?,
+    };
+    Some(next)
+  }
+}
+
+/// The specification of an `OnCalendar`, which evaluates the event in its time zone.<br/>
+/// `OnCalendar`の仕様。イベントをそのタイムゾーンで評価する。
+#[derive(Debug, Clone)]
+pub struct OnCalendarSpecification {
+  underlying: CronSpecification,
+  timezone: Option<chrono_tz::Tz>,
+  granularity: Duration,
+}
+
+impl SpecificationExt for OnCalendarSpecification {}
+
+impl<Tz: TimeZone> Specification<DateTime<Tz>> for OnCalendarSpecification {
+  fn is_satisfied_by(&self, datetime: &DateTime<Tz>) -> bool {
+    match self.timezone {
+      Some(timezone) => self.underlying.is_satisfied_by(&datetime.with_timezone(&timezone)),
+      None => self.underlying.is_satisfied_by(datetime),
+    }
+  }
+
+  fn granularity(&self) -> Duration {
+    self.granularity
+  }
+}
+
+fn weekday(name: &str) -> Result<u8, String> {
+  WEEKDAYS
+    .iter()
+    .find(|(short, long, _)| short.eq_ignore_ascii_case(name) || long.eq_ignore_ascii_case(name))
+    .map(|(_, _, n)| *n)
+    .ok_or_else(|| format!("{}: unknown weekday {}", CronField::DayOfWeek.name(), name))
+}
+
+/// Parses weekdays such as `Mon..Fri,Sun`. A range may wrap around the end of the week.<br/>
+/// `Mon..Fri,Sun`のような曜日を解析する。範囲は週の終わりをまたいでもよい。
+fn weekdays(token: &str) -> Result<Expr, String> {
+  list(token, |item| {
+    match item.split_once("..").or_else(|| item.split_once('-')) {
+      Some((from, to)) => Ok(Expr::RangeExpr {
+        from: Box::new(Expr::ValueExpr(weekday(from)?)),
+        to: Box::new(Expr::ValueExpr(weekday(to)?)),
+        per_option: Box::new(Expr::NoOp),
+      }),
+      None => Ok(Expr::ValueExpr(weekday(item)?)),
+    }
+  })
+}
+
+/// Parses `[year-]month-day` or `[year-]month~day` into years, months and days.<br/>
+/// `[year-]month-day`または`[year-]month~day`を年、月、日に解析する。
+fn date(token: &str) -> Result<(Expr, Expr, Expr), String> {
+  let (head, day, from_last) = match token.rsplit_once('~') {
+    Some((head, day)) => (head, day, true),
+    None => match token.rsplit_once('-') {
+      Some((head, day)) => (head, day, false),
+      None => return Err(format!("invalid date {}", token)),
+    },
+  };
+  let (year, month) = match head.split_once('-') {
+    Some((year, month)) => (Some(year), month),
+    None => (None, head),
+  };
+  let years = match year {
+    None | Some("*") => Expr::NoOp,
+    Some(year) => component(CronField::Year, year)?,
+  };
+  let months = component(CronField::Month, month)?;
+  let days = if from_last {
+    list(day, last_day)?
+  } else {
+    component(CronField::Day, day)?
+  };
+  Ok((years, months, days))
+}
+
+/// Parses `hour:minute[:second]` into hours, minutes and seconds, which default to 0.<br/>
+/// `hour:minute[:second]`を時、分、秒に解析する。秒の既定値は0となる。
+fn time(token: &str) -> Result<(Expr, Expr, Expr), String> {
+  let parts = token.split(':').collect::<Vec<_>>();
+  let (hour, minute, second) = match parts.as_slice() {
+    [hour, minute] => (*hour, *minute, "00"),
+    [hour, minute, second] => (*hour, *minute, *second),
+    _ => return Err(format!("invalid time {}", token)),
+  };
+  if second.contains('.') {
+    return Err(format!(
+      "{}: fractional seconds are not supported",
+      CronField::Second.name()
+    ));
+  }
+  Ok((
+    component(CronField::Hour, hour)?,
+    component(CronField::Minute, minute)?,
+    component(CronField::Second, second)?,
+  ))
+}
+
+fn list(token: &str, item: impl Fn(&str) -> Result<Expr, String>) -> Result<Expr, String> {
+  let mut items = token.split(',').map(item).collect::<Result<Vec<_>, _>>()?;
+  Ok(if items.len() == 1 {
+    items.remove(0)
+  } else {
+    Expr::ListExpr(items)
+  })
+}
+
+/// Parses a component such as `*`, `5`, `1..5`, `0/15` or a list of them.<br/>
+/// `*`、`5`、`1..5`、`0/15`やそれらのリストのような要素を解析する。
+fn component(field: CronField, token: &str) -> Result<Expr, String> {
+  list(token, |item| {
+    let (range, step) = match item.split_once('/') {
+      Some((range, step)) => (range, Some(self::step(field, step)?)),
+      None => (item, None),
+    };
+    let range = match range.split_once("..") {
+      Some((from, to)) => {
+        let (from, to) = (value(field, from)?, value(field, to)?);
+        if from > to {
+          return Err(format!("{}: range {} is reversed", field.name(), range));
+        }
+        Expr::RangeExpr {
+          from: Box::new(Expr::ValueExpr(from)),
+          to: Box::new(Expr::ValueExpr(to)),
+          per_option: Box::new(Expr::NoOp),
+        }
+      }
+      None if range == "*" => Expr::AnyValueExpr,
+      None => Expr::ValueExpr(value(field, range)?),
+    };
+    Ok(match (range, step) {
+      (range, None) => range,
+      (Expr::RangeExpr { from, to, .. }, Some(step)) => Expr::RangeExpr {
+        from,
+        to,
+        per_option: Box::new(step),
+      },
+      (digit, Some(step)) => Expr::PerExpr {
+        digit: Box::new(digit),
+        option: Box::new(step),
+      },
+    })
+  })
+}
+
+/// Parses a day counted from the end of the month, where `1` is the last day, with an optional step.<br/>
+/// 月末から数えた日(`1`が最終日)を、省略可能な間隔指定とともに解析する。
+fn last_day(item: &str) -> Result<Expr, String> {
+  let (day, step) = match item.split_once('/') {
+    Some((day, step)) => (day, Some(self::step(CronField::Day, step)?)),
+    None => (item, None),
+  };
+  let offset = value(CronField::Day, day)? - 1;
+  Ok(match step {
+    None if offset == 0 => Expr::LastValueExpr,
+    None => Expr::LastDayOffsetExpr(offset),
+    Some(step) => Expr::PerExpr {
+      digit: Box::new(Expr::LastDayOffsetExpr(offset)),
+      option: Box::new(step),
+    },
+  })
+}
+
+fn number(field: CronField, text: &str) -> Result<u16, String> {
+  if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+    return Err(format!("{}: invalid value {}", field.name(), text));
+  }
+  text
+    .parse::<u16>()
+    .map_err(|_| format!("{}: {} is out of range", field.name(), text))
+}
+
+/// Parses a value of the field. Years are returned as offsets from `YEAR_BASE`,
+/// and two digit years are taken as 1970 to 2069 as systemd does.<br/>
+/// フィールドの値を解析する。年は`YEAR_BASE`からのオフセットとして返し、
+/// 2桁の年はsystemdと同様に1970年から2069年とみなす。
+fn value(field: CronField, text: &str) -> Result<u8, String> {
+  let n = number(field, text)?;
+  let n = match field {
+    CronField::Year if text.len() == 2 && n >= 70 => n + 1900,
+    CronField::Year if text.len() == 2 => n + 2000,
+    _ => n,
+  };
+  let (n, min) = match field {
+    CronField::Year => (n.wrapping_sub(YEAR_BASE), CronField::Year.min() as u16),
+    _ => (n, field.min() as u16),
+  };
+  if n < min || n > field.max() as u16 {
+    return Err(format!("{}: {} is out of range", field.name(), text));
+  }
+  Ok(n as u8)
+}
+
+fn step(field: CronField, text: &str) -> Result<Expr, String> {
+  match number(field, text)? {
+    0 => Err(format!("{}: step of zero is not allowed", field.name())),
+    n if n > field.max() as u16 => Err(format!("{}: step {} is out of range", field.name(), text)),
+    n => Ok(Expr::ValueExpr(n as u8)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::{TimeZone, Utc};
+
+  use super::*;
+
+  fn to_string(source: &str) -> String {
+    OnCalendar::parse(source).unwrap().expr().to_string()
+  }
+
+  #[test]
+  fn test_parse() {
+    assert_eq!(to_string("Mon..Fri *-*-* 09:00:00"), "0 0 9 * * 2-6");
+    assert_eq!(to_string("*-*-01 00:00"), "0 0 0 1 * *");
+    assert_eq!(to_string("weekly"), "0 0 0 * * 2");
+    assert_eq!(to_string("*:0/15"), "0 */15 * * * *");
+    assert_eq!(to_string("Sat,Sun 10:30"), "0 30 10 * * 7,1");
+    assert_eq!(to_string("Fri..Mon 2024-*-1..7 12:00"), "0 0 12 1-7 * 6-2 2024");
+    assert_eq!(to_string("quarterly"), "0 0 0 1 1,4,7,10 *");
+    assert_eq!(to_string("*-02~03"), "0 0 0 L-2 2 *");
+    assert_eq!(to_string("Mon *-05~07/1"), "0 0 0 L-6/1 5 2");
+    assert_eq!(to_string("*-*~01 23:59:59"), "59 59 23 L * *");
+    assert_eq!(to_string("12-10-15 1:2:3"), "3 2 1 15 10 * 2012");
+  }
+
+  #[test]
+  fn test_parse_errors() {
+    for source in [
+      "",
+      "Mon..Fry",
+      "*-13-01",
+      "*-*-32",
+      "24:00",
+      "*:0/0",
+      "*:5..1",
+      "*:*:0.5",
+      "1969-01-01",
+      "daily daily",
+      "*-*-* 00:00 Mars/Base",
+    ] {
+      assert!(OnCalendar::parse(source).is_err(), "{}", source);
+    }
+  }
+
+  #[test]
+  fn test_upcoming() {
+    // 2021-01-01 is a Friday
+    let dt = Utc.with_ymd_and_hms(2021, 1, 1, 9, 0, 1).unwrap();
+    let event = OnCalendar::parse("Mon..Fri *-*-* 09:00:00").unwrap();
+    let dt_vec = event.upcoming(dt).take(2).collect::<Vec<_>>();
+    assert_eq!(dt_vec[0], Utc.with_ymd_and_hms(2021, 1, 4, 9, 0, 0).unwrap());
+    assert_eq!(dt_vec[1], Utc.with_ymd_and_hms(2021, 1, 5, 9, 0, 0).unwrap());
+
+    let dt = Utc.with_ymd_and_hms(2021, 2, 22, 0, 0, 0).unwrap();
+    let event = OnCalendar::parse("*-02~03 06:00").unwrap();
+    assert_eq!(
+      event.upcoming(dt).next().unwrap(),
+      Utc.with_ymd_and_hms(2021, 2, 26, 6, 0, 0).unwrap()
+    );
+  }
+
+  #[test]
+  fn test_next_after() {
+    let dt = Utc.with_ymd_and_hms(2021, 1, 1, 9, 0, 0).unwrap();
+    let event = OnCalendar::parse("yearly").unwrap();
+    assert_eq!(
+      Specification::<DateTime<Utc>>::granularity(&event.specification()),
+      Duration::minutes(1)
+    );
+    assert_eq!(
+      event.next_after(dt),
+      Some(Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap())
+    );
+    assert_eq!(
+      event.upcoming(dt).next(),
+      Some(Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap())
+    );
+    let event = OnCalendar::parse("*-*-* 09:00:30 Asia/Tokyo").unwrap();
+    assert_eq!(
+      Specification::<DateTime<Utc>>::granularity(&event.specification()),
+      Duration::seconds(1)
+    );
+    assert_eq!(
+      event.next_after(dt),
+      Some(Utc.with_ymd_and_hms(2021, 1, 2, 0, 0, 30).unwrap())
+    );
+    assert_eq!(OnCalendar::parse("*-02-30").unwrap().next_after(dt), None);
+    assert_eq!(OnCalendar::parse("2020-*-* 00:00").unwrap().next_after(dt), None);
+  }
+
+  #[test]
+  fn test_timezone() {
+    let event = OnCalendar::parse("*-*-* 09:00 Asia/Tokyo").unwrap();
+    assert_eq!(event.timezone(), Some(chrono_tz::Asia::Tokyo));
+    let dt = Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap();
+    assert!(event.specification().is_satisfied_by(&dt));
+    assert!(!event
+      .specification()
+      .is_satisfied_by(&Utc.with_ymd_and_hms(2021, 1, 1, 9, 0, 0).unwrap()));
+    assert_eq!(OnCalendar::parse("daily UTC").unwrap().timezone(), Some(chrono_tz::UTC));
+  }
+}
@@ -0,0 +1,421 @@
+use crate::{CronField, DayCombination, Expr, YEAR_BASE};
+
+/// Weekday names of systemd in the native numbering, where Sunday is 1.<br/>
+/// ネイティブの番号付け(日曜日が1)によるsystemdの曜日名。
+const WEEKDAY_NAMES: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+/// The result of converting an `Expr` to systemd `OnCalendar=` lines.<br/>
+/// `Expr`をsystemdの`OnCalendar=`の行に変換した結果。
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct OnCalendarExport {
+  /// The calendar events, all of which are needed to cover the expression.<br/>
+  /// 式を網羅するために必要な全てのカレンダーイベント。
+  pub events: Vec<String>,
+  /// The constructs that could not be translated exactly.<br/>
+  /// 正確に変換できなかった構文。
+  pub issues: Vec<String>,
+}
+
+impl OnCalendarExport {
+  /// Returns true if the events match exactly the same instants as the expression.<br/>
+  /// イベントが式と全く同じ日時にマッチする場合にtrueを返す。
+  pub fn is_exact(&self) -> bool {
+    self.issues.is_empty()
+  }
+}
+
+/// The day part of a calendar event, counted from the start of the month or, with `~`, from the end.<br/>
+/// カレンダーイベントの日の部分。月初から、または`~`の場合は月末から数える。
+#[derive(Debug, Clone, PartialEq)]
+struct Days {
+  from_last: bool,
+  text: String,
+}
+
+impl Days {
+  fn any() -> Self {
+    Self {
+      from_last: false,
+      text: "*".to_string(),
+    }
+  }
+}
+
+/// Converts a `CronExpr` to systemd `OnCalendar=` lines.<br/>
+/// `CronExpr`をsystemdの`OnCalendar=`の行に変換する。
+///
+/// A single line cannot express Vixie cron's either-day semantics, `L` mixed with other days,
+/// or several `nL` and `n#k` at once, so the expression is split into as many lines as needed.<br/>
+/// 1行ではVixie cronのいずれかの日にマッチする意味、他の日と混在した`L`、
+/// 複数の`nL`や`n#k`を表現できないため、式は必要なだけの行に分割される。
+///
+/// # Arguments(引数)
+///
+/// * expr - The `CronExpr` to convert(変換する`CronExpr`)
+pub fn to_on_calendar(expr: &Expr) -> OnCalendarExport {
+  let mut export = OnCalendarExport::default();
+  let Expr::CronExpr {
+    secs,
+    mins,
+    hours,
+    days,
+    months,
+    day_of_weeks,
+    years,
+    day_combination,
+  } = expr
+  else {
+    export
+      .issues
+      .push(format!("{} cannot be expressed as a calendar event", expr));
+    return export;
+  };
+  let issues = &mut export.issues;
+  let time = format!(
+    "{}:{}:{}",
+    component(CronField::Hour, hours, issues),
+    component(CronField::Minute, mins, issues),
+    match secs {
+      box Expr::NoOp => "00".to_string(),
+      secs => component(CronField::Second, secs, issues),
+    }
+  );
+  let year = match years {
+    box Expr::NoOp => "*".to_string(),
+    years => component(CronField::Year, years, issues),
+  };
+  let month = component(CronField::Month, months, issues);
+  let day_list = day_parts(days, issues);
+  let (weekdays, weekday_days) = weekday_parts(day_of_weeks, issues);
+
+  let mut lines: Vec<(Option<String>, Days)> = vec![];
+  let days_restricted = day_list != vec![Days::any()];
+  let weekdays_restricted = weekdays.is_some() || !weekday_days.is_empty();
+  if !days_restricted || !weekdays_restricted || *day_combination == DayCombination::Either {
+    if days_restricted || !weekdays_restricted {
+      lines.extend(day_list.iter().map(|days| (None, days.clone())));
+    }
+    if weekdays_restricted {
+      lines.extend(weekdays.map(|weekdays| (Some(weekdays), Days::any())));
+      lines.extend(weekday_days.into_iter().map(|(weekday, days)| (Some(weekday), days)));
+    }
+  } else {
+    for (weekday, _) in &weekday_days {
+      issues.push(format!(
+        "{}: {} cannot be combined with restricted days",
+        CronField::DayOfWeek.name(),
+        weekday
+      ));
+    }
+    if let Some(weekdays) = weekdays {
+      lines.extend(day_list.iter().map(|days| (Some(weekdays.clone()), days.clone())));
+    }
+  }
+
+  export.events = lines
+    .into_iter()
+    .map(|(weekdays, days)| {
+      let date = format!(
+        "{}-{}{}{}",
+        year,
+        month,
+        if days.from_last { '~' } else { '-' },
+        days.text
+      );
+      match weekdays {
+        Some(weekdays) => format!("{} {} {}", weekdays, date, time),
+        None => format!("{} {}", date, time),
+      }
+    })
+    .collect();
+  export
+}
+
+fn value(field: CronField, n: u8) -> String {
+  match field {
+    CronField::Year => (YEAR_BASE + n as u16).to_string(),
+    _ => format!("{:02}", n),
+  }
+}
+
+/// Returns the values a step or a wrap-around range covers, or `None` for other expressions.<br/>
+/// 間隔指定または折り返す範囲が網羅する値を返す。それ以外の式では`None`を返す。
+fn expand(field: CronField, expr: &Expr) -> Option<Vec<u8>> {
+  let (min, max) = (field.min(), field.max());
+  let (values, step): (Vec<u8>, u8) = match expr {
+    Expr::RangeExpr {
+      from: box Expr::ValueExpr(from),
+      to: box Expr::ValueExpr(to),
+      per_option,
+    } => {
+      let step = match per_option {
+        box Expr::ValueExpr(step) => *step,
+        _ => 1,
+      };
+      if from <= to {
+        ((*from..=*to).collect(), step)
+      } else {
+        ((*from..=max).chain(min..=*to).collect(), step)
+      }
+    }
+    Expr::PerExpr {
+      digit: box Expr::ValueExpr(start),
+      option: box Expr::ValueExpr(step),
+    } => ((*start..=max).collect(), *step),
+    Expr::PerExpr {
+      digit: box Expr::AnyValueExpr,
+      option: box Expr::ValueExpr(step),
+    } => ((min..=max).collect(), *step),
+    _ => return None,
+  };
+  Some(values.into_iter().step_by(step as usize).collect())
+}
+
+/// Renders a field as a component such as `*`, `05`, `01..05`, `00/15` or a list of them.<br/>
+/// フィールドを`*`、`05`、`01..05`、`00/15`やそれらのリストのような要素として描画する。
+fn component(field: CronField, expr: &Expr, issues: &mut Vec<String>) -> String {
+  match expr {
+    Expr::NoOp | Expr::AnyValueExpr | Expr::NoSpecificValueExpr => "*".to_string(),
+    Expr::ValueExpr(n) => value(field, *n),
+    Expr::LastValueExpr => value(field, field.max()),
+    Expr::RangeExpr {
+      from: box Expr::ValueExpr(from),
+      to: box Expr::ValueExpr(to),
+      per_option: box Expr::NoOp,
+    } if from <= to => format!("{}..{}", value(field, *from), value(field, *to)),
+    Expr::PerExpr {
+      digit: box Expr::ValueExpr(start),
+      option: box Expr::ValueExpr(step),
+    } => format!("{}/{}", value(field, *start), step),
+    Expr::PerExpr {
+      digit: box Expr::AnyValueExpr,
+      option: box Expr::ValueExpr(step),
+    } => format!("{}/{}", value(field, field.min()), step),
+    Expr::ListExpr(list) => list
+      .iter()
+      .map(|e| component(field, e, issues))
+      .collect::<Vec<_>>()
+      .join(","),
+    e => match expand(field, e) {
+      Some(values) => values
+        .into_iter()
+        .map(|n| value(field, n))
+        .collect::<Vec<_>>()
+        .join(","),
+      None => {
+        issues.push(format!("{}: {} cannot be expressed", field.name(), e));
+        "*".to_string()
+      }
+    },
+  }
+}
+
+fn items(expr: &Expr) -> Vec<&Expr> {
+  match expr {
+    Expr::ListExpr(list) => list.iter().flat_map(items).collect(),
+    e => vec![e],
+  }
+}
+
+/// Splits the days field into the days counted from the start of the month and those counted from the end.<br/>
+/// 日のフィールドを月初から数える日と月末から数える日に分ける。
+fn day_parts(expr: &Expr, issues: &mut Vec<String>) -> Vec<Days> {
+  let field = CronField::Day;
+  let (mut forward, mut from_last) = (vec![], vec![]);
+  for item in items(expr) {
+    match item {
+      Expr::NoOp | Expr::AnyValueExpr | Expr::NoSpecificValueExpr => return vec![Days::any()],
+      Expr::LastValueExpr => from_last.push("01".to_string()),
+      Expr::LastDayOffsetExpr(offset) => from_last.push(value(field, offset + 1)),
+      Expr::PerExpr {
+        digit: box Expr::LastDayOffsetExpr(offset),
+        option: box Expr::ValueExpr(step),
+      } => from_last.push(format!("{}/{}", value(field, offset + 1), step)),
+      e @ (Expr::NearestWeekdayExpr(_) | Expr::LastWeekdayExpr) => {
+        issues.push(format!("{}: {} cannot be expressed", field.name(), e))
+      }
+      e => forward.push(component(field, e, issues)),
+    }
+  }
+  let mut parts = vec![];
+  if !forward.is_empty() {
+    parts.push(Days {
+      from_last: false,
+      text: forward.join(","),
+    });
+  }
+  if !from_last.is_empty() {
+    parts.push(Days {
+      from_last: true,
+      text: from_last.join(","),
+    });
+  }
+  parts
+}
+
+fn weekday(n: u8) -> String {
+  WEEKDAY_NAMES[(n as usize + 6) % 7].to_string()
+}
+
+/// Splits the day of weeks field into plain weekdays and the weekdays that also restrict the days,
+/// such as `nL` and `n#k`.<br/>
+/// 曜日のフィールドを、通常の曜日と`nL`や`n#k`のように日も制限する曜日に分ける。
+fn weekday_parts(expr: &Expr, issues: &mut Vec<String>) -> (Option<String>, Vec<(String, Days)>) {
+  let field = CronField::DayOfWeek;
+  let (mut plain, mut with_days) = (vec![], vec![]);
+  for item in items(expr) {
+    match item {
+      Expr::NoOp | Expr::AnyValueExpr | Expr::NoSpecificValueExpr => return (None, vec![]),
+      Expr::ValueExpr(n) => plain.push(*n),
+      Expr::LastValueExpr => plain.push(field.max()),
+      Expr::LastDayOfWeekExpr(n) => with_days.push((
+        weekday(*n),
+        Days {
+          from_last: true,
+          text: "07/1".to_string(),
+        },
+      )),
+      Expr::NthDayOfWeekExpr { day_of_week, nth } => {
+        let first = (nth - 1) * 7 + 1;
+        with_days.push((
+          weekday(*day_of_week),
+          Days {
+            from_last: false,
+            text: format!("{:02}..{:02}", first, (first + 6).min(CronField::Day.max())),
+          },
+        ))
+      }
+      e => match expand(field, e) {
+        Some(values) => plain.extend(values),
+        None => issues.push(format!("{}: {} cannot be expressed", field.name(), e)),
+      },
+    }
+  }
+  let plain = if plain.is_empty() {
+    None
+  } else {
+    Some(weekday_list(plain))
+  };
+  (plain, with_days)
+}
+
+/// Writes weekdays in the Monday-first order of systemd, joining three or more consecutive days into a range.
+/// A range never wraps around Sunday, so `Sun..Thu` is written as `Mon..Thu,Sun`.<br/>
+/// 曜日をsystemdの月曜始まりの順に書き、3日以上連続する曜日は範囲にまとめる。範囲は日曜日をまたがないため、
+/// `Sun..Thu`は`Mon..Thu,Sun`と書く。
+fn weekday_list(values: Vec<u8>) -> String {
+  // Monday is 0 and Sunday is 6 in the order of systemd.
+  let mut positions = values.into_iter().map(|n| (n + 5) % 7).collect::<Vec<_>>();
+  positions.sort_unstable();
+  positions.dedup();
+  let name = |position: u8| weekday((position + 1) % 7 + 1);
+  let mut runs: Vec<(u8, u8)> = vec![];
+  for position in positions {
+    match runs.last_mut() {
+      Some((_, last)) if *last + 1 == position => *last = position,
+      _ => runs.push((position, position)),
+    }
+  }
+  runs
+    .into_iter()
+    .flat_map(|(first, last)| match last - first {
+      0 => vec![name(first)],
+      1 => vec![name(first), name(last)],
+      _ => vec![format!("{}..{}", name(first), name(last))],
+    })
+    .collect::<Vec<_>>()
+    .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::{Duration, TimeZone, Utc};
+
+  use super::*;
+  use crate::{CronParser, CronSpecification, Dialect, OnCalendar, Specification};
+
+  fn export(dialect: Dialect, source: &str) -> OnCalendarExport {
+    to_on_calendar(&CronParser::parse_with(dialect, source).to_result().unwrap())
+  }
+
+  fn events(source: &str) -> Vec<String> {
+    let export = export(Dialect::Native, source);
+    assert!(export.is_exact(), "{:?}", export.issues);
+    export.events
+  }
+
+  #[test]
+  fn test_to_on_calendar() {
+    assert_eq!(events("0 9 * * 2-6"), vec!["Mon..Fri *-*-* 09:00:00"]);
+    assert_eq!(events("*/15 * * * *"), vec!["*-*-* *:00/15:00"]);
+    assert_eq!(events("0 0 1,15 */3 *"), vec!["*-01/3-01,15 00:00:00"]);
+    assert_eq!(events("30 22-2 * * *"), vec!["*-*-* 22,23,00,01,02:30:00"]);
+    assert_eq!(events("0 0 1,L * *"), vec!["*-*-01 00:00:00", "*-*~01 00:00:00"]);
+    assert_eq!(events("0 0 * * 6L"), vec!["Fri *-*~07/1 00:00:00"]);
+    assert_eq!(events("0 0 * * 2#2"), vec!["Mon *-*-08..14 00:00:00"]);
+    assert_eq!(events("0 0 * * 6#5"), vec!["Fri *-*-29..31 00:00:00"]);
+    assert_eq!(
+      export(Dialect::Quartz, "0 0 12 ? * 2 2030").events,
+      vec!["Mon 2030-*-* 12:00:00"]
+    );
+    assert_eq!(
+      export(Dialect::Vixie, "0 0 1 * 1").events,
+      vec!["*-*-01 00:00:00", "Mon *-*-* 00:00:00"]
+    );
+  }
+
+  #[test]
+  fn test_weekday_order() {
+    let vixie = |source| export(Dialect::Vixie, source).events;
+    assert_eq!(vixie("0 9 * * 0-4"), vec!["Mon..Thu,Sun *-*-* 09:00:00"]);
+    assert_eq!(vixie("0 9 * * 1-7"), vec!["Mon..Sun *-*-* 09:00:00"]);
+    assert_eq!(vixie("0 9 * * 0,6"), vec!["Sat,Sun *-*-* 09:00:00"]);
+    assert_eq!(vixie("0 9 * * 5-7"), vec!["Fri..Sun *-*-* 09:00:00"]);
+    assert_eq!(events("0 9 * * 5-2"), vec!["Mon,Thu..Sun *-*-* 09:00:00"]);
+    assert_eq!(events("0 9 * * 1,3"), vec!["Tue,Sun *-*-* 09:00:00"]);
+  }
+
+  #[test]
+  fn test_issues() {
+    let result = export(Dialect::Native, "0 0 15W * *");
+    assert!(!result.is_exact());
+    assert_eq!(result.issues, vec!["days: 15W cannot be expressed"]);
+    let result = export(Dialect::Native, "0 0 1 * 6L");
+    assert_eq!(
+      result.issues,
+      vec!["day of weeks: Fri cannot be combined with restricted days"]
+    );
+    assert!(!export(Dialect::Aws, "rate(5 minutes)").is_exact());
+  }
+
+  #[test]
+  fn test_round_trip() {
+    for (dialect, source) in [
+      (Dialect::Native, "*/20 9-17 * * 2-6"),
+      (Dialect::Native, "5 4 1,L */2 *"),
+      (Dialect::Native, "0 12 * * 6L"),
+      (Dialect::Native, "0 12 * * 2#5"),
+      (Dialect::Vixie, "30 0 1,15 * 1"),
+      (Dialect::Vixie, "0 9 * * 0-4"),
+    ] {
+      let expr = CronParser::parse_with(dialect, source).to_result().unwrap();
+      let cron = CronSpecification::new(expr.clone());
+      let events = to_on_calendar(&expr)
+        .events
+        .iter()
+        .map(|e| OnCalendar::parse(e).unwrap().specification())
+        .collect::<Vec<_>>();
+      let start = Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap();
+      for minute in 0..60 * 24 * 62 {
+        let dt = start + Duration::minutes(minute);
+        assert_eq!(
+          cron.is_satisfied_by(&dt),
+          events.iter().any(|e| e.is_satisfied_by(&dt)),
+          "{} at {}",
+          source,
+          dt
+        );
+      }
+    }
+  }
+}
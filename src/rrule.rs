@@ -0,0 +1,782 @@
+use std::fmt;
+
+use anyhow::{anyhow, Result};
+use chrono::{
+  DateTime, Datelike, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc, Weekday,
+};
+use intervals_rs::LimitValue;
+
+use crate::ast::FieldDisplay;
+use crate::{CronField, CronInterval, DayCombination, Expr, RateUnit, Specification, SpecificationExt};
+
+const WEEKDAY_CODES: [(&str, Weekday); 7] = [
+  ("MO", Weekday::Mon),
+  ("TU", Weekday::Tue),
+  ("WE", Weekday::Wed),
+  ("TH", Weekday::Thu),
+  ("FR", Weekday::Fri),
+  ("SA", Weekday::Sat),
+  ("SU", Weekday::Sun),
+];
+
+/// The `FREQ` of an RRULE, ordered from the shortest period.<br/>
+/// RRULEの`FREQ`。周期の短い順に並ぶ。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Frequency {
+  Secondly,
+  Minutely,
+  Hourly,
+  Daily,
+  Weekly,
+  Monthly,
+  Yearly,
+}
+
+impl Frequency {
+  fn name(&self) -> &'static str {
+    match self {
+      Frequency::Secondly => "SECONDLY",
+      Frequency::Minutely => "MINUTELY",
+      Frequency::Hourly => "HOURLY",
+      Frequency::Daily => "DAILY",
+      Frequency::Weekly => "WEEKLY",
+      Frequency::Monthly => "MONTHLY",
+      Frequency::Yearly => "YEARLY",
+    }
+  }
+}
+
+/// The `UNTIL` of an RRULE.<br/>
+/// RRULEの`UNTIL`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Until {
+  Date(NaiveDate),
+  Local(NaiveDateTime),
+  Utc(DateTime<Utc>),
+}
+
+/// An iCalendar (RFC 5545) recurrence rule such as `FREQ=MONTHLY;BYDAY=-1FR;BYHOUR=9;BYMINUTE=0`.<br/>
+/// `FREQ=MONTHLY;BYDAY=-1FR;BYHOUR=9;BYMINUTE=0`のようなiCalendar(RFC 5545)の繰り返し規則。
+///
+/// `BYYEARDAY`, `BYWEEKNO` and `BYSETPOS` are not supported.<br/>
+/// `BYYEARDAY`、`BYWEEKNO`、`BYSETPOS`はサポートしない。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RRule {
+  freq: Frequency,
+  interval: u32,
+  count: Option<usize>,
+  until: Option<Until>,
+  by_month: Vec<u8>,
+  by_month_day: Vec<i8>,
+  by_day: Vec<(Option<i8>, Weekday)>,
+  by_hour: Vec<u8>,
+  by_minute: Vec<u8>,
+  by_second: Vec<u8>,
+  week_start: Weekday,
+}
+
+impl RRule {
+  fn new(freq: Frequency) -> Self {
+    Self {
+      freq,
+      interval: 1,
+      count: None,
+      until: None,
+      by_month: vec![],
+      by_month_day: vec![],
+      by_day: vec![],
+      by_hour: vec![],
+      by_minute: vec![],
+      by_second: vec![],
+      week_start: Weekday::Mon,
+    }
+  }
+
+  /// Parses a recurrence rule, with or without the `RRULE:` prefix.<br/>
+  /// `RRULE:`の接頭辞の有無に関わらず繰り返し規則を解析する。
+  ///
+  /// # Arguments(引数)
+  ///
+  /// * source - The recurrence rule(繰り返し規則)
+  ///
+  /// # Return values(戻り値)
+  ///
+  /// * Err:
+  ///   - If the rule is malformed or uses an unsupported part
+  ///   - 規則の形式が不正な場合、またはサポートしない部分を使う場合
+  /// * Ok
+  ///   - If the rule is valid
+  ///   - 規則が正しい場合
+  pub fn parse(source: &str) -> Result<Self> {
+    Self::parse_parts(source.trim()).map_err(|e| anyhow!("Failed to parse RRULE: {}", e))
+  }
+
+  fn parse_parts(source: &str) -> Result<Self, String> {
+    let source = source.strip_prefix("RRULE:").unwrap_or(source);
+    let mut freq = None;
+    let mut rule = Self::new(Frequency::Daily);
+    for part in source.split(';') {
+      let (name, value) = part.split_once('=').ok_or_else(|| format!("invalid part {}", part))?;
+      match name {
+        "FREQ" => {
+          freq = Some(
+            [
+              Frequency::Secondly,
+              Frequency::Minutely,
+              Frequency::Hourly,
+              Frequency::Daily,
+              Frequency::Weekly,
+              Frequency::Monthly,
+              Frequency::Yearly,
+            ]
+            .into_iter()
+            .find(|f| f.name() == value)
+            .ok_or_else(|| format!("unknown FREQ {}", value))?,
+          )
+        }
+        "INTERVAL" => rule.interval = number(name, value, 1, u32::MAX as i64)? as u32,
+        "COUNT" => rule.count = Some(number(name, value, 1, u32::MAX as i64)? as usize),
+        "UNTIL" => rule.until = Some(until(value)?),
+        "BYMONTH" => rule.by_month = list(name, value, 1, 12)?,
+        "BYMONTHDAY" => {
+          rule.by_month_day = list(name, value, -31, 31)?;
+          if rule.by_month_day.contains(&0) {
+            return Err("BYMONTHDAY: 0 is out of range".to_string());
+          }
+        }
+        "BYDAY" => rule.by_day = value.split(',').map(by_day).collect::<Result<_, _>>()?,
+        "BYHOUR" => rule.by_hour = list(name, value, 0, 23)?,
+        "BYMINUTE" => rule.by_minute = list(name, value, 0, 59)?,
+        "BYSECOND" => rule.by_second = list(name, value, 0, 59)?,
+        "WKST" => rule.week_start = weekday(value)?,
+        "BYYEARDAY" | "BYWEEKNO" | "BYSETPOS" => return Err(format!("{} is not supported", name)),
+        _ => return Err(format!("unknown part {}", name)),
+      }
+    }
+    rule.freq = freq.ok_or_else(|| "FREQ is required".to_string())?;
+    if rule.count.is_some() && rule.until.is_some() {
+      return Err("COUNT and UNTIL cannot be used together".to_string());
+    }
+    if rule.by_day.iter().any(|(ordinal, _)| ordinal.is_some()) && rule.freq < Frequency::Monthly {
+      return Err(format!("BYDAY: ordinals cannot be used with FREQ={}", rule.freq.name()));
+    }
+    Ok(rule)
+  }
+
+  /// Returns the specification of this rule, whose first occurrence is `dtstart` when it matches the rule.<br/>
+  /// この規則の仕様を返す。`dtstart`が規則にマッチする場合は最初の発生日時となる。
+  ///
+  /// Parts the rule leaves unspecified, such as the time of a `FREQ=DAILY` rule, are taken from `dtstart`.<br/>
+  /// `FREQ=DAILY`の時刻のように規則が指定しない部分は`dtstart`から取る。
+  pub fn specification<Tz: TimeZone>(&self, dtstart: &DateTime<Tz>) -> RRuleSpecification {
+    RRuleSpecification {
+      rule: self.clone(),
+      dtstart: dtstart.naive_local(),
+    }
+  }
+
+  /// Returns a CronInterval from `dtstart` to `UNTIL`, if any.<br/>
+  /// `dtstart`から`UNTIL`(もしあれば)までのCronIntervalを返す。
+  pub fn cron_interval<Tz: TimeZone>(&self, dtstart: DateTime<Tz>) -> CronInterval<Tz, RRuleSpecification> {
+    let end = self.until_in(&dtstart.timezone());
+    let end = end.map_or(LimitValue::Limitless, LimitValue::Limit);
+    CronInterval::new(LimitValue::Limit(dtstart.clone()), end, self.specification(&dtstart))
+  }
+
+  /// Returns an iterator over the occurrences from `dtstart`, limited by `COUNT` and `UNTIL`. Matching days are
+  /// searched day by day, and the iterator ends when a whole 400-year cycle of the Gregorian calendar has no further
+  /// occurrence, as for `FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30`.<br/>
+  /// `dtstart`からの発生日時のイテレータを返す。`COUNT`と`UNTIL`で制限される。マッチする日を1日ずつ探し、
+  /// `FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30`のようにグレゴリオ暦の400年周期全体に以降の発生日時がない場合に終わる。
+  pub fn upcoming<Tz: TimeZone>(&self, dtstart: DateTime<Tz>) -> impl Iterator<Item = DateTime<Tz>> {
+    let timezone = dtstart.timezone();
+    let end = self.until_in(&timezone);
+    let specification = self.specification(&dtstart);
+    let start = dtstart.naive_local();
+    let first = match specification.matches(start) {
+      true => Some(start),
+      false => specification.next_after(start),
+    };
+    std::iter::successors(first, move |t| specification.next_after(*t))
+      .filter_map(move |t| timezone.from_local_datetime(&t).earliest())
+      .take_while(move |d| end.as_ref().is_none_or(|end| d <= end))
+      .take(self.count.unwrap_or(usize::MAX))
+  }
+
+  fn until_in<Tz: TimeZone>(&self, timezone: &Tz) -> Option<DateTime<Tz>> {
+    match self.until {
+      None => None,
+      Some(Until::Utc(until)) => Some(until.with_timezone(timezone)),
+      Some(Until::Local(until)) => timezone.from_local_datetime(&until).latest(),
+      Some(Until::Date(until)) => timezone
+        .from_local_datetime(&until.and_hms_opt(23, 59, 59).unwrap())
+        .latest(),
+    }
+  }
+}
+
+impl fmt::Display for RRule {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fn join<T: ToString>(values: &[T]) -> String {
+      values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",")
+    }
+    write!(f, "FREQ={}", self.freq.name())?;
+    if self.interval != 1 {
+      write!(f, ";INTERVAL={}", self.interval)?;
+    }
+    if let Some(count) = self.count {
+      write!(f, ";COUNT={}", count)?;
+    }
+    match self.until {
+      None => {}
+      Some(Until::Date(until)) => write!(f, ";UNTIL={}", until.format("%Y%m%d"))?,
+      Some(Until::Local(until)) => write!(f, ";UNTIL={}", until.format("%Y%m%dT%H%M%S"))?,
+      Some(Until::Utc(until)) => write!(f, ";UNTIL={}", until.format("%Y%m%dT%H%M%SZ"))?,
+    }
+    for (name, values) in [
+      ("BYMONTH", join(&self.by_month)),
+      ("BYMONTHDAY", join(&self.by_month_day)),
+    ] {
+      if !values.is_empty() {
+        write!(f, ";{}={}", name, values)?;
+      }
+    }
+    if !self.by_day.is_empty() {
+      let days = self
+        .by_day
+        .iter()
+        .map(|(ordinal, day)| format!("{}{}", ordinal.map(|n| n.to_string()).unwrap_or_default(), code(*day)))
+        .collect::<Vec<_>>();
+      write!(f, ";BYDAY={}", days.join(","))?;
+    }
+    for (name, values) in [
+      ("BYHOUR", &self.by_hour),
+      ("BYMINUTE", &self.by_minute),
+      ("BYSECOND", &self.by_second),
+    ] {
+      if !values.is_empty() {
+        write!(f, ";{}={}", name, join(values))?;
+      }
+    }
+    if self.week_start != Weekday::Mon {
+      write!(f, ";WKST={}", code(self.week_start))?;
+    }
+    Ok(())
+  }
+}
+
+/// The specification of an `RRule` starting at a `dtstart`, evaluated in the local time of the instant.<br/>
+/// `dtstart`から始まる`RRule`の仕様。日時のローカル時刻で評価する。
+#[derive(Debug, Clone)]
+pub struct RRuleSpecification {
+  rule: RRule,
+  dtstart: NaiveDateTime,
+}
+
+impl RRuleSpecification {
+  fn matches(&self, t: NaiveDateTime) -> bool {
+    let (rule, start) = (&self.rule, self.dtstart);
+    let unit = |values: &[u8], freq: Frequency, now: u32, default: u32| {
+      if !values.is_empty() {
+        values.contains(&(now as u8))
+      } else {
+        rule.freq <= freq || now == default
+      }
+    };
+    t >= start
+      && unit(&rule.by_second, Frequency::Secondly, t.second(), start.second())
+      && unit(&rule.by_minute, Frequency::Minutely, t.minute(), start.minute())
+      && unit(&rule.by_hour, Frequency::Hourly, t.hour(), start.hour())
+      && self.matches_date(t.date())
+      && self.period(t) % rule.interval as i64 == 0
+  }
+
+  /// Whether the rule allows the date, ignoring the time of day.<br/>
+  /// 時刻を無視して、規則が日付を許すかどうか。
+  fn matches_date(&self, t: NaiveDate) -> bool {
+    let (rule, start) = (&self.rule, self.dtstart);
+    let day = t.day() as i8;
+    let last_day = last_day(t) as i8;
+    let month = if !rule.by_month.is_empty() {
+      rule.by_month.contains(&(t.month() as u8))
+    } else {
+      rule.freq != Frequency::Yearly
+        || !rule.by_month_day.is_empty()
+        || !rule.by_day.is_empty()
+        || t.month() == start.month()
+    };
+    let month_day = if !rule.by_month_day.is_empty() {
+      rule
+        .by_month_day
+        .iter()
+        .any(|n| if *n > 0 { *n == day } else { last_day + 1 + *n == day })
+    } else {
+      rule.freq < Frequency::Monthly || !rule.by_day.is_empty() || t.day() == start.day()
+    };
+    let week_day = if !rule.by_day.is_empty() {
+      let (nth, nth_from_last) = if rule.freq == Frequency::Yearly && rule.by_month.is_empty() {
+        let days = NaiveDate::from_ymd_opt(t.year(), 12, 31).unwrap().ordinal();
+        ((t.ordinal() - 1) / 7 + 1, (days - t.ordinal()) / 7 + 1)
+      } else {
+        ((day as u32 - 1) / 7 + 1, (last_day - day) as u32 / 7 + 1)
+      };
+      rule.by_day.iter().any(|(ordinal, weekday)| {
+        *weekday == t.weekday()
+          && match ordinal {
+            None => true,
+            Some(n) if *n > 0 => *n as u32 == nth,
+            Some(n) => n.unsigned_abs() as u32 == nth_from_last,
+          }
+      })
+    } else {
+      rule.freq != Frequency::Weekly || t.weekday() == start.weekday()
+    };
+    let period = rule.freq < Frequency::Daily || self.period(t.and_time(start.time())) % rule.interval as i64 == 0;
+    t >= start.date() && month && month_day && week_day && period
+  }
+
+  /// Returns the first occurrence strictly after `after`, searching the days a whole 400-year cycle of the
+  /// Gregorian calendar ahead and testing the times of day of each matching day at the granularity.<br/>
+  /// `after`より後の最初の発生日時を返す。グレゴリオ暦の400年周期先までの日を探し、マッチする日の時刻を粒度ごとに調べる。
+  fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+    let (step, offset) = match Specification::<DateTime<Utc>>::granularity(self).num_seconds() {
+      1 => (1, 0),
+      _ => (60, self.dtstart.second()),
+    };
+    let first = after.date().max(self.dtstart.date());
+    let last = first.checked_add_months(Months::new(400 * 12))?;
+    let mut date = first;
+    while date <= last {
+      if self.matches_date(date) {
+        let next = (offset..24 * 60 * 60)
+          .step_by(step)
+          .map(|s| date.and_time(NaiveTime::from_num_seconds_from_midnight_opt(s, 0).unwrap()))
+          .find(|t| *t > after && self.matches(*t));
+        if next.is_some() {
+          return next;
+        }
+      }
+      date = date.succ_opt()?;
+    }
+    None
+  }
+
+  /// Returns the number of `FREQ` periods between `dtstart` and the instant.<br/>
+  /// `dtstart`から日時までの`FREQ`の周期の数を返す。
+  fn period(&self, t: NaiveDateTime) -> i64 {
+    let start = self.dtstart;
+    let seconds = |t: NaiveDateTime| t.and_utc().timestamp();
+    match self.rule.freq {
+      Frequency::Secondly => seconds(t) - seconds(start),
+      Frequency::Minutely => seconds(t).div_euclid(60) - seconds(start).div_euclid(60),
+      Frequency::Hourly => seconds(t).div_euclid(3600) - seconds(start).div_euclid(3600),
+      Frequency::Daily => (t.date() - start.date()).num_days(),
+      Frequency::Weekly => {
+        let week = |date: NaiveDate| date - Duration::days(date.weekday().days_since(self.rule.week_start) as i64);
+        (week(t.date()) - week(start.date())).num_days() / 7
+      }
+      Frequency::Monthly => {
+        (t.year() as i64 * 12 + t.month0() as i64) - (start.year() as i64 * 12 + start.month0() as i64)
+      }
+      Frequency::Yearly => (t.year() - start.year()) as i64,
+    }
+  }
+}
+
+impl SpecificationExt for RRuleSpecification {}
+
+impl<Tz: TimeZone> Specification<DateTime<Tz>> for RRuleSpecification {
+  fn is_satisfied_by(&self, datetime: &DateTime<Tz>) -> bool {
+    self.matches(datetime.naive_local())
+  }
+
+  fn granularity(&self) -> Duration {
+    if self.rule.freq == Frequency::Secondly || !self.rule.by_second.is_empty() {
+      Duration::seconds(1)
+    } else {
+      Duration::minutes(1)
+    }
+  }
+}
+
+/// The result of converting an `Expr` to an RRULE.<br/>
+/// `Expr`をRRULEに変換した結果。
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RRuleExport {
+  /// The rule, or `None` if the expression cannot be converted at all.<br/>
+  /// 規則。式を全く変換できない場合は`None`。
+  pub rule: Option<RRule>,
+  /// The constructs that have no RRULE equivalent.<br/>
+  /// RRULEに相当するものがない構文。
+  pub issues: Vec<String>,
+}
+
+impl RRuleExport {
+  /// Returns true if the rule matches exactly the same instants as the expression.<br/>
+  /// 規則が式と全く同じ日時にマッチする場合にtrueを返す。
+  pub fn is_exact(&self) -> bool {
+    self.rule.is_some() && self.issues.is_empty()
+  }
+}
+
+/// Converts a `CronExpr` or `RateExpr` to an RRULE.<br/>
+/// `CronExpr`または`RateExpr`をRRULEに変換する。
+///
+/// The coarsest `FREQ` that keeps the rule exact is chosen, and every field coarser than it is listed
+/// with a `BY` part. The seconds are left to `DTSTART` unless the expression has a seconds field.<br/>
+/// 規則が正確になる最も粗い`FREQ`を選び、それより粗いフィールドは全て`BY`で列挙する。
+/// 式が秒のフィールドを持たない限り、秒は`DTSTART`に委ねる。
+///
+/// # Arguments(引数)
+///
+/// * expr - The expression to convert(変換する式)
+pub fn to_rrule(expr: &Expr) -> RRuleExport {
+  let mut export = RRuleExport::default();
+  match expr {
+    Expr::RateExpr { value, unit } => {
+      let mut rule = RRule::new(match unit {
+        RateUnit::Minute => Frequency::Minutely,
+        RateUnit::Hour => Frequency::Hourly,
+        RateUnit::Day => Frequency::Daily,
+      });
+      rule.interval = *value;
+      export.rule = Some(rule);
+    }
+    Expr::CronExpr {
+      secs,
+      mins,
+      hours,
+      days,
+      months,
+      day_of_weeks,
+      years,
+      day_combination,
+    } => {
+      let issues = &mut export.issues;
+      let by_month_day = month_days(days, issues);
+      let by_day = week_days(day_of_weeks, issues);
+      let mut rule = RRule::new(if by_day.iter().any(|(ordinal, _)| ordinal.is_some()) {
+        Frequency::Monthly
+      } else if **secs == Expr::AnyValueExpr {
+        Frequency::Secondly
+      } else if **mins == Expr::AnyValueExpr {
+        Frequency::Minutely
+      } else if **hours == Expr::AnyValueExpr {
+        Frequency::Hourly
+      } else {
+        Frequency::Daily
+      });
+      // A field coarser than the frequency is taken from DTSTART unless it is given, so it is written even as `*`.
+      if **secs != Expr::NoOp && (**secs != Expr::AnyValueExpr || rule.freq > Frequency::Secondly) {
+        rule.by_second = values(CronField::Second, secs, issues);
+      }
+      if **mins != Expr::AnyValueExpr || rule.freq > Frequency::Minutely {
+        rule.by_minute = values(CronField::Minute, mins, issues);
+      }
+      if **hours != Expr::AnyValueExpr || rule.freq > Frequency::Hourly {
+        rule.by_hour = values(CronField::Hour, hours, issues);
+      }
+      if !matches!(**months, Expr::AnyValueExpr) {
+        rule.by_month = values(CronField::Month, months, issues);
+      }
+      if !matches!(**years, Expr::NoOp | Expr::AnyValueExpr) {
+        issues.push(format!(
+          "{}: {} has no RRULE equivalent",
+          CronField::Year.name(),
+          FieldDisplay(CronField::Year, years)
+        ));
+      }
+      if *day_combination == DayCombination::Either && !by_month_day.is_empty() && !by_day.is_empty() {
+        issues.push("days and day of weeks: matching either of them has no RRULE equivalent".to_string());
+      }
+      rule.by_month_day = by_month_day;
+      rule.by_day = by_day;
+      export.rule = Some(rule);
+    }
+    e => export.issues.push(format!("{} has no RRULE equivalent", e)),
+  }
+  export
+}
+
+fn values(field: CronField, expr: &Expr, issues: &mut Vec<String>) -> Vec<u8> {
+  field.values(expr).unwrap_or_else(|| {
+    issues.push(format!(
+      "{}: {} has no RRULE equivalent",
+      field.name(),
+      FieldDisplay(field, expr)
+    ));
+    vec![]
+  })
+}
+
+fn items(expr: &Expr) -> Vec<&Expr> {
+  match expr {
+    Expr::ListExpr(list) => list.iter().flat_map(items).collect(),
+    e => vec![e],
+  }
+}
+
+/// Converts the days field to `BYMONTHDAY`, where days counted from the end of the month are negative.<br/>
+/// 日のフィールドを`BYMONTHDAY`に変換する。月末から数える日は負の値となる。
+fn month_days(expr: &Expr, issues: &mut Vec<String>) -> Vec<i8> {
+  let field = CronField::Day;
+  let mut month_days = vec![];
+  for item in items(expr) {
+    match item {
+      Expr::NoOp | Expr::AnyValueExpr | Expr::NoSpecificValueExpr => return vec![],
+      Expr::LastValueExpr => month_days.push(-1),
+      Expr::LastDayOffsetExpr(offset) => month_days.push(-(*offset as i8) - 1),
+      Expr::PerExpr {
+        digit: box Expr::LastDayOffsetExpr(offset),
+        option: box Expr::ValueExpr(step),
+      } => month_days.extend((0..=*offset).rev().step_by(*step as usize).map(|o| -(o as i8) - 1)),
+      e => month_days.extend(values(field, e, issues).into_iter().map(|n| n as i8)),
+    }
+  }
+  month_days
+}
+
+/// Converts the day of weeks field to `BYDAY`, where `nL` becomes `-1` and `n#k` becomes `k`.<br/>
+/// 曜日のフィールドを`BYDAY`に変換する。`nL`は`-1`、`n#k`は`k`となる。
+fn week_days(expr: &Expr, issues: &mut Vec<String>) -> Vec<(Option<i8>, Weekday)> {
+  let field = CronField::DayOfWeek;
+  let mut week_days = vec![];
+  for item in items(expr) {
+    match item {
+      Expr::NoOp | Expr::AnyValueExpr | Expr::NoSpecificValueExpr => return vec![],
+      Expr::LastDayOfWeekExpr(n) => week_days.push((Some(-1), from_native(*n))),
+      Expr::NthDayOfWeekExpr { day_of_week, nth } => week_days.push((Some(*nth as i8), from_native(*day_of_week))),
+      e => week_days.extend(values(field, e, issues).into_iter().map(|n| (None, from_native(n)))),
+    }
+  }
+  week_days
+}
+
+/// Converts a native day of week, where Sunday is 1, to a `Weekday`.<br/>
+/// 日曜日を1とするネイティブの曜日を`Weekday`に変換する。
+fn from_native(n: u8) -> Weekday {
+  Weekday::try_from((n + 5) % 7).unwrap()
+}
+
+fn code(weekday: Weekday) -> &'static str {
+  WEEKDAY_CODES.iter().find(|(_, w)| *w == weekday).unwrap().0
+}
+
+fn weekday(code: &str) -> Result<Weekday, String> {
+  WEEKDAY_CODES
+    .iter()
+    .find(|(c, _)| *c == code)
+    .map(|(_, w)| *w)
+    .ok_or_else(|| format!("unknown weekday {}", code))
+}
+
+fn by_day(text: &str) -> Result<(Option<i8>, Weekday), String> {
+  // The weekday code is the last two characters, which may not be ASCII in untrusted input.
+  let split = text.char_indices().rev().nth(1).map_or(0, |(i, _)| i);
+  let (ordinal, code) = text.split_at(split);
+  let ordinal = match ordinal {
+    "" => None,
+    ordinal => match ordinal.trim_start_matches('+').parse::<i8>() {
+      Ok(n) if n != 0 && (-53..=53).contains(&n) => Some(n),
+      _ => return Err(format!("BYDAY: invalid ordinal in {}", text)),
+    },
+  };
+  Ok((ordinal, weekday(code)?))
+}
+
+fn number(name: &str, text: &str, min: i64, max: i64) -> Result<i64, String> {
+  match text.parse::<i64>() {
+    Ok(n) if (min..=max).contains(&n) => Ok(n),
+    Ok(_) => Err(format!("{}: {} is out of range", name, text)),
+    Err(_) => Err(format!("{}: invalid value {}", name, text)),
+  }
+}
+
+fn list<T: TryFrom<i64>>(name: &str, text: &str, min: i64, max: i64) -> Result<Vec<T>, String> {
+  text
+    .split(',')
+    .map(|v| number(name, v, min, max).map(|n| T::try_from(n).ok().unwrap()))
+    .collect()
+}
+
+fn until(text: &str) -> Result<Until, String> {
+  if let Some(utc) = text.strip_suffix('Z') {
+    NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").map(|t| Until::Utc(t.and_utc()))
+  } else if text.contains('T') {
+    NaiveDateTime::parse_from_str(text, "%Y%m%dT%H%M%S").map(Until::Local)
+  } else {
+    NaiveDate::parse_from_str(text, "%Y%m%d").map(Until::Date)
+  }
+  .map_err(|_| format!("UNTIL: invalid value {}", text))
+}
+
+fn last_day(date: NaiveDate) -> u32 {
+  let (year, month) = if date.month() == 12 {
+    (date.year() + 1, 1)
+  } else {
+    (date.year(), date.month() + 1)
+  };
+  NaiveDate::from_ymd_opt(year, month, 1)
+    .unwrap()
+    .pred_opt()
+    .unwrap()
+    .day()
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::{TimeZone, Utc};
+
+  use super::*;
+  use crate::{CronParser, CronSchedule, Dialect};
+
+  fn export(dialect: Dialect, source: &str) -> RRuleExport {
+    to_rrule(&CronParser::parse_with(dialect, source).to_result().unwrap())
+  }
+
+  fn rule(source: &str) -> String {
+    let export = export(Dialect::Native, source);
+    assert!(export.is_exact(), "{:?}", export.issues);
+    export.rule.unwrap().to_string()
+  }
+
+  #[test]
+  fn test_to_rrule() {
+    assert_eq!(
+      rule("0 9 * * 2-6"),
+      "FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR;BYHOUR=9;BYMINUTE=0"
+    );
+    assert_eq!(rule("*/15 * * * *"), "FREQ=HOURLY;BYMINUTE=0,15,30,45");
+    assert_eq!(rule("* * * * *"), "FREQ=MINUTELY");
+    assert_eq!(rule("* 9 * * *"), "FREQ=MINUTELY;BYHOUR=9");
+    assert_eq!(rule("0 0 L * *"), "FREQ=DAILY;BYMONTHDAY=-1;BYHOUR=0;BYMINUTE=0");
+    assert_eq!(
+      rule("30 9 * 1,7 6L"),
+      "FREQ=MONTHLY;BYMONTH=1,7;BYDAY=-1FR;BYHOUR=9;BYMINUTE=30"
+    );
+    assert_eq!(rule("0 9 * * 3#2"), "FREQ=MONTHLY;BYDAY=2TU;BYHOUR=9;BYMINUTE=0");
+    assert_eq!(
+      export(Dialect::Quartz, "*/30 0 12 * * ?").rule.unwrap().to_string(),
+      "FREQ=DAILY;BYHOUR=12;BYMINUTE=0;BYSECOND=0,30"
+    );
+    assert_eq!(
+      export(Dialect::Quartz, "* 0 12 * * ?").rule.unwrap().to_string(),
+      "FREQ=SECONDLY;BYHOUR=12;BYMINUTE=0"
+    );
+    assert_eq!(
+      export(Dialect::Aws, "rate(90 minutes)").rule.unwrap().to_string(),
+      "FREQ=MINUTELY;INTERVAL=90"
+    );
+  }
+
+  #[test]
+  fn test_issues() {
+    assert_eq!(
+      export(Dialect::Native, "0 0 15W * *").issues,
+      vec!["days: 15W has no RRULE equivalent"]
+    );
+    assert_eq!(
+      export(Dialect::Vixie, "0 0 1 * 1").issues,
+      vec!["days and day of weeks: matching either of them has no RRULE equivalent"]
+    );
+    assert_eq!(
+      export(Dialect::Quartz, "0 0 12 ? * 2 2030").issues,
+      vec!["years: 2030 has no RRULE equivalent"]
+    );
+  }
+
+  #[test]
+  fn test_parse() {
+    for source in [
+      "FREQ=WEEKLY;INTERVAL=2;COUNT=4;BYDAY=TU,TH",
+      "FREQ=MONTHLY;UNTIL=20211231T235959Z;BYMONTHDAY=1,-1",
+      "FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU;BYHOUR=1;WKST=SU",
+    ] {
+      assert_eq!(RRule::parse(source).unwrap().to_string(), source);
+    }
+    assert_eq!(
+      RRule::parse("RRULE:FREQ=DAILY;INTERVAL=1;BYDAY=+1MO")
+        .unwrap_err()
+        .to_string(),
+      "Failed to parse RRULE: BYDAY: ordinals cannot be used with FREQ=DAILY"
+    );
+    for source in [
+      "BYHOUR=1",
+      "FREQ=HOURLY;BYHOUR=24",
+      "FREQ=DAILY;BYMONTHDAY=0",
+      "FREQ=DAILY;COUNT=1;UNTIL=20210101",
+      "FREQ=YEARLY;BYSETPOS=-1",
+      "FREQ=DAILY;BYDAY=XX",
+      "FREQ=DAILY;BYDAY=éa",
+      "FREQ=MONTHLY;BYDAY=1é",
+    ] {
+      assert!(RRule::parse(source).is_err(), "{}", source);
+    }
+  }
+
+  #[test]
+  fn test_upcoming() {
+    // 2021-01-05 is a Tuesday
+    let dtstart = Utc.with_ymd_and_hms(2021, 1, 5, 9, 0, 0).unwrap();
+    let rule = RRule::parse("FREQ=WEEKLY;INTERVAL=2;COUNT=4;BYDAY=TU,TH").unwrap();
+    let dt_vec = rule.upcoming(dtstart).collect::<Vec<_>>();
+    assert_eq!(
+      dt_vec,
+      vec![
+        Utc.with_ymd_and_hms(2021, 1, 5, 9, 0, 0).unwrap(),
+        Utc.with_ymd_and_hms(2021, 1, 7, 9, 0, 0).unwrap(),
+        Utc.with_ymd_and_hms(2021, 1, 19, 9, 0, 0).unwrap(),
+        Utc.with_ymd_and_hms(2021, 1, 21, 9, 0, 0).unwrap(),
+      ]
+    );
+
+    let rule = RRule::parse("FREQ=MONTHLY;UNTIL=20210331T000000Z;BYDAY=-1FR").unwrap();
+    let dt_vec = rule.upcoming(dtstart).collect::<Vec<_>>();
+    assert_eq!(
+      dt_vec,
+      vec![
+        Utc.with_ymd_and_hms(2021, 1, 29, 9, 0, 0).unwrap(),
+        Utc.with_ymd_and_hms(2021, 2, 26, 9, 0, 0).unwrap(),
+        Utc.with_ymd_and_hms(2021, 3, 26, 9, 0, 0).unwrap(),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_upcoming_without_occurrence() {
+    let dtstart = Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap();
+    let rule = RRule::parse("FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30").unwrap();
+    assert_eq!(rule.upcoming(dtstart).next(), None);
+    let rule = RRule::parse("FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=29").unwrap();
+    assert_eq!(
+      rule.upcoming(dtstart).next(),
+      Some(Utc.with_ymd_and_hms(2024, 2, 29, 0, 0, 0).unwrap())
+    );
+  }
+
+  #[test]
+  fn test_consistent_with_cron_schedule() {
+    let start = Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap();
+    for (dialect, source) in [
+      (Dialect::Native, "*/20 9-17 * * 2-6"),
+      (Dialect::Native, "5 4 1,L */2 *"),
+      (Dialect::Native, "0 12 * * 6L"),
+      (Dialect::Native, "0 12 * * 2#5"),
+      (Dialect::Native, "15 * * * *"),
+      (Dialect::Native, "* 9 * * *"),
+      (Dialect::Quartz, "0 * 9 * * ?"),
+      (Dialect::Quartz, "* 0 12 * * ?"),
+    ] {
+      let schedule = CronSchedule::<Utc>::with_dialect(dialect, source).unwrap();
+      let export = to_rrule(schedule.expr());
+      assert!(export.is_exact(), "{}: {:?}", source, export.issues);
+      let rule = export.rule.unwrap();
+      assert_eq!(
+        schedule.upcoming(start).take(20).collect::<Vec<_>>(),
+        rule.upcoming(start).take(20).collect::<Vec<_>>(),
+        "{}",
+        source
+      );
+    }
+  }
+}
@@ -0,0 +1,245 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+
+use chrono::{DateTime, Duration, TimeZone};
+
+use crate::cron_interval::align_up;
+use crate::{CronSchedule, Specification};
+
+/// A set of keyed schedules whose upcoming occurrences are merged into one chronological stream.
+/// Each item is an instant with the keys of all schedules that fire then, in the order they were added.<br/>
+/// キー付きのスケジュールの集合。今後の実行日時を1つの時系列に併合する。
+/// 各要素は日時と、その日時に実行される全てのスケジュールのキー(追加した順)である。
+pub struct ScheduleSet<K, Tz: TimeZone> {
+  position: DateTime<Tz>,
+  entries: HashMap<K, Entry<Tz>>,
+  heap: BinaryHeap<Reverse<Pending<K, Tz>>>,
+  next_generation: u64,
+}
+
+struct Entry<Tz: TimeZone> {
+  generation: u64,
+  schedule: CronSchedule<Tz>,
+  anchor: DateTime<Tz>,
+}
+
+/// The next occurrence of a schedule, ordered by time and then by when the schedule was added.
+struct Pending<K, Tz: TimeZone> {
+  at: DateTime<Tz>,
+  generation: u64,
+  key: K,
+}
+
+impl<K, Tz: TimeZone> PartialEq for Pending<K, Tz> {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl<K, Tz: TimeZone> Eq for Pending<K, Tz> {}
+
+impl<K, Tz: TimeZone> PartialOrd for Pending<K, Tz> {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl<K, Tz: TimeZone> Ord for Pending<K, Tz> {
+  fn cmp(&self, other: &Self) -> Ordering {
+    (&self.at, self.generation).cmp(&(&other.at, other.generation))
+  }
+}
+
+impl<K: Clone + Eq + Hash, Tz: TimeZone> ScheduleSet<K, Tz> {
+  /// The factory method, with occurrences starting at the start date and time.<br/>
+  /// 開始日時以降の実行日時を返すファクトリメソッド。
+  pub fn new(start: DateTime<Tz>) -> Self {
+    Self {
+      position: start,
+      entries: HashMap::new(),
+      heap: BinaryHeap::new(),
+      next_generation: 0,
+    }
+  }
+
+  /// Adds a schedule, replacing the one with the same key. Its occurrences start after the last yielded instant,
+  /// and a schedule with no further occurrence never appears in the stream.<br/>
+  /// スケジュールを追加する。同じキーのスケジュールは置き換える。実行日時は最後に返した日時の後から始まり、
+  /// 以降の実行日時がないスケジュールはストリームに現れない。
+  pub fn add(&mut self, key: K, schedule: CronSchedule<Tz>) -> Option<CronSchedule<Tz>> {
+    let granularity = Specification::<DateTime<Tz>>::granularity(&schedule.specification());
+    let anchor = align_up(self.position.clone(), granularity);
+    let first = match schedule.specification().anchored_at(&anchor).is_satisfied_by(&anchor) {
+      true => Some(anchor.clone()),
+      false => schedule.next_after_anchored(&anchor, &anchor),
+    };
+    let generation = self.next_generation;
+    self.next_generation += 1;
+    if let Some(at) = first {
+      self.heap.push(Reverse(Pending {
+        at,
+        generation,
+        key: key.clone(),
+      }));
+    }
+    let entry = Entry {
+      generation,
+      schedule,
+      anchor,
+    };
+    self.entries.insert(key, entry).map(|e| e.schedule)
+  }
+
+  /// Removes a schedule, so that it no longer appears in the stream.<br/>
+  /// スケジュールを削除する。以降はストリームに現れない。
+  pub fn remove(&mut self, key: &K) -> Option<CronSchedule<Tz>> {
+    self.entries.remove(key).map(|e| e.schedule)
+  }
+
+  /// Returns the schedule with the key.<br/>
+  /// キーに対応するスケジュールを返す。
+  pub fn get(&self, key: &K) -> Option<&CronSchedule<Tz>> {
+    self.entries.get(key).map(|e| &e.schedule)
+  }
+
+  /// Returns the number of schedules.<br/>
+  /// スケジュールの数を返す。
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  /// Returns whether there are no schedules.<br/>
+  /// スケジュールがないかどうかを返す。
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  /// Drops the pending occurrences of schedules that were removed or replaced from the top of the heap.
+  fn discard_stale(&mut self) {
+    while let Some(Reverse(pending)) = self.heap.peek() {
+      match self.entries.get(&pending.key) {
+        Some(entry) if entry.generation == pending.generation => return,
+        _ => {
+          self.heap.pop();
+        }
+      }
+    }
+  }
+
+  /// Pops the earliest pending occurrence of a schedule that is still in the set, and queues its next one.
+  fn pop(&mut self) -> Option<Pending<K, Tz>> {
+    self.discard_stale();
+    let Reverse(pending) = self.heap.pop()?;
+    let entry = self.entries.get_mut(&pending.key)?;
+    if let Some(at) = entry.schedule.next_after_anchored(&entry.anchor, &pending.at) {
+      self.heap.push(Reverse(Pending {
+        at,
+        generation: pending.generation,
+        key: pending.key.clone(),
+      }));
+    }
+    Some(pending)
+  }
+}
+
+impl<K: Clone + Eq + Hash, Tz: TimeZone> Iterator for ScheduleSet<K, Tz> {
+  type Item = (DateTime<Tz>, Vec<K>);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let first = self.pop()?;
+    let mut keys = vec![first.key];
+    loop {
+      self.discard_stale();
+      match self.heap.peek() {
+        Some(Reverse(pending)) if pending.at == first.at => keys.extend(self.pop().map(|p| p.key)),
+        _ => break,
+      }
+    }
+    self.position = first.at.clone() + Duration::seconds(1);
+    Some((first.at, keys))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::Dialect;
+  use chrono::Utc;
+
+  fn schedule(s: &str) -> CronSchedule<Utc> {
+    CronSchedule::new(s).unwrap()
+  }
+
+  fn at(h: u32, m: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2021, 1, 1, h, m, 0).unwrap()
+  }
+
+  #[test]
+  fn test_merge() {
+    let mut set = ScheduleSet::new(at(0, 0));
+    set.add("quarter", schedule("*/15 * * * *"));
+    set.add("twenty", schedule("*/20 * * * *"));
+    set.add("hourly", schedule("0 * * * *"));
+    let items = set.by_ref().take(5).collect::<Vec<_>>();
+    assert_eq!(
+      items,
+      vec![
+        (at(0, 0), vec!["quarter", "twenty", "hourly"]),
+        (at(0, 15), vec!["quarter"]),
+        (at(0, 20), vec!["twenty"]),
+        (at(0, 30), vec!["quarter"]),
+        (at(0, 40), vec!["twenty"]),
+      ]
+    );
+    assert_eq!(set.len(), 3);
+  }
+
+  #[test]
+  fn test_add_and_remove_while_iterating() {
+    let mut set = ScheduleSet::new(at(0, 0));
+    set.add(1, schedule("*/10 * * * *"));
+    set.add(2, schedule("*/15 * * * *"));
+    assert_eq!(set.next(), Some((at(0, 0), vec![1, 2])));
+    assert_eq!(set.next(), Some((at(0, 10), vec![1])));
+
+    assert!(set.remove(&1).is_some());
+    set.add(3, schedule("*/5 * * * *"));
+    assert_eq!(set.next(), Some((at(0, 15), vec![2, 3])));
+    assert_eq!(set.next(), Some((at(0, 20), vec![3])));
+
+    // Re-adding a key replaces the schedule instead of duplicating it
+    assert!(set.add(3, schedule("0 * * * *")).is_some());
+    assert_eq!(set.next(), Some((at(0, 30), vec![2])));
+    assert_eq!(set.next(), Some((at(0, 45), vec![2])));
+    assert_eq!(set.next(), Some((at(1, 0), vec![2, 3])));
+
+    set.remove(&2);
+    set.remove(&3);
+    assert!(set.is_empty());
+    assert_eq!(set.next(), None);
+  }
+
+  #[test]
+  fn test_schedule_without_next_run() {
+    let mut set = ScheduleSet::new(at(0, 0));
+    set.add("never", schedule("0 0 30 2 *"));
+    assert_eq!(set.next(), None);
+
+    set.add("hourly", schedule("0 * * * *"));
+    set.add(
+      "once",
+      CronSchedule::with_dialect(Dialect::Quartz, "0 30 0 1 1 ? 2021").unwrap(),
+    );
+    let items = set.by_ref().take(3).collect::<Vec<_>>();
+    assert_eq!(
+      items,
+      vec![
+        (at(0, 0), vec!["hourly"]),
+        (at(0, 30), vec!["once"]),
+        (at(1, 0), vec!["hourly"]),
+      ]
+    );
+    assert_eq!(set.len(), 3);
+  }
+}
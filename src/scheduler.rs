@@ -0,0 +1,455 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+use chrono::{DateTime, TimeZone};
+
+use crate::{Clock, CronSchedule, SystemClock};
+
+/// The identifier of a job registered with a `Scheduler`.<br/>
+/// `Scheduler`に登録したジョブの識別子。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JobId(u64);
+
+impl Display for JobId {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "job-{}", self.0)
+  }
+}
+
+type Task = Arc<dyn Fn() + Send + Sync>;
+
+struct Job<Tz: TimeZone> {
+  schedule: CronSchedule<Tz>,
+  anchor: DateTime<Tz>,
+  next: Option<DateTime<Tz>>,
+  paused: bool,
+  running: Arc<AtomicBool>,
+  task: Task,
+}
+
+struct Inner<Tz: TimeZone, C> {
+  clock: C,
+  jobs: Mutex<HashMap<JobId, Job<Tz>>>,
+  next_id: AtomicU64,
+}
+
+/// An in-process scheduler that runs closures at the occurrences of their schedules.
+/// Each run happens on its own thread, and a job whose previous run has not finished is skipped rather than run
+/// twice at once. Runs missed while the scheduler was not ticking are not made up for; see `MisfirePolicy` for that.<br/>
+/// スケジュールの実行日時にクロージャを実行するプロセス内スケジューラ。
+/// 各実行は専用のスレッドで行い、前回の実行が終わっていないジョブは同時に2回実行せずに飛ばす。
+/// スケジューラが動いていない間に逃した実行は補わない。補う場合は`MisfirePolicy`を参照。
+pub struct Scheduler<Tz: TimeZone, C = SystemClock<Tz>> {
+  inner: Arc<Inner<Tz, C>>,
+}
+
+impl<Tz: TimeZone, C> Clone for Scheduler<Tz, C> {
+  fn clone(&self) -> Self {
+    Self {
+      inner: self.inner.clone(),
+    }
+  }
+}
+
+impl<Tz> Scheduler<Tz>
+where
+  Tz: TimeZone + Send + Sync + 'static,
+  Tz::Offset: Send + Sync,
+{
+  /// The factory method, reading the time from the system clock in the timezone.<br/>
+  /// タイムゾーンにおけるシステム時計から現在日時を読むファクトリメソッド。
+  pub fn new(timezone: Tz) -> Self {
+    Self::with_clock(SystemClock::new(timezone))
+  }
+}
+
+impl<Tz, C> Scheduler<Tz, C>
+where
+  Tz: TimeZone + Send + Sync + 'static,
+  Tz::Offset: Send + Sync,
+  C: Clock<Tz> + Send + Sync + 'static,
+{
+  /// The factory method, reading the time from the given clock, such as a `ManualClock` in tests.
+  /// The schedules are evaluated in the timezone of the clock.<br/>
+  /// テストでの`ManualClock`など、与えた時計から現在日時を読むファクトリメソッド。
+  /// スケジュールは時計のタイムゾーンで評価する。
+  pub fn with_clock(clock: C) -> Self {
+    Self {
+      inner: Arc::new(Inner {
+        clock,
+        jobs: Mutex::new(HashMap::new()),
+        next_id: AtomicU64::new(0),
+      }),
+    }
+  }
+
+  /// Returns the current date and time of the clock.<br/>
+  /// 時計の現在日時を返す。
+  pub fn now(&self) -> DateTime<Tz> {
+    self.inner.clock.now()
+  }
+
+  fn jobs(&self) -> MutexGuard<'_, HashMap<JobId, Job<Tz>>> {
+    self.inner.jobs.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  /// Registers a closure to run at the occurrences of a schedule after now.
+  /// A `rate(...)` schedule counts from now.<br/>
+  /// 現在日時より後のスケジュールの実行日時に実行するクロージャを登録する。
+  /// `rate(...)`のスケジュールは現在日時から数える。
+  pub fn add<F>(&self, schedule: CronSchedule<Tz>, task: F) -> JobId
+  where
+    F: Fn() + Send + Sync + 'static, {
+    let id = JobId(self.inner.next_id.fetch_add(1, Ordering::Relaxed));
+    let now = self.now();
+    let job = Job {
+      next: schedule.next_after(now.clone()),
+      schedule,
+      anchor: now,
+      paused: false,
+      running: Arc::new(AtomicBool::new(false)),
+      task: Arc::new(task),
+    };
+    self.jobs().insert(id, job);
+    id
+  }
+
+  /// Removes a job. A run in progress is not interrupted.<br/>
+  /// ジョブを削除する。実行中の処理は中断しない。
+  pub fn remove(&self, id: JobId) -> bool {
+    self.jobs().remove(&id).is_some()
+  }
+
+  /// Pauses a job, so that it does not run until resumed.<br/>
+  /// ジョブを一時停止する。再開するまで実行しない。
+  pub fn pause(&self, id: JobId) -> bool {
+    match self.jobs().get_mut(&id) {
+      Some(job) => {
+        job.paused = true;
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Resumes a paused job from its next occurrence after now, skipping those that passed while it was paused.<br/>
+  /// 一時停止したジョブを現在日時より後の実行日時から再開する。停止中に過ぎた実行日時は飛ばす。
+  pub fn resume(&self, id: JobId) -> bool {
+    let now = self.now();
+    match self.jobs().get_mut(&id) {
+      Some(job) => {
+        if job.paused {
+          job.paused = false;
+          job.next = job.schedule.next_after_anchored(&job.anchor, &now);
+        }
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Returns whether a job is paused, or `None` if there is no such job.<br/>
+  /// ジョブが一時停止しているかどうかを返す。ジョブがない場合は`None`を返す。
+  pub fn is_paused(&self, id: JobId) -> Option<bool> {
+    self.jobs().get(&id).map(|job| job.paused)
+  }
+
+  /// Returns whether a run of a job is in progress, or `None` if there is no such job.<br/>
+  /// ジョブが実行中かどうかを返す。ジョブがない場合は`None`を返す。
+  pub fn is_running(&self, id: JobId) -> Option<bool> {
+    self.jobs().get(&id).map(|job| job.running.load(Ordering::Acquire))
+  }
+
+  /// Returns the next run of a job, or `None` if it is paused, has no more occurrences or does not exist.<br/>
+  /// ジョブの次の実行日時を返す。一時停止中、以降の実行日時がない、またはジョブがない場合は`None`を返す。
+  pub fn next_run(&self, id: JobId) -> Option<DateTime<Tz>> {
+    self
+      .jobs()
+      .get(&id)
+      .filter(|job| !job.paused)
+      .and_then(|job| job.next.clone())
+  }
+
+  /// Returns the number of jobs.<br/>
+  /// ジョブの数を返す。
+  pub fn len(&self) -> usize {
+    self.jobs().len()
+  }
+
+  /// Returns whether there are no jobs.<br/>
+  /// ジョブがないかどうかを返す。
+  pub fn is_empty(&self) -> bool {
+    self.jobs().is_empty()
+  }
+
+  /// Starts the runs that are due now and schedules the next ones. A job that is due while its previous run is
+  /// still in progress is skipped until its next occurrence.<br/>
+  /// 現在日時に実行すべき処理を開始し、次の実行日時を決める。前回の実行中に実行日時を迎えたジョブは、
+  /// 次の実行日時まで飛ばす。
+  ///
+  /// # Return values(戻り値)
+  ///
+  /// * The number of runs started(開始した実行の数)
+  pub fn tick(&self) -> usize {
+    let now = self.now();
+    let mut started = 0;
+    for job in self.jobs().values_mut() {
+      if job.paused || job.next.as_ref().is_none_or(|next| *next > now) {
+        continue;
+      }
+      job.next = job.schedule.next_after_anchored(&job.anchor, &now);
+      if job.running.swap(true, Ordering::AcqRel) {
+        continue;
+      }
+      let running = Running(job.running.clone());
+      let task = job.task.clone();
+      thread::spawn(move || {
+        let _running = running;
+        task();
+      });
+      started += 1;
+    }
+    started
+  }
+
+  /// Ticks on a background thread at the poll interval until the returned handle is stopped or dropped.<br/>
+  /// 返したハンドルを停止または破棄するまで、バックグラウンドスレッドでポーリング間隔ごとにtickする。
+  pub fn start(&self, poll: std::time::Duration) -> SchedulerHandle {
+    let (stop, stopped) = mpsc::channel();
+    let scheduler = self.clone();
+    let thread = thread::spawn(move || loop {
+      scheduler.tick();
+      match stopped.recv_timeout(poll) {
+        Err(RecvTimeoutError::Timeout) => continue,
+        _ => break,
+      }
+    });
+    SchedulerHandle {
+      stop: Some(stop),
+      thread: Some(thread),
+    }
+  }
+
+  /// Ticks on a tokio task at the poll interval until the task is aborted. Runs still happen on their own threads.<br/>
+  /// タスクを中止するまで、tokioのタスクでポーリング間隔ごとにtickする。各実行は引き続き専用のスレッドで行う。
+  #[cfg(feature = "tokio")]
+  pub fn spawn(&self, poll: std::time::Duration) -> tokio::task::JoinHandle<()> {
+    let scheduler = self.clone();
+    tokio::spawn(async move {
+      loop {
+        scheduler.tick();
+        tokio::time::sleep(poll).await;
+      }
+    })
+  }
+}
+
+/// Marks a job as no longer running when its run finishes, even if the closure panics.
+struct Running(Arc<AtomicBool>);
+
+impl Drop for Running {
+  fn drop(&mut self) {
+    self.0.store(false, Ordering::Release);
+  }
+}
+
+/// The handle of the background thread started by `Scheduler::start`, which stops it when dropped.<br/>
+/// `Scheduler::start`が開始したバックグラウンドスレッドのハンドル。破棄するとスレッドを停止する。
+pub struct SchedulerHandle {
+  stop: Option<Sender<()>>,
+  thread: Option<JoinHandle<()>>,
+}
+
+impl SchedulerHandle {
+  /// Stops ticking and waits for the background thread to finish. Runs in progress are not waited for.<br/>
+  /// tickを停止し、バックグラウンドスレッドの終了を待つ。実行中の処理の終了は待たない。
+  pub fn stop(mut self) {
+    self.shutdown();
+  }
+
+  fn shutdown(&mut self) {
+    self.stop.take();
+    if let Some(thread) = self.thread.take() {
+      let _ = thread.join();
+    }
+  }
+}
+
+impl Drop for SchedulerHandle {
+  fn drop(&mut self) {
+    self.shutdown();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{Dialect, ManualClock};
+  use chrono::Utc;
+  use std::sync::mpsc::Receiver;
+
+  type ManualScheduler = Scheduler<Utc, ManualClock<Utc>>;
+
+  fn manual(start: DateTime<Utc>) -> (ManualClock<Utc>, ManualScheduler) {
+    let clock = ManualClock::new(start);
+    (clock.clone(), Scheduler::with_clock(clock))
+  }
+
+  fn at(h: u32, m: u32, s: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2021, 1, 1, h, m, s).unwrap()
+  }
+
+  fn counter(scheduler: &ManualScheduler, s: &str) -> (JobId, Receiver<()>) {
+    let (sender, receiver) = mpsc::channel();
+    let sender = Mutex::new(sender);
+    let id = scheduler.add(CronSchedule::new(s).unwrap(), move || {
+      sender.lock().unwrap().send(()).unwrap();
+    });
+    (id, receiver)
+  }
+
+  fn received(receiver: &Receiver<()>, n: usize) {
+    for _ in 0..n {
+      receiver.recv_timeout(std::time::Duration::from_secs(5)).unwrap();
+    }
+    assert!(receiver.try_recv().is_err());
+  }
+
+  fn wait_idle(scheduler: &ManualScheduler, id: JobId) {
+    while scheduler.is_running(id) == Some(true) {
+      thread::yield_now();
+    }
+  }
+
+  #[test]
+  fn test_tick() {
+    let (clock, scheduler) = manual(at(0, 0, 0));
+    let (quarter, quarters) = counter(&scheduler, "*/15 * * * *");
+    let (hourly, hours) = counter(&scheduler, "0 * * * *");
+    assert_eq!(scheduler.next_run(quarter), Some(at(0, 15, 0)));
+    assert_eq!(scheduler.next_run(hourly), Some(at(1, 0, 0)));
+    assert_eq!(scheduler.tick(), 0);
+
+    clock.set(at(0, 15, 0));
+    assert_eq!(scheduler.tick(), 1);
+    assert_eq!(scheduler.tick(), 0);
+    received(&quarters, 1);
+    wait_idle(&scheduler, quarter);
+    assert_eq!(scheduler.next_run(quarter), Some(at(0, 30, 0)));
+
+    // A jump over several occurrences runs once and resumes after now
+    clock.set(at(1, 20, 0));
+    assert_eq!(scheduler.tick(), 2);
+    received(&quarters, 1);
+    received(&hours, 1);
+    assert_eq!(scheduler.next_run(quarter), Some(at(1, 30, 0)));
+    assert_eq!(scheduler.next_run(hourly), Some(at(2, 0, 0)));
+  }
+
+  #[test]
+  fn test_pause_resume_remove() {
+    let (clock, scheduler) = manual(at(0, 0, 0));
+    let (id, runs) = counter(&scheduler, "*/15 * * * *");
+    assert!(scheduler.pause(id));
+    assert_eq!(scheduler.is_paused(id), Some(true));
+    assert_eq!(scheduler.next_run(id), None);
+    clock.set(at(0, 15, 0));
+    assert_eq!(scheduler.tick(), 0);
+
+    clock.set(at(0, 20, 0));
+    assert!(scheduler.resume(id));
+    assert_eq!(scheduler.next_run(id), Some(at(0, 30, 0)));
+    assert_eq!(scheduler.tick(), 0);
+    clock.set(at(0, 30, 0));
+    assert_eq!(scheduler.tick(), 1);
+    received(&runs, 1);
+
+    assert!(scheduler.remove(id));
+    assert!(!scheduler.remove(id));
+    assert!(!scheduler.pause(id));
+    assert!(scheduler.is_empty());
+    clock.set(at(0, 45, 0));
+    assert_eq!(scheduler.tick(), 0);
+  }
+
+  #[test]
+  fn test_overlapping_runs() {
+    let (clock, scheduler) = manual(at(0, 0, 0));
+    let (release, released) = mpsc::channel::<()>();
+    let released = Mutex::new(released);
+    let id = scheduler.add(CronSchedule::new("* * * * *").unwrap(), move || {
+      released.lock().unwrap().recv().unwrap();
+    });
+    clock.set(at(0, 1, 0));
+    assert_eq!(scheduler.tick(), 1);
+    assert_eq!(scheduler.is_running(id), Some(true));
+
+    // The previous run is still in progress
+    clock.set(at(0, 2, 0));
+    assert_eq!(scheduler.tick(), 0);
+    assert_eq!(scheduler.next_run(id), Some(at(0, 3, 0)));
+
+    release.send(()).unwrap();
+    wait_idle(&scheduler, id);
+    clock.set(at(0, 3, 0));
+    assert_eq!(scheduler.tick(), 1);
+    release.send(()).unwrap();
+  }
+
+  #[test]
+  fn test_rate() {
+    let (clock, scheduler) = manual(at(0, 0, 30));
+    let schedule = CronSchedule::with_dialect(Dialect::Aws, "rate(90 minutes)").unwrap();
+    let id = scheduler.add(schedule, || {});
+    assert_eq!(scheduler.next_run(id), Some(at(1, 30, 30)));
+    clock.set(at(4, 0, 0));
+    assert_eq!(scheduler.tick(), 1);
+    assert_eq!(scheduler.next_run(id), Some(at(4, 30, 30)));
+  }
+
+  #[test]
+  fn test_no_more_occurrences() {
+    let (clock, scheduler) = manual(at(0, 0, 0));
+    let past = CronSchedule::with_dialect(Dialect::Quartz, "0 0 0 1 1 ? 2020").unwrap();
+    let past = scheduler.add(past, || {});
+    let never = scheduler.add(CronSchedule::new("0 0 30 2 *").unwrap(), || {});
+    assert_eq!(scheduler.next_run(past), None);
+    assert_eq!(scheduler.next_run(never), None);
+    clock.set(at(12, 0, 0));
+    assert_eq!(scheduler.tick(), 0);
+  }
+
+  #[test]
+  fn test_start() {
+    let (clock, scheduler) = manual(at(0, 0, 0));
+    let (_, runs) = counter(&scheduler, "*/15 * * * *");
+    let handle = scheduler.start(std::time::Duration::from_millis(5));
+    clock.set(at(0, 15, 0));
+    runs.recv_timeout(std::time::Duration::from_secs(5)).unwrap();
+    handle.stop();
+    clock.set(at(0, 30, 0));
+    assert!(runs.recv_timeout(std::time::Duration::from_millis(50)).is_err());
+  }
+
+  #[cfg(feature = "tokio")]
+  #[test]
+  fn test_spawn() {
+    let (clock, scheduler) = manual(at(0, 0, 0));
+    let (_, runs) = counter(&scheduler, "*/15 * * * *");
+    clock.set(at(0, 15, 0));
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .enable_time()
+      .build()
+      .unwrap();
+    runtime.block_on(async {
+      let task = scheduler.spawn(std::time::Duration::from_millis(5));
+      tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+      task.abort();
+    });
+    received(&runs, 1);
+  }
+}